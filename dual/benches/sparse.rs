@@ -0,0 +1,86 @@
+//! Gradient of a banded cost with `Dual<f64, N>` against `SparseDual<f64>`.
+//!
+//! Every term of the cost couples at most three neighbouring variables, so the dense type
+//! does `O(N)` work per operation where the sparse one does `O(1)`, except for the final
+//! accumulation. Run with `cargo bench -p dual`; the crossover is printed as the ratio of
+//! the timings, and lies between `N = 64` and `N = 128`.
+
+use dual::{SparseDual, Variables};
+use num_traits::Float;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// `sum (x_{i+1} - x_i^2)^2 + sin(x_i x_{i+2}) / 10`.
+fn cost<T: Float>(x: &[T]) -> T {
+    let tenth = T::from(0.1).unwrap();
+    let mut sum = T::zero();
+    for i in 0..x.len() - 2 {
+        let a = x[i + 1] - x[i] * x[i];
+        sum = sum + a * a + (x[i] * x[i + 2]).sin() * tenth;
+    }
+    sum
+}
+
+/// `cost` spelled out for `SparseDual`, which is not `Float`; the same has to be done to
+/// implement `easyopt::minimize::SparseCost`.
+fn sparse_cost(x: &[SparseDual<f64>]) -> SparseDual<f64> {
+    let mut sum = SparseDual::constant(0.);
+    for i in 0..x.len() - 2 {
+        let a = &x[i + 1] - &x[i] * &x[i];
+        sum = sum + &a * &a + (&x[i] * &x[i + 2]).sin() * 0.1;
+    }
+    sum
+}
+
+/// Mean time of `f` over enough repetitions to fill about 0.2 s.
+fn time<R>(mut f: impl FnMut() -> R) -> Duration {
+    let mut reps = 1u32;
+    loop {
+        let start = Instant::now();
+        for _ in 0..reps {
+            black_box(f());
+        }
+        let elapsed = start.elapsed();
+        if elapsed > Duration::from_millis(200) {
+            return elapsed / reps;
+        }
+        reps *= 2;
+    }
+}
+
+fn compare<const N: usize>() {
+    let values: Vec<f64> = (0..N).map(|i| 0.5 + 0.01 * i as f64).collect();
+
+    let dense_x = Variables::<f64, N>::new().gen_all(&values);
+    let sparse_x = SparseDual::variables(&values);
+    let dense = cost(&dense_x);
+    let sparse = sparse_cost(&sparse_x);
+    for (a, b) in dense.grad().iter().zip(sparse.dense_grad(N).iter()) {
+        assert!((a - b).abs() <= 1e-12 * a.abs().max(1.));
+    }
+
+    let t_dense = time(|| cost(black_box(&dense_x)));
+    let t_sparse = time(|| sparse_cost(black_box(&sparse_x)));
+    println!(
+        "{:>6} {:>14.3?} {:>14.3?} {:>8.2}",
+        N,
+        t_dense,
+        t_sparse,
+        t_dense.as_secs_f64() / t_sparse.as_secs_f64()
+    );
+}
+
+fn main() {
+    println!(
+        "{:>6} {:>14} {:>14} {:>8}",
+        "N", "Dual", "SparseDual", "ratio"
+    );
+    compare::<4>();
+    compare::<8>();
+    compare::<16>();
+    compare::<32>();
+    compare::<64>();
+    compare::<128>();
+    compare::<256>();
+    compare::<512>();
+}
@@ -0,0 +1,226 @@
+use super::Dual;
+use num_complex::Complex;
+use num_traits::{Float, Zero};
+
+/// Complex-valued dual numbers, for complex quantities differentiated with respect to real
+/// parameters.
+///
+/// The arithmetic operators work for `Dual<Complex<T>, N>` as for any field; the functions
+/// below complete them with the holomorphic elementary functions. Since the directions of
+/// the derivatives are real, the non-holomorphic parts `re`, `im`, `conj` and `norm_sqr`
+/// are differentiable as well. Seeding an imaginary direction instead differentiates a
+/// holomorphic function with respect to its complex argument, for which those four do not
+/// apply.
+impl<T, const N: usize> Dual<Complex<T>, N>
+where
+    T: Float,
+{
+    /// The complex number `re + i im`.
+    pub fn from_parts(re: Dual<T, N>, im: Dual<T, N>) -> Self {
+        let mut dx = [Complex::zero(); N];
+        for (dst, (&a, &b)) in dx.iter_mut().zip(re.dx.iter().zip(im.dx.iter())) {
+            *dst = Complex::new(a, b);
+        }
+        Self {
+            x: Complex::new(re.x, im.x),
+            dx,
+        }
+    }
+
+    pub fn re(&self) -> Dual<T, N> {
+        let mut dx = [T::zero(); N];
+        for (dst, src) in dx.iter_mut().zip(self.dx.iter()) {
+            *dst = src.re;
+        }
+        Dual { x: self.x.re, dx }
+    }
+
+    pub fn im(&self) -> Dual<T, N> {
+        let mut dx = [T::zero(); N];
+        for (dst, src) in dx.iter_mut().zip(self.dx.iter()) {
+            *dst = src.im;
+        }
+        Dual { x: self.x.im, dx }
+    }
+
+    pub fn conj(mut self) -> Self {
+        for dst in self.dx.iter_mut() {
+            *dst = dst.conj();
+        }
+        self.x = self.x.conj();
+        self
+    }
+
+    /// `|z|^2` as a real dual number.
+    pub fn norm_sqr(&self) -> Dual<T, N> {
+        let (re, im) = (self.re(), self.im());
+        re * re + im * im
+    }
+
+    /// `|z|` as a real dual number.
+    pub fn norm(&self) -> Dual<T, N> {
+        self.norm_sqr().sqrt()
+    }
+
+    pub fn recip(self) -> Self {
+        let r = self.x.inv();
+        self.lift(r, -r * r)
+    }
+
+    pub fn exp(self) -> Self {
+        let e = self.x.exp();
+        self.lift(e, e)
+    }
+
+    pub fn ln(self) -> Self {
+        let x = self.x;
+        self.lift(x.ln(), x.inv())
+    }
+
+    pub fn sqrt(self) -> Self {
+        let s = self.x.sqrt();
+        self.lift(s, (s + s).inv())
+    }
+
+    pub fn powi(self, n: i32) -> Self {
+        let x = self.x;
+        let dfx = if n == 0 {
+            Complex::zero()
+        } else {
+            x.powi(n - 1) * T::from(n).unwrap()
+        };
+        self.lift(x.powi(n), dfx)
+    }
+
+    /// `z^p` for a constant complex exponent on the principal branch.
+    pub fn powc(self, p: Complex<T>) -> Self {
+        let x = self.x;
+        let dfx = if p.is_zero() {
+            Complex::zero()
+        } else {
+            p * x.powc(p - Complex::new(T::one(), T::zero()))
+        };
+        self.lift(x.powc(p), dfx)
+    }
+
+    pub fn sin(self) -> Self {
+        let x = self.x;
+        self.lift(x.sin(), x.cos())
+    }
+
+    pub fn cos(self) -> Self {
+        let x = self.x;
+        self.lift(x.cos(), -x.sin())
+    }
+
+    pub fn tan(self) -> Self {
+        let t = self.x.tan();
+        self.lift(t, t * t + T::one())
+    }
+
+    pub fn sinh(self) -> Self {
+        let x = self.x;
+        self.lift(x.sinh(), x.cosh())
+    }
+
+    pub fn cosh(self) -> Self {
+        let x = self.x;
+        self.lift(x.cosh(), x.sinh())
+    }
+
+    pub fn tanh(self) -> Self {
+        let t = self.x.tanh();
+        self.lift(t, -t * t + T::one())
+    }
+
+    pub fn atan(self) -> Self {
+        let x = self.x;
+        self.lift(x.atan(), (x * x + T::one()).inv())
+    }
+}
+
+impl<T, const N: usize> From<Dual<T, N>> for Dual<Complex<T>, N>
+where
+    T: Float,
+{
+    fn from(re: Dual<T, N>) -> Self {
+        let mut dx = [Complex::zero(); N];
+        for (dst, &src) in dx.iter_mut().zip(re.dx.iter()) {
+            *dst = Complex::new(src, T::zero());
+        }
+        Self {
+            x: Complex::new(re.x, T::zero()),
+            dx,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use num_complex::Complex64;
+
+    fn close(a: Complex64, b: Complex64) -> bool {
+        (a - b).norm() < 1e-12 * b.norm().max(1.)
+    }
+
+    #[test]
+    fn green_function() {
+        // G(w) = 1 / (w + i eta - e - g^2 G0) with real parameters (e, g)
+        let mut vars = Variables::<f64, 2>::new();
+        let p = vars.gen_all(&[0.3, 0.8]);
+        let (w, eta) = (0.5, 0.05);
+        let g0 = Complex64::new(0.1, -0.4);
+
+        let e = Dual::<Complex64, 2>::from(p[0]);
+        let g = Dual::<Complex64, 2>::from(p[1]);
+        let denominator = -(e + g * g * g0) + Complex64::new(w, eta);
+        let green = denominator.recip();
+
+        let value = (Complex64::new(w, eta) - 0.3 - 0.64 * g0).inv();
+        assert!(close(*green.val(), value));
+        // dG/de = G^2, dG/dg = 2 g G0 G^2
+        assert!(close(green.grad()[0], value * value));
+        assert!(close(green.grad()[1], value * value * g0 * 1.6));
+
+        // spectral function -Im G / pi through the real part of the derivative
+        let spectral = green.im() * (-1. / std::f64::consts::PI);
+        assert!((spectral.grad()[0] + (value * value).im / std::f64::consts::PI).abs() < 1e-12);
+        let n2 = green.norm_sqr();
+        assert!((n2.grad()[0] - 2. * (value.conj() * value * value).re).abs() < 1e-12);
+    }
+
+    #[test]
+    fn holomorphic() {
+        let z0 = Complex64::new(0.4, -0.7);
+        let one = Complex64::new(1., 0.);
+        let z = Variables::<Complex64, 1>::new().gen(z0).unwrap();
+        for (f, value, derivative) in [
+            (z.exp(), z0.exp(), z0.exp()),
+            (z.ln(), z0.ln(), z0.inv()),
+            (z.sqrt(), z0.sqrt(), 0.5 / z0.sqrt()),
+            (z.sin(), z0.sin(), z0.cos()),
+            (z.cosh(), z0.cosh(), z0.sinh()),
+            (z.tan(), z0.tan(), 1. / (z0.cos() * z0.cos())),
+            (z.tanh(), z0.tanh(), 1. / (z0.cosh() * z0.cosh())),
+            (z.atan(), z0.atan(), 1. / (1. + z0 * z0)),
+            (z.powi(3), z0.powi(3), 3. * z0 * z0),
+            (
+                z.powc(Complex64::new(0.5, 1.)),
+                z0.powc(Complex64::new(0.5, 1.)),
+                Complex64::new(0.5, 1.) * z0.powc(Complex64::new(-0.5, 1.)),
+            ),
+            (
+                z / (z * z + one),
+                z0 / (z0 * z0 + 1.),
+                (1. - z0 * z0) / (z0 * z0 + 1.).powi(2),
+            ),
+        ] {
+            assert!(close(*f.val(), value));
+            assert!(close(f.grad()[0], derivative));
+        }
+        let parts = Dual::from_parts(z.re(), z.im());
+        assert_eq!(z0, *parts.val());
+        assert_eq!(Complex64::new(1., 0.), parts.grad()[0]);
+    }
+}
@@ -1,18 +1,63 @@
 use super::Dual;
-/*
-use num_traits::{Float, Num};
+use num_traits::{Float, Num, NumCast, ToPrimitive};
+use std::num::FpCategory;
+
+impl<T, const N: usize> Dual<T, N>
+where
+    T: Float,
+{
+    fn constant(x: T) -> Self {
+        Self {
+            x,
+            dx: [T::zero(); N],
+        }
+    }
+
+    /// Applies the chain rule for a binary function with partial derivatives `dfx` and `dfy`.
+    #[inline]
+    fn chain2(mut self, other: &Self, fxy: T, dfx: T, dfy: T) -> Self {
+        for (dst, &src) in self.dx.iter_mut().zip(other.dx.iter()) {
+            *dst = *dst * dfx + src * dfy;
+        }
+        self.x = fxy;
+        self
+    }
+}
+
+impl<T, const N: usize> ToPrimitive for Dual<T, N>
+where
+    T: ToPrimitive,
+{
+    fn to_i64(&self) -> Option<i64> {
+        self.x.to_i64()
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        self.x.to_u64()
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        self.x.to_f64()
+    }
+}
+
+impl<T, const N: usize> NumCast for Dual<T, N>
+where
+    T: Float,
+{
+    fn from<P: ToPrimitive>(n: P) -> Option<Self> {
+        <T as NumCast>::from(n).map(Self::constant)
+    }
+}
 
 impl<T, const N: usize> Num for Dual<T, N>
 where
-    T: Num + Copy,
+    T: Float,
 {
     type FromStrRadixErr = T::FromStrRadixErr;
 
     fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
-        Ok(Self {
-            x: T::from_str_radix(s, radix)?,
-            dx: [T::zero(); N],
-        })
+        T::from_str_radix(s, radix).map(Self::constant)
     }
 }
 
@@ -21,10 +66,372 @@ where
     T: Float,
 {
     fn nan() -> Self {
-        Self {
-            x: T::nan(),
-            dx: [T::zero(); N],
+        Self::constant(T::nan())
+    }
+
+    fn infinity() -> Self {
+        Self::constant(T::infinity())
+    }
+
+    fn neg_infinity() -> Self {
+        Self::constant(T::neg_infinity())
+    }
+
+    fn neg_zero() -> Self {
+        Self::constant(T::neg_zero())
+    }
+
+    fn min_value() -> Self {
+        Self::constant(T::min_value())
+    }
+
+    fn min_positive_value() -> Self {
+        Self::constant(T::min_positive_value())
+    }
+
+    fn epsilon() -> Self {
+        Self::constant(T::epsilon())
+    }
+
+    fn max_value() -> Self {
+        Self::constant(T::max_value())
+    }
+
+    fn is_nan(self) -> bool {
+        self.x.is_nan()
+    }
+
+    fn is_infinite(self) -> bool {
+        self.x.is_infinite()
+    }
+
+    fn is_finite(self) -> bool {
+        self.x.is_finite()
+    }
+
+    fn is_normal(self) -> bool {
+        self.x.is_normal()
+    }
+
+    fn classify(self) -> FpCategory {
+        self.x.classify()
+    }
+
+    fn floor(self) -> Self {
+        Self::constant(self.x.floor())
+    }
+
+    fn ceil(self) -> Self {
+        Self::constant(self.x.ceil())
+    }
+
+    fn round(self) -> Self {
+        Self::constant(self.x.round())
+    }
+
+    fn trunc(self) -> Self {
+        Self::constant(self.x.trunc())
+    }
+
+    fn fract(self) -> Self {
+        let x = self.x;
+        self.lift(x.fract(), T::one())
+    }
+
+    fn abs(self) -> Self {
+        let x = self.x;
+        if x.is_sign_negative() {
+            self.lift(x.abs(), -T::one())
+        } else {
+            self
         }
     }
+
+    fn signum(self) -> Self {
+        Self::constant(self.x.signum())
+    }
+
+    fn is_sign_positive(self) -> bool {
+        self.x.is_sign_positive()
+    }
+
+    fn is_sign_negative(self) -> bool {
+        self.x.is_sign_negative()
+    }
+
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        self * a + b
+    }
+
+    fn recip(self) -> Self {
+        let x = self.x;
+        let r = x.recip();
+        self.lift(r, -r * r)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        let x = self.x;
+        let dfx = if n == 0 {
+            T::zero()
+        } else {
+            x.powi(n - 1) * T::from(n).unwrap()
+        };
+        self.lift(x.powi(n), dfx)
+    }
+
+    fn powf(self, n: Self) -> Self {
+        let (x, y) = (self.x, n.x);
+        let fxy = x.powf(y);
+        // The shortcuts keep 0 * inf and the logarithm of a non-positive base out of constant
+        // exponents, and are only taken where the factor is not finite: a nested exponent
+        // that vanishes by value may still carry inner derivatives.
+        let pow = x.powf(y - T::one());
+        let dfx = if y.is_zero() && !pow.is_finite() {
+            T::zero()
+        } else {
+            y * pow
+        };
+        // d(x^y)/dy = x^y ln(x), which only matters when the exponent carries a derivative.
+        let ln = x.ln();
+        let dfy = if n.dx.iter().all(|d| d.is_zero()) && !ln.is_finite() {
+            T::zero()
+        } else {
+            fxy * ln
+        };
+        self.chain2(&n, fxy, dfx, dfy)
+    }
+
+    fn sqrt(self) -> Self {
+        let x = self.x;
+        let s = x.sqrt();
+        self.lift(s, (s + s).recip())
+    }
+
+    fn exp(self) -> Self {
+        let x = self.x;
+        let e = x.exp();
+        self.lift(e, e)
+    }
+
+    fn exp2(self) -> Self {
+        let x = self.x;
+        let e = x.exp2();
+        self.lift(e, e * T::from(std::f64::consts::LN_2).unwrap())
+    }
+
+    fn ln(self) -> Self {
+        let x = self.x;
+        self.lift(x.ln(), x.recip())
+    }
+
+    fn log(self, base: Self) -> Self {
+        self.ln() * base.ln().recip()
+    }
+
+    fn log2(self) -> Self {
+        let x = self.x;
+        self.lift(
+            x.log2(),
+            (x * T::from(std::f64::consts::LN_2).unwrap()).recip(),
+        )
+    }
+
+    fn log10(self) -> Self {
+        let x = self.x;
+        self.lift(
+            x.log10(),
+            (x * T::from(std::f64::consts::LN_10).unwrap()).recip(),
+        )
+    }
+
+    fn max(self, other: Self) -> Self {
+        if self.x >= other.x || other.x.is_nan() {
+            self
+        } else {
+            other
+        }
+    }
+
+    fn min(self, other: Self) -> Self {
+        if self.x <= other.x || other.x.is_nan() {
+            self
+        } else {
+            other
+        }
+    }
+
+    fn abs_sub(self, other: Self) -> Self {
+        if self.x <= other.x {
+            Self::constant(T::zero())
+        } else {
+            self - other
+        }
+    }
+
+    fn cbrt(self) -> Self {
+        let x = self.x;
+        let c = x.cbrt();
+        self.lift(c, (c * c * T::from(3.).unwrap()).recip())
+    }
+
+    fn hypot(self, other: Self) -> Self {
+        let (x, y) = (self.x, other.x);
+        let h = x.hypot(y);
+        self.chain2(&other, h, x / h, y / h)
+    }
+
+    fn sin(self) -> Self {
+        let x = self.x;
+        self.lift(x.sin(), x.cos())
+    }
+
+    fn cos(self) -> Self {
+        let x = self.x;
+        self.lift(x.cos(), -x.sin())
+    }
+
+    fn tan(self) -> Self {
+        let x = self.x;
+        let t = x.tan();
+        self.lift(t, T::one() + t * t)
+    }
+
+    fn asin(self) -> Self {
+        let x = self.x;
+        self.lift(x.asin(), (T::one() - x * x).sqrt().recip())
+    }
+
+    fn acos(self) -> Self {
+        let x = self.x;
+        self.lift(x.acos(), -(T::one() - x * x).sqrt().recip())
+    }
+
+    fn atan(self) -> Self {
+        let x = self.x;
+        self.lift(x.atan(), (T::one() + x * x).recip())
+    }
+
+    fn atan2(self, other: Self) -> Self {
+        let (y, x) = (self.x, other.x);
+        let r2 = x * x + y * y;
+        self.chain2(&other, y.atan2(x), x / r2, -y / r2)
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        (self.sin(), self.cos())
+    }
+
+    fn exp_m1(self) -> Self {
+        let x = self.x;
+        self.lift(x.exp_m1(), x.exp())
+    }
+
+    fn ln_1p(self) -> Self {
+        let x = self.x;
+        self.lift(x.ln_1p(), (T::one() + x).recip())
+    }
+
+    fn sinh(self) -> Self {
+        let x = self.x;
+        self.lift(x.sinh(), x.cosh())
+    }
+
+    fn cosh(self) -> Self {
+        let x = self.x;
+        self.lift(x.cosh(), x.sinh())
+    }
+
+    fn tanh(self) -> Self {
+        let x = self.x;
+        let t = x.tanh();
+        self.lift(t, T::one() - t * t)
+    }
+
+    fn asinh(self) -> Self {
+        let x = self.x;
+        self.lift(x.asinh(), (x * x + T::one()).sqrt().recip())
+    }
+
+    fn acosh(self) -> Self {
+        let x = self.x;
+        self.lift(x.acosh(), (x * x - T::one()).sqrt().recip())
+    }
+
+    fn atanh(self) -> Self {
+        let x = self.x;
+        self.lift(x.atanh(), (T::one() - x * x).recip())
+    }
+
+    fn integer_decode(self) -> (u64, i16, i8) {
+        self.x.integer_decode()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use num_traits::{Float, One};
+
+    fn rosenbrock<T: Float>(x: &[T]) -> T {
+        let a = T::one();
+        let b = T::from(100.).unwrap();
+        (a - x[0]).powi(2) + b * (x[1] - x[0] * x[0]).powi(2)
+    }
+
+    #[test]
+    fn powf_zero_exponent() {
+        // Hessian of x^y at (2, 0): [[0, 1 / 2], [1 / 2, ln(2)^2]]
+        let inner = Variables::<f64, 2>::new().gen_all(&[2., 0.]);
+        let v = Variables::<Dual<f64, 2>, 2>::new().gen_all(&inner);
+        let y = v[0].powf(v[1]);
+        let h = |i: usize, j: usize| y.grad()[i].grad()[j];
+        assert_eq!(1., *y.val().val());
+        assert_eq!(0., h(0, 0));
+        assert!((h(0, 1) - 0.5).abs() < 1e-15);
+        assert!((h(1, 0) - 0.5).abs() < 1e-15);
+        assert!((h(1, 1) - 2f64.ln().powi(2)).abs() < 1e-15);
+
+        // a constant exponent leaves negative bases alone
+        let x = Variables::<f64, 1>::new().gen(-2.).unwrap();
+        let c = Variables::<f64, 1>::new().constant(2.);
+        assert_eq!(-4., x.powf(c).grad()[0]);
+    }
+
+    #[test]
+    fn elementary_derivatives() {
+        let x = Variables::<f64, 1>::new().gen(0.3).unwrap();
+        let check = |y: Dual<f64, 1>, v: f64, d: f64| {
+            assert!((*y.val() - v).abs() < 1e-14);
+            assert!((y.grad()[0] - d).abs() < 1e-14);
+        };
+        check(x.exp(), 0.3f64.exp(), 0.3f64.exp());
+        check(x.ln(), 0.3f64.ln(), 1. / 0.3);
+        check(x.sin(), 0.3f64.sin(), 0.3f64.cos());
+        check(x.sqrt(), 0.3f64.sqrt(), 0.5 / 0.3f64.sqrt());
+        check(x.powi(3), 0.027, 3. * 0.09);
+        check(
+            x.powf(x),
+            0.3f64.powf(0.3),
+            0.3f64.powf(0.3) * (0.3f64.ln() + 1.),
+        );
+        check(x.atan2(Dual::one()), 0.3f64.atan(), 1. / 1.09);
+        check(x.recip(), 1. / 0.3, -1. / 0.09);
+    }
+
+    #[test]
+    fn nested_hessian() {
+        let x = Variables::<f64, 2>::new().gen_all(&[-1.2, 1.]);
+        let x = Variables::<Dual<f64, 2>, 2>::new().gen_all(&x);
+        let y = rosenbrock(&x);
+        assert_eq!(*y.val().val(), rosenbrock(&[-1.2, 1.]));
+        // gradient from the inner and the outer derivative agree
+        assert_eq!(y.val().grad()[0], *y.grad()[0].val());
+        assert_eq!(y.val().grad()[1], *y.grad()[1].val());
+        // [[1200 x0^2 - 400 x1 + 2, -400 x0], [-400 x0, 200]]
+        let h00 = 1200. * 1.44 - 400. + 2.;
+        assert!((y.grad()[0].grad()[0] - h00).abs() < 1e-10);
+        assert!((y.grad()[0].grad()[1] - 480.).abs() < 1e-10);
+        assert!((y.grad()[1].grad()[0] - 480.).abs() < 1e-10);
+        assert!((y.grad()[1].grad()[1] - 200.).abs() < 1e-10);
+    }
 }
-*/
@@ -0,0 +1,57 @@
+use super::Dual;
+use std::fmt;
+
+/// Writes `value [d_0, d_1, ...]` with `write` applying the flags of `f` to every number, or
+/// only the value with the alternate flag `#`.
+fn write_dual<T, const N: usize>(
+    dual: &Dual<T, N>,
+    f: &mut fmt::Formatter<'_>,
+    write: fn(&T, &mut fmt::Formatter<'_>) -> fmt::Result,
+) -> fmt::Result {
+    write(&dual.x, f)?;
+    if f.alternate() {
+        return Ok(());
+    }
+    f.write_str(" [")?;
+    for (i, d) in dual.dx.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write(d, f)?;
+    }
+    f.write_str("]")
+}
+
+impl<T, const N: usize> fmt::Display for Dual<T, N>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_dual(self, f, fmt::Display::fmt)
+    }
+}
+
+impl<T, const N: usize> fmt::LowerExp for Dual<T, N>
+where
+    T: fmt::LowerExp,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_dual(self, f, fmt::LowerExp::fmt)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn flags() {
+        let x = Variables::<f64, 2>::new().gen_all(&[1.5, -0.25]);
+        let y = x[0] * x[1];
+        assert_eq!("-0.375 [-0.25, 1.5]", format!("{}", y));
+        assert_eq!("-0.38 [-0.25, 1.50]", format!("{:.2}", y));
+        assert_eq!("-0.375", format!("{:#}", y));
+        assert_eq!("-3.8e-1 [-2.5e-1, 1.5e0]", format!("{:.1e}", y));
+        assert_eq!("+1.5 [+1, +0]", format!("{:+}", x[0]));
+    }
+}
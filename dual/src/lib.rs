@@ -1,12 +1,23 @@
 use ndarray::prelude::*;
+use ndarray::ScalarOperand;
 use num_traits::{One, Zero};
 use std::marker::PhantomData;
+use std::ops::{Add, Mul};
 
 pub struct Variables<T, const MAX_VAR: usize> {
     num_var: usize,
     _type: PhantomData<T>,
 }
 
+impl<T, const MAX_VAR: usize> Default for Variables<T, MAX_VAR>
+where
+    T: One + Zero + Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T, const MAX_VAR: usize> Variables<T, MAX_VAR>
 where
     T: One + Zero + Copy,
@@ -21,7 +32,7 @@ where
     fn var(i: usize, v: T) -> Dual<T, MAX_VAR> {
         let mut dx = [T::zero(); MAX_VAR];
         dx[i] = T::one();
-        Dual::<T, MAX_VAR> { x: v, dx: dx }
+        Dual::<T, MAX_VAR> { x: v, dx }
     }
 
     pub fn gen(&mut self, init_value: T) -> Option<Dual<T, MAX_VAR>> {
@@ -39,7 +50,7 @@ where
             init_values
                 .iter()
                 .zip(self.num_var..MAX_VAR)
-                .map(|(v, i)| Self::var(i, v.clone()))
+                .map(|(v, i)| Self::var(i, *v))
                 .collect()
         } else {
             panic!();
@@ -52,9 +63,23 @@ where
             dx: [T::zero(); MAX_VAR],
         }
     }
+
+    /// All of `values`, with the `MAX_VAR` components starting at `offset` as the variables
+    /// and the others as constants, to differentiate in chunks with respect to more variables
+    /// than `MAX_VAR`. The chunk is cut short at the end of `values`.
+    pub fn chunk(&self, values: &[T], offset: usize) -> Vec<Dual<T, MAX_VAR>> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| match i.checked_sub(offset) {
+                Some(k) if k < MAX_VAR => Self::var(k, v),
+                _ => self.constant(v),
+            })
+            .collect()
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 pub struct Dual<T, const N: usize> {
     x: T,
     dx: [T; N],
@@ -65,15 +90,68 @@ impl<T, const N: usize> Dual<T, N> {
         &self.x
     }
 
-    pub fn grad(&self) -> ArrayView1<T> {
+    pub fn grad(&self) -> ArrayView1<'_, T> {
         ArrayView1::<T>::from(&self.dx)
     }
 
-    pub fn grad_mut(&mut self) -> ArrayViewMut1<T> {
+    pub fn grad_mut(&mut self) -> ArrayViewMut1<'_, T> {
         ArrayViewMut1::<T>::from(&mut self.dx)
     }
 }
 
+/// Custom derivative rules, so that functions evaluated outside of `Dual` arithmetic, such as
+/// black-box special functions or table lookups, still propagate derivatives.
+impl<T, const N: usize> Dual<T, N>
+where
+    T: Zero + Add<Output = T> + Mul<Output = T> + Copy,
+{
+    /// Result of a unary function `f` at `self`, given `value = f(x)` and
+    /// `derivative = f'(x)`.
+    pub fn lift(mut self, value: T, derivative: T) -> Self {
+        for dst in self.dx.iter_mut() {
+            *dst = *dst * derivative;
+        }
+        self.x = value;
+        self
+    }
+
+    /// Applies `f` with the derivative `df`, both evaluated at the value of `self`.
+    pub fn apply_unary<F, D>(self, f: F, df: D) -> Self
+    where
+        F: FnOnce(T) -> T,
+        D: FnOnce(T) -> T,
+    {
+        let x = self.x;
+        self.lift(f(x), df(x))
+    }
+
+    /// Result of a function of several inputs, given its value and its gradient with respect
+    /// to each input.
+    ///
+    /// # Panics
+    /// If `inputs` and `gradient` differ in length.
+    pub fn lift_multi(inputs: &[Self], value: T, gradient: &[T]) -> Self {
+        assert_eq!(inputs.len(), gradient.len());
+        let mut dx = [T::zero(); N];
+        for (input, &g) in inputs.iter().zip(gradient.iter()) {
+            for (dst, &src) in dx.iter_mut().zip(input.dx.iter()) {
+                *dst = *dst + src * g;
+            }
+        }
+        Self { x: value, dx }
+    }
+
+    /// Applies `f` with the gradient `grad`, both evaluated at the values of `inputs`.
+    pub fn apply_multi<F, G>(inputs: &[Self], f: F, grad: G) -> Self
+    where
+        F: FnOnce(&[T]) -> T,
+        G: FnOnce(&[T]) -> Vec<T>,
+    {
+        let values: Vec<T> = inputs.iter().map(|d| d.x).collect();
+        Self::lift_multi(inputs, f(&values), &grad(&values))
+    }
+}
+
 impl<T, const N: usize> Default for Dual<T, N>
 where
     T: Zero + Default + Copy,
@@ -86,6 +164,10 @@ where
     }
 }
 
+/// Dual numbers as scalars of `ndarray` arithmetic, as in `&a * s` for an array `a`. Together
+/// with the arithmetic operators, this makes `Dual<T, N>` a `LinalgScalar` for `dot`.
+impl<T, const N: usize> ScalarOperand for Dual<T, N> where T: 'static + Clone {}
+
 /*
 impl<T, const N: usize> From<Dual<T, N>> for T
 {
@@ -197,9 +279,25 @@ where
     }
 }
 
+pub mod complex;
 pub mod elementary;
+pub mod format;
+pub mod linalg;
+#[cfg(feature = "serde")]
+pub mod serialization;
+pub mod sparse;
+pub mod special;
+pub mod taylor;
+pub mod tracer;
+#[cfg(feature = "serde")]
+pub use serialization::value_only;
+pub use sparse::SparseDual;
+pub use special::Special;
+pub use taylor::Taylor;
 
 #[cfg(test)]
+// the operators on references are exercised on purpose
+#[allow(clippy::op_ref)]
 mod test {
     use super::*;
 
@@ -224,4 +322,112 @@ mod test {
         assert_eq!(array![11., 10., 3.].view(), loss.grad());
         assert_eq!(2. + (2. + 1.) * 10., *loss.val());
     }
+
+    #[test]
+    fn chunk() {
+        let vars = Variables::<f64, 2>::new();
+        let x = vars.chunk(&[1., 2., 3., 4., 5.], 3);
+        let loss = x[0] * x[3] + x[2] * x[4];
+        assert_eq!(4. + 15., *loss.val());
+        assert_eq!(array![1., 3.].view(), loss.grad());
+        let x = vars.chunk(&[1., 2., 3.], 2);
+        assert_eq!(array![1., 0.].view(), x[2].grad());
+    }
+
+    #[test]
+    fn quotient_rule() {
+        let mut vars = Variables::<f64, 2>::new();
+        let x = vars.gen_all(&[3., 2.]);
+
+        // d(x / y) = (y dx - x dy) / y^2
+        let q = x[0] / x[1];
+        assert_eq!(1.5, *q.val());
+        assert_eq!(array![0.5, -0.75].view(), q.grad());
+        let q = &x[0] / &x[1];
+        assert_eq!(array![0.5, -0.75].view(), q.grad());
+    }
+
+    #[test]
+    fn custom_rules() {
+        let mut vars = Variables::<f64, 2>::new();
+        let x = vars.gen_all(&[0.5, 2.]);
+
+        // piecewise linear table lookup with the slope of the segment as its derivative
+        let table = [(0., 1.), (1., 3.), (2., 4.)];
+        let lookup = |v: f64| {
+            let i = if v < 1. { 0 } else { 1 };
+            let ((x0, y0), (x1, y1)) = (table[i], table[i + 1]);
+            let slope = (y1 - y0) / (x1 - x0);
+            (y0 + slope * (v - x0), slope)
+        };
+        let (value, slope) = lookup(*x[0].val());
+        let y = (x[0] * 3.).lift(value, slope);
+        assert_eq!(2., *y.val());
+        assert_eq!(array![6., 0.].view(), y.grad());
+
+        let y = x[1].apply_unary(|v| v.powi(3), |v| 3. * v * v);
+        assert_eq!(8., *y.val());
+        assert_eq!(array![0., 12.].view(), y.grad());
+
+        // f(u, v) = u^2 v at (x0 + x1, x0 x1)
+        let inputs = [x[0] + x[1], x[0] * x[1]];
+        let z = Dual::apply_multi(
+            &inputs,
+            |w| w[0] * w[0] * w[1],
+            |w| vec![2. * w[0] * w[1], w[0] * w[0]],
+        );
+        let expected = inputs[0] * inputs[0] * inputs[1];
+        assert_eq!(*expected.val(), *z.val());
+        assert_eq!(expected.grad(), z.grad());
+
+        // the rules also hold at the outer level of nested duals
+        let inner = Variables::<f64, 1>::new().gen_all(&[0.3]);
+        let outer = Variables::<Dual<f64, 1>, 1>::new().gen_all(&inner);
+        let s = outer[0].apply_unary(num_traits::Float::sin, num_traits::Float::cos);
+        assert!((s.grad()[0].grad()[0] + 0.3f64.sin()).abs() < 1e-15);
+    }
+
+    #[test]
+    fn operator_surface() {
+        let mut vars = Variables::<f64, 2>::new();
+        let x = vars.gen_all(&[3., 2.]);
+
+        // constants on the left
+        assert_eq!(array![1., 0.].view(), (1. + x[0]).grad());
+        assert_eq!(array![-1., 0.].view(), (1. - &x[0]).grad());
+        assert_eq!(array![0., 2.].view(), (2. * x[1]).grad());
+        let q = 6. / x[1];
+        assert_eq!(3., *q.val());
+        assert_eq!(array![0., -1.5].view(), q.grad());
+        // 7 % y = 7 - 3 y at y = 2
+        let r = 7. % &x[1];
+        assert_eq!(1., *r.val());
+        assert_eq!(array![0., -3.].view(), r.grad());
+        let y = Variables::<f32, 1>::new().gen(2.).unwrap();
+        assert_eq!(-0.25f32, (1f32 / y).grad()[0]);
+
+        // 3 % 2 = 3 - 2 with the quotient 1 held constant
+        let r = &x[0] % &x[1];
+        assert_eq!(array![1., -1.].view(), r.grad());
+        assert_eq!(array![1., 0.].view(), (x[0] % 2.).grad());
+
+        let mut z = x[0];
+        z += x[1];
+        z *= &x[0];
+        z -= 1.;
+        z /= 2.;
+        assert_eq!(7., *z.val());
+        assert_eq!(array![4., 1.5].view(), z.grad());
+        z %= x[1];
+        assert_eq!(1., *z.val());
+
+        let sum: Dual<f64, 2> = x.iter().sum();
+        assert_eq!(sum, x[0] + x[1]);
+        assert_eq!(array![1., 1.].view(), sum.grad());
+        let product: Dual<f64, 2> = x.iter().copied().product();
+        assert_eq!(array![2., 3.].view(), product.grad());
+        let empty: Dual<f64, 2> = std::iter::empty::<Dual<f64, 2>>().product();
+        assert_eq!(1., *empty.val());
+        assert_eq!(array![0., 0.].view(), empty.grad());
+    }
 }
@@ -0,0 +1,306 @@
+//! Small dense linear algebra generic over the scalar, so that derivatives propagate through
+//! solves, determinants and inverses of matrices of `Dual` numbers.
+//!
+//! With `Dual<T, N>: ndarray::LinalgScalar`, products such as `a.dot(&x)` work on arrays of
+//! dual numbers as they are.
+
+use ndarray::prelude::*;
+use num_traits::Float;
+
+/// LU decomposition `P A = L U` with partial pivoting, where `L` is unit lower triangular.
+///
+/// Pivots are chosen by the magnitude of the value, so for dual numbers the elimination
+/// follows the same steps as for the values alone. The factorization does not stop at a
+/// vanishing pivot; the matrix is then singular, which `solve` and `inverse` report. The
+/// derivatives of a singular matrix's determinant are generally not zero, but cannot be
+/// reached by dividing by the pivot, so `det` then uses `berkowitz_det` on a kept copy of `A`.
+#[derive(Debug, Clone)]
+pub struct Lu<T> {
+    /// `L` below the diagonal and `U` on and above.
+    factors: Array2<T>,
+    /// Row of `A` moved to each row of `P A`.
+    pivots: Vec<usize>,
+    odd: bool,
+    /// `A`, kept only if it is singular.
+    singular: Option<Array2<T>>,
+}
+
+impl<T> Lu<T>
+where
+    T: Float,
+{
+    /// # Panics
+    /// If `a` is not square.
+    pub fn new(a: &Array2<T>) -> Self {
+        assert!(a.is_square());
+        let n = a.nrows();
+        let mut factors = a.clone();
+        let mut pivots: Vec<usize> = (0..n).collect();
+        let mut odd = false;
+        let mut singular = None;
+        for k in 0..n {
+            let p = (k + 1..n).fold(k, |p, i| {
+                if factors[[i, k]].abs() > factors[[p, k]].abs() {
+                    i
+                } else {
+                    p
+                }
+            });
+            if p != k {
+                for j in 0..n {
+                    factors.swap([k, j], [p, j]);
+                }
+                pivots.swap(k, p);
+                odd = !odd;
+            }
+            let pivot = factors[[k, k]];
+            if pivot.is_zero() {
+                singular.get_or_insert_with(|| a.clone());
+                continue;
+            }
+            for i in k + 1..n {
+                let l = factors[[i, k]] / pivot;
+                factors[[i, k]] = l;
+                for j in k + 1..n {
+                    factors[[i, j]] = factors[[i, j]] - l * factors[[k, j]];
+                }
+            }
+        }
+        Self {
+            factors,
+            pivots,
+            odd,
+            singular,
+        }
+    }
+
+    pub fn is_singular(&self) -> bool {
+        self.factors.diag().iter().any(|u| u.is_zero())
+    }
+
+    pub fn det(&self) -> T {
+        if let Some(a) = &self.singular {
+            return berkowitz_det(a);
+        }
+        let det = self.factors.diag().iter().fold(T::one(), |acc, &u| acc * u);
+        if self.odd {
+            -det
+        } else {
+            det
+        }
+    }
+
+    /// Solves `A x = b`, or `None` if `A` is singular.
+    pub fn solve(&self, b: &Array1<T>) -> Option<Array1<T>> {
+        if self.is_singular() {
+            return None;
+        }
+        let f = &self.factors;
+        let n = f.nrows();
+        let mut x: Array1<T> = self.pivots.iter().map(|&i| b[i]).collect();
+        for i in 0..n {
+            for k in 0..i {
+                x[i] = x[i] - f[[i, k]] * x[k];
+            }
+        }
+        for i in (0..n).rev() {
+            for k in (i + 1)..n {
+                x[i] = x[i] - f[[i, k]] * x[k];
+            }
+            x[i] = x[i] / f[[i, i]];
+        }
+        Some(x)
+    }
+
+    /// `A^-1`, or `None` if `A` is singular.
+    pub fn inverse(&self) -> Option<Array2<T>> {
+        let n = self.factors.nrows();
+        let mut inverse = Array2::zeros((n, n));
+        for j in 0..n {
+            let e = Array1::from_shape_fn(n, |i| if i == j { T::one() } else { T::zero() });
+            inverse.column_mut(j).assign(&self.solve(&e)?);
+        }
+        Some(inverse)
+    }
+}
+
+/// Solves `a x = b`, or `None` if `a` is singular.
+pub fn solve<T: Float>(a: &Array2<T>, b: &Array1<T>) -> Option<Array1<T>> {
+    Lu::new(a).solve(b)
+}
+
+pub fn det<T: Float>(a: &Array2<T>) -> T {
+    Lu::new(a).det()
+}
+
+/// `a^-1`, or `None` if `a` is singular.
+pub fn inverse<T: Float>(a: &Array2<T>) -> Option<Array2<T>> {
+    Lu::new(a).inverse()
+}
+
+fn dot<T: Float>(a: ArrayView1<T>, b: ArrayView1<T>) -> T {
+    a.iter()
+        .zip(b.iter())
+        .fold(T::zero(), |acc, (&u, &v)| acc + u * v)
+}
+
+/// Determinant by Berkowitz's algorithm, which uses no division and so keeps every
+/// derivative of dual numbers exact whatever the rank of `a`, at `O(n^4)` operations.
+///
+/// Builds the characteristic polynomial `det(t I - A)` of the trailing principal submatrices
+/// from the bottom right up, each from the last by a lower triangular Toeplitz product.
+///
+/// # Panics
+/// If `a` is not square.
+pub fn berkowitz_det<T: Float>(a: &Array2<T>) -> T {
+    assert!(a.is_square());
+    let n = a.nrows();
+    // coefficients of the characteristic polynomial, leading one first
+    let mut poly = vec![T::one()];
+    for k in (0..n).rev() {
+        let r = a.slice(s![k, k + 1..]);
+        let sub = a.slice(s![k + 1.., k + 1..]);
+        let m = n - k - 1;
+        // first column of the Toeplitz matrix: 1, -a_kk, -R C, -R S C, ..., -R S^(m-1) C
+        let mut column = vec![T::one(), -a[[k, k]]];
+        let mut c = a.slice(s![k + 1.., k]).to_owned();
+        for _ in 0..m {
+            column.push(-dot(r, c.view()));
+            c = sub
+                .rows()
+                .into_iter()
+                .map(|row| dot(row, c.view()))
+                .collect();
+        }
+        poly = (0..m + 2)
+            .map(|i| (0..=i.min(m)).fold(T::zero(), |acc, j| acc + column[i - j] * poly[j]))
+            .collect();
+    }
+    let det = poly[n];
+    if n % 2 == 1 {
+        -det
+    } else {
+        det
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Dual, Variables};
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-12
+    }
+
+    /// The 3 x 3 matrix `[[2 + p, 1, 0], [1, 3, q], [p q, 1, 4]]` as a function of `p`, `q`.
+    fn matrix(p: f64, q: f64) -> Array2<Dual<f64, 2>> {
+        let x = Variables::<f64, 2>::new().gen_all(&[p, q]);
+        let (p, q) = (x[0], x[1]);
+        let c = |v: f64| Variables::<f64, 2>::new().constant(v);
+        array![
+            [p + 2., c(1.), c(0.)],
+            [c(1.), c(3.), q],
+            [p * q, c(1.), c(4.)]
+        ]
+    }
+
+    #[test]
+    fn jacobi_formula() {
+        let a = matrix(0.5, -1.);
+        let lu = Lu::new(&a);
+        let det = lu.det();
+        let inverse = lu.inverse().unwrap();
+        // d det(A) = det(A) tr(A^-1 dA)
+        for k in 0..2 {
+            let da = a.mapv(|v| v.grad()[k]);
+            let trace: f64 = (0..3)
+                .map(|i| {
+                    (0..3)
+                        .map(|j| inverse[[i, j]].val() * da[[j, i]])
+                        .sum::<f64>()
+                })
+                .sum();
+            assert!(close(det.val() * trace, det.grad()[k]));
+        }
+        // against the cofactor expansion
+        let expected = a[[0, 0]] * (a[[1, 1]] * a[[2, 2]] - a[[1, 2]] * a[[2, 1]])
+            - a[[0, 1]] * (a[[1, 0]] * a[[2, 2]] - a[[1, 2]] * a[[2, 0]]);
+        assert!(close(*expected.val(), *det.val()));
+        assert!((0..2).all(|k| close(expected.grad()[k], det.grad()[k])));
+    }
+
+    #[test]
+    fn solve_and_inverse() {
+        let a = matrix(0.5, -1.);
+        let b = array![1., -2., 0.5].mapv(|v| Variables::<f64, 2>::new().constant(v));
+        // the residual vanishes together with its derivatives
+        let x = solve(&a, &b).unwrap();
+        for r in (a.dot(&x) - &b).iter() {
+            assert!(close(0., *r.val()));
+            assert!(r.grad().iter().all(|&g| close(0., g)));
+        }
+        let identity = a.dot(&inverse(&a).unwrap());
+        for ((i, j), v) in identity.indexed_iter() {
+            assert!(close(if i == j { 1. } else { 0. }, *v.val()));
+            assert!(v.grad().iter().all(|&g| close(0., g)));
+        }
+
+        // scaling by a dual number, det(s A) = s^3 det(A)
+        let s = Variables::<f64, 2>::new().gen(2.).unwrap();
+        let scaled = det(&(&a * s));
+        let expected = det(&a) * s.powi(3);
+        assert!(close(*expected.val(), *scaled.val()));
+        assert!((0..2).all(|k| close(expected.grad()[k], scaled.grad()[k])));
+    }
+
+    #[test]
+    fn singular() {
+        // det [[1, 2], [2, 4 + p]] = p, with derivative 1 at the singular point p = 0
+        let p = Variables::<f64, 1>::new().gen(0.).unwrap();
+        let c = |v: f64| Variables::<f64, 1>::new().constant(v);
+        let a = array![[c(1.), c(2.)], [c(2.), p + 4.]];
+        let lu = Lu::new(&a);
+        assert!(lu.is_singular());
+        assert!(lu.solve(&array![c(1.), c(0.)]).is_none());
+        assert!(lu.inverse().is_none());
+        assert_eq!(0., *lu.det().val());
+        assert!(close(1., lu.det().grad()[0]));
+        assert_eq!(0., det(&array![[1., 2.], [2., 4.]]));
+    }
+
+    #[test]
+    fn singular_pivot_with_derivative() {
+        // det [[0, 1], [p, 1]] = -p, where the vanishing pivot has a derivative of its own
+        let p = Variables::<f64, 1>::new().gen(0.).unwrap();
+        let c = |v: f64| Variables::<f64, 1>::new().constant(v);
+        let d = det(&array![[c(0.), c(1.)], [p, c(1.)]]);
+        assert_eq!(0., *d.val());
+        assert!(close(-1., d.grad()[0]));
+
+        // a regular matrix gives the same as the LU determinant
+        let a = matrix(0.5, -1.);
+        let (lu, berkowitz) = (det(&a), berkowitz_det(&a));
+        assert!(close(*lu.val(), *berkowitz.val()));
+        assert!((0..2).all(|k| close(lu.grad()[k], berkowitz.grad()[k])));
+        let b = array![
+            [2., -1., 0., 3.],
+            [1., 4., -2., 0.],
+            [0., 1., 5., -1.],
+            [3., 0., 1., 2.]
+        ];
+        assert!(close(det(&b), berkowitz_det(&b)));
+
+        // second derivatives at a rank-one matrix: det [[p, q], [q, p]] = p^2 - q^2
+        let mut vars = Variables::<Dual<f64, 2>, 2>::new();
+        let inner = Variables::<f64, 2>::new().gen_all(&[1., 1.]);
+        let x = vars.gen_all(&inner);
+        let d = det(&array![[x[0], x[1]], [x[1], x[0]]]);
+        assert_eq!(0., *d.val().val());
+        assert!(close(2., *d.grad()[0].val()));
+        assert!(close(-2., *d.grad()[1].val()));
+        assert!(close(2., d.grad()[0].grad()[0]));
+        assert!(close(0., d.grad()[0].grad()[1]));
+        assert!(close(-2., d.grad()[1].grad()[1]));
+    }
+}
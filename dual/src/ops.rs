@@ -1,6 +1,9 @@
-use num_traits::{One, Zero};
+use num_traits::{Num, One, Zero};
 use std::cmp::Ordering;
-use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::iter::{Product, Sum};
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign,
+};
 
 use super::Dual;
 
@@ -11,10 +14,6 @@ where
     fn eq(&self, other: &Self) -> bool {
         self.x.eq(&other.x)
     }
-
-    fn ne(&self, other: &Self) -> bool {
-        self.x.ne(&other.x)
-    }
 }
 
 impl<T, const N: usize> PartialEq<T> for Dual<T, N>
@@ -22,11 +21,7 @@ where
     T: PartialEq,
 {
     fn eq(&self, other: &T) -> bool {
-        self.x.eq(&other)
-    }
-
-    fn ne(&self, other: &T) -> bool {
-        self.x.ne(&other)
+        self.x.eq(other)
     }
 }
 
@@ -44,7 +39,7 @@ where
     T: PartialOrd,
 {
     fn partial_cmp(&self, other: &T) -> Option<Ordering> {
-        self.x.partial_cmp(&other)
+        self.x.partial_cmp(other)
     }
 }
 
@@ -69,18 +64,18 @@ where
     type Output = Dual<T, N>;
     #[inline]
     fn add(self, rhs: Self) -> Self::Output {
-        self + &rhs
+        Add::add(self, &rhs)
     }
 }
 
-impl<'a, 'b, T, const N: usize> Add<&'a Dual<T, N>> for &'b Dual<T, N>
+impl<'a, T, const N: usize> Add<&'a Dual<T, N>> for &Dual<T, N>
 where
     T: Zero + Add + Copy,
 {
     type Output = Dual<T, N>;
     #[inline]
     fn add(self, rhs: &'a Dual<T, N>) -> Self::Output {
-        self.clone() + rhs
+        *self + rhs
     }
 }
 
@@ -113,7 +108,7 @@ where
     type Output = Dual<T, N>;
     #[inline]
     fn add(self, rhs: T) -> Self::Output {
-        self.clone() + rhs
+        *self + rhs
     }
 }
 
@@ -137,7 +132,7 @@ where
 {
     type Output = Dual<T, N>;
     fn neg(self) -> Self::Output {
-        -self.clone()
+        -*self
     }
 }
 
@@ -162,18 +157,18 @@ where
     type Output = Self;
     #[inline]
     fn sub(self, rhs: Self) -> Self::Output {
-        self - &rhs
+        Sub::sub(self, &rhs)
     }
 }
 
-impl<'a, 'b, T, const N: usize> Sub<&'a Dual<T, N>> for &'b Dual<T, N>
+impl<'a, T, const N: usize> Sub<&'a Dual<T, N>> for &Dual<T, N>
 where
     T: Zero + Sub<Output = T> + Copy,
 {
     type Output = Dual<T, N>;
     #[inline]
     fn sub(self, rhs: &'a Dual<T, N>) -> Self::Output {
-        self.clone() - rhs
+        *self - rhs
     }
 }
 
@@ -184,7 +179,7 @@ where
     type Output = Dual<T, N>;
     #[inline]
     fn sub(self, rhs: Dual<T, N>) -> Self::Output {
-        self.clone() - &rhs
+        Sub::sub(*self, &rhs)
     }
 }
 
@@ -206,7 +201,7 @@ where
     type Output = Dual<T, N>;
     #[inline]
     fn sub(self, rhs: T) -> Self::Output {
-        self.clone() - rhs
+        *self - rhs
     }
 }
 
@@ -231,18 +226,18 @@ where
     type Output = Dual<T, N>;
     #[inline]
     fn mul(self, rhs: Self) -> Self::Output {
-        self * &rhs
+        Mul::mul(self, &rhs)
     }
 }
 
-impl<'a, 'b, T, const N: usize> Mul<&'a Dual<T, N>> for &'b Dual<T, N>
+impl<'a, T, const N: usize> Mul<&'a Dual<T, N>> for &Dual<T, N>
 where
     T: Zero + One + Add + Mul + Copy,
 {
     type Output = Dual<T, N>;
     #[inline]
     fn mul(self, rhs: &'a Dual<T, N>) -> Self::Output {
-        self.clone() * rhs
+        *self * rhs
     }
 }
 
@@ -278,7 +273,7 @@ where
     type Output = Dual<T, N>;
     #[inline]
     fn mul(self, rhs: T) -> Self::Output {
-        self.clone() * rhs
+        *self * rhs
     }
 }
 
@@ -289,7 +284,7 @@ where
     type Output = Dual<T, N>;
     fn div(mut self, rhs: &Self) -> Self::Output {
         for (dst, src) in self.dx.iter_mut().zip(rhs.dx) {
-            *dst = *dst * rhs.x - src * self.x / (rhs.x * rhs.x);
+            *dst = (*dst * rhs.x - src * self.x) / (rhs.x * rhs.x);
         }
         self.x = self.x / rhs.x;
         self
@@ -303,18 +298,18 @@ where
     type Output = Dual<T, N>;
     #[inline]
     fn div(self, rhs: Self) -> Self::Output {
-        self / &rhs
+        Div::div(self, &rhs)
     }
 }
 
-impl<'a, 'b, T, const N: usize> Div<&'a Dual<T, N>> for &'b Dual<T, N>
+impl<'a, T, const N: usize> Div<&'a Dual<T, N>> for &Dual<T, N>
 where
     T: Zero + One + Add + Sub<Output = T> + Mul + Div<Output = T> + Copy,
 {
     type Output = Dual<T, N>;
     #[inline]
     fn div(self, rhs: &'a Dual<T, N>) -> Self::Output {
-        self.clone() / rhs
+        *self / rhs
     }
 }
 
@@ -325,7 +320,7 @@ where
     type Output = Dual<T, N>;
     #[inline]
     fn div(self, rhs: Dual<T, N>) -> Self::Output {
-        self.clone() / &rhs
+        Div::div(*self, &rhs)
     }
 }
 
@@ -350,6 +345,241 @@ where
     type Output = Dual<T, N>;
     #[inline]
     fn div(self, rhs: T) -> Self::Output {
-        self.clone() / rhs
+        *self / rhs
+    }
+}
+
+impl<T, const N: usize> Rem<&Self> for Dual<T, N>
+where
+    T: Num + Copy,
+{
+    type Output = Dual<T, N>;
+    fn rem(mut self, rhs: &Self) -> Self::Output {
+        let r = self.x % rhs.x;
+        // x % y = x - q * y with the integer-valued quotient q held constant
+        let q = (self.x - r) / rhs.x;
+        for (dst, &src) in self.dx.iter_mut().zip(rhs.dx.iter()) {
+            *dst = *dst - q * src;
+        }
+        self.x = r;
+        self
+    }
+}
+
+impl<T, const N: usize> Rem<Self> for Dual<T, N>
+where
+    T: Num + Copy,
+{
+    type Output = Dual<T, N>;
+    #[inline]
+    fn rem(self, rhs: Self) -> Self::Output {
+        Rem::rem(self, &rhs)
+    }
+}
+
+impl<'a, T, const N: usize> Rem<&'a Dual<T, N>> for &Dual<T, N>
+where
+    T: Num + Copy,
+{
+    type Output = Dual<T, N>;
+    #[inline]
+    fn rem(self, rhs: &'a Dual<T, N>) -> Self::Output {
+        *self % rhs
+    }
+}
+
+impl<T, const N: usize> Rem<Dual<T, N>> for &Dual<T, N>
+where
+    T: Num + Copy,
+{
+    type Output = Dual<T, N>;
+    #[inline]
+    fn rem(self, rhs: Dual<T, N>) -> Self::Output {
+        Rem::rem(*self, &rhs)
+    }
+}
+
+impl<T, const N: usize> Rem<T> for Dual<T, N>
+where
+    T: Num + Copy,
+{
+    type Output = Dual<T, N>;
+    fn rem(mut self, rhs: T) -> Self::Output {
+        self.x = self.x % rhs;
+        self
+    }
+}
+
+impl<T, const N: usize> Rem<T> for &Dual<T, N>
+where
+    T: Num + Copy,
+{
+    type Output = Dual<T, N>;
+    #[inline]
+    fn rem(self, rhs: T) -> Self::Output {
+        *self % rhs
+    }
+}
+
+/// `f32` and `f64` on the left of the arithmetic operators, which the generic impls above
+/// cannot cover.
+macro_rules! scalar_lhs {
+    ($($t:ty),*) => {
+        $(
+            impl<const N: usize> Add<Dual<$t, N>> for $t {
+                type Output = Dual<$t, N>;
+                #[inline]
+                fn add(self, rhs: Dual<$t, N>) -> Self::Output {
+                    rhs + self
+                }
+            }
+
+            impl<const N: usize> Sub<Dual<$t, N>> for $t {
+                type Output = Dual<$t, N>;
+                #[inline]
+                fn sub(self, rhs: Dual<$t, N>) -> Self::Output {
+                    -rhs + self
+                }
+            }
+
+            impl<const N: usize> Mul<Dual<$t, N>> for $t {
+                type Output = Dual<$t, N>;
+                #[inline]
+                fn mul(self, rhs: Dual<$t, N>) -> Self::Output {
+                    rhs * self
+                }
+            }
+
+            impl<const N: usize> Div<Dual<$t, N>> for $t {
+                type Output = Dual<$t, N>;
+                fn div(self, rhs: Dual<$t, N>) -> Self::Output {
+                    let x = rhs.x;
+                    rhs.lift(self / x, -self / (x * x))
+                }
+            }
+
+            impl<const N: usize> Rem<Dual<$t, N>> for $t {
+                type Output = Dual<$t, N>;
+                fn rem(self, rhs: Dual<$t, N>) -> Self::Output {
+                    let r = self % rhs.x;
+                    let q = (self - r) / rhs.x;
+                    rhs.lift(r, -q)
+                }
+            }
+
+            scalar_lhs!(@ref $t, Add, add);
+            scalar_lhs!(@ref $t, Sub, sub);
+            scalar_lhs!(@ref $t, Mul, mul);
+            scalar_lhs!(@ref $t, Div, div);
+            scalar_lhs!(@ref $t, Rem, rem);
+        )*
+    };
+    (@ref $t:ty, $imp:ident, $method:ident) => {
+        impl<const N: usize> $imp<&Dual<$t, N>> for $t {
+            type Output = Dual<$t, N>;
+            #[inline]
+            fn $method(self, rhs: &Dual<$t, N>) -> Self::Output {
+                $imp::$method(self, *rhs)
+            }
+        }
+    };
+}
+
+scalar_lhs!(f32, f64);
+
+/// Compound assignment through the binary operators, for a dual number or a constant on the
+/// right.
+macro_rules! assign_op {
+    ($imp:ident, $method:ident, $op:ident, $op_method:ident) => {
+        impl<T, const N: usize> $imp<&Self> for Dual<T, N>
+        where
+            T: Copy,
+            for<'a> Self: $op<&'a Self, Output = Self>,
+        {
+            #[inline]
+            fn $method(&mut self, rhs: &Self) {
+                *self = $op::$op_method(*self, rhs);
+            }
+        }
+
+        impl<T, const N: usize> $imp<Self> for Dual<T, N>
+        where
+            T: Copy,
+            for<'a> Self: $op<&'a Self, Output = Self>,
+        {
+            #[inline]
+            fn $method(&mut self, rhs: Self) {
+                *self = $op::$op_method(*self, &rhs);
+            }
+        }
+
+        impl<T, const N: usize> $imp<T> for Dual<T, N>
+        where
+            T: Copy,
+            Self: $op<T, Output = Self>,
+        {
+            #[inline]
+            fn $method(&mut self, rhs: T) {
+                *self = $op::$op_method(*self, rhs);
+            }
+        }
+    };
+}
+
+assign_op!(AddAssign, add_assign, Add, add);
+assign_op!(SubAssign, sub_assign, Sub, sub);
+assign_op!(MulAssign, mul_assign, Mul, mul);
+assign_op!(DivAssign, div_assign, Div, div);
+assign_op!(RemAssign, rem_assign, Rem, rem);
+
+impl<T, const N: usize> Sum for Dual<T, N>
+where
+    T: Zero + Add + Copy,
+{
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        let init = Self {
+            x: T::zero(),
+            dx: [T::zero(); N],
+        };
+        iter.fold(init, |acc, x| acc + x)
+    }
+}
+
+impl<'a, T, const N: usize> Sum<&'a Dual<T, N>> for Dual<T, N>
+where
+    T: Zero + Add + Copy,
+{
+    fn sum<I: Iterator<Item = &'a Dual<T, N>>>(iter: I) -> Self {
+        let init = Self {
+            x: T::zero(),
+            dx: [T::zero(); N],
+        };
+        iter.fold(init, |acc, x| acc + x)
+    }
+}
+
+impl<T, const N: usize> Product for Dual<T, N>
+where
+    T: Zero + One + Add + Mul + Copy,
+{
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        let init = Self {
+            x: T::one(),
+            dx: [T::zero(); N],
+        };
+        iter.fold(init, |acc, x| acc * x)
+    }
+}
+
+impl<'a, T, const N: usize> Product<&'a Dual<T, N>> for Dual<T, N>
+where
+    T: Zero + One + Add + Mul + Copy,
+{
+    fn product<I: Iterator<Item = &'a Dual<T, N>>>(iter: I) -> Self {
+        let init = Self {
+            x: T::one(),
+            dx: [T::zero(); N],
+        };
+        iter.fold(init, |acc, x| acc * x)
     }
 }
@@ -0,0 +1,239 @@
+use super::Dual;
+use num_traits::Zero;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeStruct, SerializeTuple, Serializer};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// The gradient as a tuple of `N` elements, since serde only covers small arrays.
+struct Gradient<'a, T>(&'a [T]);
+
+impl<T> Serialize for Gradient<'_, T>
+where
+    T: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(self.0.len())?;
+        for d in self.0.iter() {
+            tuple.serialize_element(d)?;
+        }
+        tuple.end()
+    }
+}
+
+/// Serialized as a struct with the fields `value` and `grad`.
+impl<T, const N: usize> Serialize for Dual<T, N>
+where
+    T: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Dual", 2)?;
+        state.serialize_field("value", &self.x)?;
+        state.serialize_field("grad", &Gradient(&self.dx))?;
+        state.end()
+    }
+}
+
+struct GradientArray<T, const N: usize>([T; N]);
+
+impl<'de, T, const N: usize> Deserialize<'de> for GradientArray<T, N>
+where
+    T: Deserialize<'de> + Zero + Copy,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+        impl<'de, T, const N: usize> Visitor<'de> for ArrayVisitor<T, N>
+        where
+            T: Deserialize<'de> + Zero + Copy,
+        {
+            type Value = GradientArray<T, N>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a gradient of {} elements", N)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut dx = [T::zero(); N];
+                for (i, dst) in dx.iter_mut().enumerate() {
+                    *dst = seq
+                        .next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+                }
+                if seq.next_element::<de::IgnoredAny>()?.is_some() {
+                    return Err(de::Error::invalid_length(N + 1, &self));
+                }
+                Ok(GradientArray(dx))
+            }
+        }
+
+        deserializer.deserialize_tuple(N, ArrayVisitor(PhantomData))
+    }
+}
+
+enum Field {
+    Value,
+    Grad,
+}
+
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FieldVisitor;
+
+        impl Visitor<'_> for FieldVisitor {
+            type Value = Field;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("`value` or `grad`")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Field, E> {
+                match v {
+                    "value" => Ok(Field::Value),
+                    "grad" => Ok(Field::Grad),
+                    _ => Err(de::Error::unknown_field(v, FIELDS)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(FieldVisitor)
+    }
+}
+
+const FIELDS: &[&str] = &["value", "grad"];
+
+impl<'de, T, const N: usize> Deserialize<'de> for Dual<T, N>
+where
+    T: Deserialize<'de> + Zero + Copy,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DualVisitor<T, const N: usize>(PhantomData<T>);
+
+        impl<'de, T, const N: usize> Visitor<'de> for DualVisitor<T, N>
+        where
+            T: Deserialize<'de> + Zero + Copy,
+        {
+            type Value = Dual<T, N>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a dual number")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let x = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let GradientArray(dx) = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                Ok(Dual { x, dx })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let (mut x, mut dx) = (None, None);
+                while let Some(key) = map.next_key()? {
+                    match key {
+                        Field::Value if x.is_none() => x = Some(map.next_value()?),
+                        Field::Grad if dx.is_none() => {
+                            let GradientArray(grad) = map.next_value()?;
+                            dx = Some(grad);
+                        }
+                        Field::Value => return Err(de::Error::duplicate_field("value")),
+                        Field::Grad => return Err(de::Error::duplicate_field("grad")),
+                    }
+                }
+                Ok(Dual {
+                    x: x.ok_or_else(|| de::Error::missing_field("value"))?,
+                    dx: dx.ok_or_else(|| de::Error::missing_field("grad"))?,
+                })
+            }
+        }
+
+        deserializer.deserialize_struct("Dual", FIELDS, DualVisitor(PhantomData))
+    }
+}
+
+/// Serializes only the value of a `Dual`, for use as `#[serde(with = "dual::value_only")]` on
+/// fields whose gradients would only clutter a report. Deserializes into a constant.
+pub mod value_only {
+    use super::*;
+
+    pub fn serialize<T, S, const N: usize>(
+        dual: &Dual<T, N>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        dual.x.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T, D, const N: usize>(deserializer: D) -> Result<Dual<T, N>, D::Error>
+    where
+        T: Deserialize<'de> + Zero + Copy,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(|x| Dual {
+            x,
+            dx: [T::zero(); N],
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Report {
+        count: usize,
+        cost: Dual<f64, 2>,
+        #[serde(with = "crate::value_only")]
+        loss: Dual<f64, 2>,
+    }
+
+    fn report() -> Report {
+        let x = Variables::<f64, 2>::new().gen_all(&[1.5, -0.25]);
+        Report {
+            count: 3,
+            cost: x[0] * x[1],
+            loss: x[0] + x[1],
+        }
+    }
+
+    #[test]
+    fn json_round_trip() -> anyhow::Result<()> {
+        let json = serde_json::to_string(&report())?;
+        assert_eq!(
+            r#"{"count":3,"cost":{"value":-0.375,"grad":[-0.25,1.5]},"loss":1.25}"#,
+            json
+        );
+        let back: Report = serde_json::from_str(&json)?;
+        assert_eq!(-0.375, *back.cost.val());
+        assert_eq!(array![-0.25, 1.5].view(), back.cost.grad());
+        assert_eq!(1.25, *back.loss.val());
+        assert_eq!(array![0., 0.].view(), back.loss.grad());
+
+        assert!(serde_json::from_str::<Dual<f64, 2>>(r#"{"value":1,"grad":[1]}"#).is_err());
+        assert!(serde_json::from_str::<Dual<f64, 2>>(r#"{"value":1,"grad":[1,2,3]}"#).is_err());
+        let nested: Dual<Dual<f64, 1>, 1> = serde_json::from_str(
+            r#"{"value":{"value":2,"grad":[1]},"grad":[{"value":1,"grad":[0]}]}"#,
+        )?;
+        assert_eq!(2., *nested.val().val());
+        Ok(())
+    }
+
+    #[test]
+    fn table_columns() -> anyhow::Result<()> {
+        let mut buf = Vec::new();
+        let mut table = table_dump::Table::from_writer(&mut buf);
+        table.serialize(&report())?;
+        assert_eq!(
+            "count\tcost.value\tcost.grad[0]\tcost.grad[1]\tloss\n3\t-0.375\t-0.25\t1.5\t1.25",
+            String::from_utf8(buf)?
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,589 @@
+use super::Dual;
+use num_traits::Float;
+use std::f64::consts::PI;
+
+/// Special functions with their derivatives, for `f64` and for `Dual` over any type that has
+/// them, so that nested duals give higher derivatives as well.
+pub trait Special: Sized {
+    fn erf(self) -> Self;
+    fn erfc(self) -> Self;
+    fn gamma(self) -> Self;
+    /// Logarithm of `|gamma(x)|`.
+    fn ln_gamma(self) -> Self;
+    fn digamma(self) -> Self;
+    /// `n`-th derivative of the digamma function; `polygamma(0)` is `digamma`. For `n >= 1`
+    /// only positive arguments are supported.
+    fn polygamma(self, n: u32) -> Self;
+    /// Beta function `gamma(a) gamma(b) / gamma(a + b)` for positive arguments.
+    fn beta(self, other: Self) -> Self;
+    /// Bessel function of the first kind of order 0.
+    fn bessel_j0(self) -> Self;
+    /// Bessel function of the first kind of order 1.
+    fn bessel_j1(self) -> Self;
+    /// Modified Bessel function of the first kind of order 0.
+    fn bessel_i0(self) -> Self;
+    /// Modified Bessel function of the first kind of order 1.
+    fn bessel_i1(self) -> Self;
+}
+
+const FRAC_2_SQRT_PI: f64 = std::f64::consts::FRAC_2_SQRT_PI;
+
+fn erf(x: f64) -> f64 {
+    if x.is_nan() {
+        x
+    } else if x.abs() <= 2. {
+        // Maclaurin series
+        let x2 = x * x;
+        let mut term = x;
+        let mut sum = x;
+        for n in 1..100 {
+            term *= -x2 / n as f64;
+            let t = term / (2 * n + 1) as f64;
+            sum += t;
+            if t.abs() < 1e-17 * sum.abs() {
+                break;
+            }
+        }
+        FRAC_2_SQRT_PI * sum
+    } else {
+        x.signum() * (1. - erfc_large(x.abs()))
+    }
+}
+
+fn erfc(x: f64) -> f64 {
+    if x.is_nan() {
+        x
+    } else if x > 2. {
+        erfc_large(x)
+    } else if x < -2. {
+        2. - erfc_large(-x)
+    } else {
+        1. - erf(x)
+    }
+}
+
+/// `erfc(x)` for `x > 2` from the continued fraction
+/// `exp(-x^2) / sqrt(pi) / (x + (1/2) / (x + 1 / (x + (3/2) / (x + ...))))`.
+fn erfc_large(x: f64) -> f64 {
+    // modified Lentz
+    let tiny = 1e-300;
+    let mut f = x;
+    let mut c = x;
+    let mut d = 0.;
+    for j in 1..1000 {
+        let a = j as f64 / 2.;
+        d = x + a * d;
+        d = if d == 0. { tiny } else { d };
+        c = x + a / c;
+        c = if c == 0. { tiny } else { c };
+        d = 1. / d;
+        let delta = c * d;
+        f *= delta;
+        if (delta - 1.).abs() < 1e-16 {
+            break;
+        }
+    }
+    (-x * x).exp() / (PI.sqrt() * f)
+}
+
+/// Lanczos approximation with g = 7.
+const LANCZOS: [f64; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7,
+];
+
+fn is_pole(x: f64) -> bool {
+    x <= 0. && x == x.floor()
+}
+
+fn ln_gamma(x: f64) -> f64 {
+    if is_pole(x) {
+        f64::INFINITY
+    } else if x < 0.5 {
+        // reflection, Gamma(x) Gamma(1 - x) = pi / sin(pi x)
+        (PI / (PI * x).sin().abs()).ln() - ln_gamma(1. - x)
+    } else {
+        let x = x - 1.;
+        let t = x + 7.5;
+        let a = LANCZOS
+            .iter()
+            .enumerate()
+            .skip(1)
+            .fold(LANCZOS[0], |acc, (i, &p)| acc + p / (x + i as f64));
+        0.5 * (2. * PI).ln() + (x + 0.5) * t.ln() - t + a.ln()
+    }
+}
+
+fn gamma(x: f64) -> f64 {
+    if is_pole(x) {
+        f64::NAN
+    } else if x < 0.5 {
+        PI / ((PI * x).sin() * gamma(1. - x))
+    } else {
+        ln_gamma(x).exp()
+    }
+}
+
+fn digamma(x: f64) -> f64 {
+    if is_pole(x) {
+        return f64::NAN;
+    }
+    if x < 0. {
+        return digamma(1. - x) - PI / (PI * x).tan();
+    }
+    let mut x = x;
+    let mut acc = 0.;
+    while x < 10. {
+        acc -= 1. / x;
+        x += 1.;
+    }
+    let x2 = 1. / (x * x);
+    // ln x - 1/(2x) - sum B_2k / (2k x^2k)
+    let series = x2
+        * (1. / 12.
+            - x2 * (1. / 120.
+                - x2 * (1. / 252.
+                    - x2 * (1. / 240. - x2 * (1. / 132. - x2 * (691. / 32760. - x2 / 12.))))));
+    acc + x.ln() - 0.5 / x - series
+}
+
+/// Bernoulli numbers `B_2k` for `k = 1..=10`.
+const BERNOULLI: [f64; 10] = [
+    1. / 6.,
+    -1. / 30.,
+    1. / 42.,
+    -1. / 30.,
+    5. / 66.,
+    -691. / 2730.,
+    7. / 6.,
+    -3617. / 510.,
+    43867. / 798.,
+    -174611. / 330.,
+];
+
+fn polygamma(n: u32, x: f64) -> f64 {
+    if n == 0 {
+        return digamma(x);
+    }
+    if x.is_nan() || x <= 0. {
+        return f64::NAN;
+    }
+    let nf = n as f64;
+    let factorial = |k: u32| (1..=k).fold(1., |acc, i| acc * i as f64);
+    let sign = if n % 2 == 1 { 1. } else { -1. };
+    // psi_n(x) = psi_n(x + 1) + (-1)^(n+1) n! / x^(n+1)
+    let mut x = x;
+    let mut acc = 0.;
+    while x < 15. + nf {
+        acc += sign * factorial(n) / x.powi(n as i32 + 1);
+        x += 1.;
+    }
+    // (n-1)!/x^n + n!/(2 x^(n+1)) + sum B_2k (2k+n-1)! / ((2k)! x^(2k+n))
+    let mut series = factorial(n - 1) / x.powf(nf) + factorial(n) / (2. * x.powf(nf + 1.));
+    // (2k+n-1)! / (2k)!, updated incrementally
+    let mut ratio = factorial(n + 1) / 2.;
+    let mut power = x.powf(nf + 2.);
+    for (k, &b) in BERNOULLI.iter().enumerate() {
+        let k = k as u32 + 1;
+        if k > 1 {
+            let m = 2 * k;
+            ratio *= ((m + n - 2) * (m + n - 1)) as f64 / ((m - 1) * m) as f64;
+            power *= x * x;
+        }
+        let term = b * ratio / power;
+        series += term;
+        if term.abs() < 1e-17 * series.abs() {
+            break;
+        }
+    }
+    acc + sign * series
+}
+
+fn beta(a: f64, b: f64) -> f64 {
+    (ln_gamma(a) + ln_gamma(b) - ln_gamma(a + b)).exp()
+}
+
+/// `prod_{j=1}^{k} (mu - (2j - 1)^2) / (k! 8^k)` of the Hankel expansions, as an iterator.
+fn hankel_coefficients(nu: f64) -> impl Iterator<Item = f64> {
+    let mu = 4. * nu * nu;
+    (1..).scan(1., move |a, k| {
+        let odd = (2 * k - 1) as f64;
+        *a *= (mu - odd * odd) / (8. * k as f64);
+        Some(*a)
+    })
+}
+
+/// `J_nu(x)` for large `x > 0` by the Hankel asymptotic expansion.
+fn bessel_j_large(nu: f64, x: f64) -> f64 {
+    let (mut p, mut q) = (1., 0.);
+    let mut last = f64::INFINITY;
+    for (k, a) in hankel_coefficients(nu).enumerate().take(100) {
+        let k = k + 1;
+        let term = a / x.powi(k as i32);
+        if term.abs() >= last || term.abs() < 1e-17 {
+            break;
+        }
+        last = term.abs();
+        // P takes the even terms with alternating signs, Q the odd ones
+        match k % 4 {
+            1 => q += term,
+            2 => p -= term,
+            3 => q -= term,
+            _ => p += term,
+        }
+    }
+    let chi = x - (0.5 * nu + 0.25) * PI;
+    (2. / (PI * x)).sqrt() * (p * chi.cos() - q * chi.sin())
+}
+
+/// `I_nu(x)` for large `x > 0` by its asymptotic expansion.
+fn bessel_i_large(nu: f64, x: f64) -> f64 {
+    let mut sum = 1.;
+    let mut last = f64::INFINITY;
+    for (k, a) in hankel_coefficients(nu).enumerate().take(100) {
+        let k = k + 1;
+        let term = a / x.powi(k as i32);
+        if term.abs() >= last || term.abs() < 1e-17 {
+            break;
+        }
+        last = term.abs();
+        sum += if k % 2 == 1 { -term } else { term };
+    }
+    x.exp() / (2. * PI * x).sqrt() * sum
+}
+
+/// `(J_0(x), J_1(x))` for `x >= 0`.
+fn bessel_j01(x: f64) -> (f64, f64) {
+    if x < 1. {
+        let y = -0.25 * x * x;
+        let (mut t0, mut t1) = (1., 0.5 * x);
+        let (mut j0, mut j1) = (t0, t1);
+        for k in 1..30 {
+            let k = k as f64;
+            t0 *= y / (k * k);
+            t1 *= y / (k * (k + 1.));
+            j0 += t0;
+            j1 += t1;
+        }
+        (j0, j1)
+    } else if x < 25. {
+        // Miller's backward recurrence normalized by J_0 + 2 sum J_2k = 1
+        let start = 2 * ((x as usize + 40) / 2);
+        let (mut next, mut cur) = (0., 1e-30);
+        let (mut sum, mut j1) = (0., 0.);
+        for k in (1..=start).rev() {
+            let prev = 2. * k as f64 / x * cur - next;
+            next = cur;
+            cur = prev;
+            if k == 2 {
+                j1 = cur;
+            }
+            if (k - 1) % 2 == 0 && k > 1 {
+                sum += 2. * cur;
+            }
+            if cur.abs() > 1e250 {
+                cur *= 1e-250;
+                next *= 1e-250;
+                sum *= 1e-250;
+                j1 *= 1e-250;
+            }
+        }
+        let norm = cur + sum;
+        (cur / norm, j1 / norm)
+    } else {
+        (bessel_j_large(0., x), bessel_j_large(1., x))
+    }
+}
+
+/// `(I_0(x), I_1(x))` for `x >= 0`.
+fn bessel_i01(x: f64) -> (f64, f64) {
+    if x <= 30. {
+        let y = 0.25 * x * x;
+        let (mut t0, mut t1) = (1., 0.5 * x);
+        let (mut i0, mut i1) = (t0, t1);
+        for k in 1..200 {
+            let k = k as f64;
+            t0 *= y / (k * k);
+            t1 *= y / (k * (k + 1.));
+            i0 += t0;
+            i1 += t1;
+            if t0 < 1e-17 * i0 {
+                break;
+            }
+        }
+        (i0, i1)
+    } else {
+        (bessel_i_large(0., x), bessel_i_large(1., x))
+    }
+}
+
+impl Special for f64 {
+    fn erf(self) -> Self {
+        erf(self)
+    }
+
+    fn erfc(self) -> Self {
+        erfc(self)
+    }
+
+    fn gamma(self) -> Self {
+        gamma(self)
+    }
+
+    fn ln_gamma(self) -> Self {
+        ln_gamma(self)
+    }
+
+    fn digamma(self) -> Self {
+        digamma(self)
+    }
+
+    fn polygamma(self, n: u32) -> Self {
+        polygamma(n, self)
+    }
+
+    fn beta(self, other: Self) -> Self {
+        beta(self, other)
+    }
+
+    fn bessel_j0(self) -> Self {
+        bessel_j01(self.abs()).0
+    }
+
+    fn bessel_j1(self) -> Self {
+        self.signum() * bessel_j01(self.abs()).1
+    }
+
+    fn bessel_i0(self) -> Self {
+        bessel_i01(self.abs()).0
+    }
+
+    fn bessel_i1(self) -> Self {
+        self.signum() * bessel_i01(self.abs()).1
+    }
+}
+
+impl<T, const N: usize> Special for Dual<T, N>
+where
+    T: Special + Float,
+{
+    fn erf(self) -> Self {
+        let x = self.x;
+        let c = T::from(FRAC_2_SQRT_PI).unwrap();
+        self.lift(x.erf(), c * (-x * x).exp())
+    }
+
+    fn erfc(self) -> Self {
+        let x = self.x;
+        let c = T::from(FRAC_2_SQRT_PI).unwrap();
+        self.lift(x.erfc(), -c * (-x * x).exp())
+    }
+
+    fn gamma(self) -> Self {
+        // Gamma' = Gamma psi
+        let g = self.x.gamma();
+        let d = g * self.x.digamma();
+        self.lift(g, d)
+    }
+
+    fn ln_gamma(self) -> Self {
+        let x = self.x;
+        self.lift(x.ln_gamma(), x.digamma())
+    }
+
+    fn digamma(self) -> Self {
+        let x = self.x;
+        self.lift(x.digamma(), x.polygamma(1))
+    }
+
+    fn polygamma(self, n: u32) -> Self {
+        let x = self.x;
+        self.lift(x.polygamma(n), x.polygamma(n + 1))
+    }
+
+    fn beta(self, other: Self) -> Self {
+        // dB/da = B (psi(a) - psi(a + b))
+        let (a, b) = (self.x, other.x);
+        let value = a.beta(b);
+        let psi_ab = (a + b).digamma();
+        let gradient = [
+            value * (a.digamma() - psi_ab),
+            value * (b.digamma() - psi_ab),
+        ];
+        Dual::lift_multi(&[self, other], value, &gradient)
+    }
+
+    fn bessel_j0(self) -> Self {
+        // J_0' = -J_1
+        let x = self.x;
+        self.lift(x.bessel_j0(), -x.bessel_j1())
+    }
+
+    fn bessel_j1(self) -> Self {
+        // J_1' = J_0 - J_1 / x, which tends to 1/2 at the origin
+        let x = self.x;
+        let j1 = x.bessel_j1();
+        let d = if x == T::zero() {
+            T::from(0.5).unwrap()
+        } else {
+            x.bessel_j0() - j1 / x
+        };
+        self.lift(j1, d)
+    }
+
+    fn bessel_i0(self) -> Self {
+        // I_0' = I_1
+        let x = self.x;
+        self.lift(x.bessel_i0(), x.bessel_i1())
+    }
+
+    fn bessel_i1(self) -> Self {
+        // I_1' = I_0 - I_1 / x, which tends to 1/2 at the origin
+        let x = self.x;
+        let i1 = x.bessel_i1();
+        let d = if x == T::zero() {
+            T::from(0.5).unwrap()
+        } else {
+            x.bessel_i0() - i1 / x
+        };
+        self.lift(i1, d)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Variables;
+
+    fn close(a: f64, b: f64, tol: f64) {
+        assert!(
+            (a - b).abs() <= tol * b.abs().max(1.),
+            "{} differs from {} by {:e}",
+            a,
+            b,
+            (a - b).abs()
+        );
+    }
+
+    #[test]
+    fn reference_values() {
+        // erf and erfc
+        close(Special::erf(0.5), 0.520_499_877_813_046_5, 1e-15);
+        close(Special::erf(1.), 0.842_700_792_949_714_9, 1e-15);
+        close(Special::erf(-1.), -0.842_700_792_949_714_9, 1e-15);
+        close(Special::erf(2.), 0.995_322_265_018_952_7, 1e-15);
+        close(Special::erfc(3.) / 2.209_049_699_858_544e-5, 1., 1e-13);
+        close(Special::erfc(5.) / 1.537_459_794_428_035e-12, 1., 1e-13);
+        close(Special::erfc(-1.), 1.842_700_792_949_715, 1e-15);
+
+        // gamma family
+        close(Special::gamma(5.), 24., 1e-14);
+        close(Special::gamma(0.1), 9.513_507_698_668_732, 1e-14);
+        close(Special::gamma(-0.5), -3.544_907_701_811_032, 1e-14);
+        close(Special::ln_gamma(0.5), 0.572_364_942_924_700_1, 1e-15);
+        close(Special::ln_gamma(10.), 12.801_827_480_081_469, 1e-15);
+        close(Special::ln_gamma(100.), 359.134_205_369_575_4, 1e-15);
+        close(Special::digamma(1.), -0.577_215_664_901_532_9, 1e-15);
+        close(Special::digamma(0.5), -1.963_510_026_021_423_5, 1e-15);
+        close(Special::digamma(10.), 2.251_752_589_066_721, 1e-15);
+        close(Special::digamma(-0.5), 0.036_489_973_978_576_52, 1e-14);
+        close(Special::polygamma(1., 1), PI * PI / 6., 1e-15);
+        close(Special::polygamma(0.5, 1), PI * PI / 2., 1e-15);
+        close(Special::polygamma(1., 2), -2.404_113_806_319_188_6, 1e-15);
+        close(Special::beta(2., 3.), 1. / 12., 1e-14);
+        close(Special::beta(0.5, 0.5), PI, 1e-14);
+
+        // Bessel functions
+        close(Special::bessel_j0(1.), 0.765_197_686_557_966_6, 1e-15);
+        close(Special::bessel_j1(1.), 0.440_050_585_744_933_5, 1e-15);
+        close(Special::bessel_j0(10.), -0.245_935_764_451_348_3, 1e-14);
+        close(Special::bessel_j1(10.), 0.043_472_746_168_861_44, 1e-14);
+        close(Special::bessel_j1(-10.), -0.043_472_746_168_861_44, 1e-14);
+        close(Special::bessel_j0(0.5), 0.938_469_807_240_812_9, 1e-15);
+        close(Special::bessel_i0(1.), 1.266_065_877_752_008_4, 1e-15);
+        close(Special::bessel_i1(1.), 0.565_159_103_992_485, 1e-15);
+        close(Special::bessel_i0(10.), 2_815.716_628_466_254, 1e-14);
+        close(Special::bessel_i1(10.), 2_670.988_303_701_255, 1e-14);
+        // zeros of J_0 and J_1, the last one in the asymptotic range
+        close(Special::bessel_j0(2.404_825_557_695_773), 0., 1e-14);
+        close(Special::bessel_j1(3.831_705_970_207_512_3), 0., 1e-14);
+        close(Special::bessel_j0(30.634_606_468_431_975), 0., 1e-12);
+    }
+
+    #[test]
+    fn continuity_at_switches() {
+        for &x in &[1., 2., 25., 30.] {
+            let (a, b) = (x - 1e-12, x + 1e-12);
+            close(Special::bessel_j0(a), Special::bessel_j0(b), 1e-11);
+            close(Special::bessel_j1(a), Special::bessel_j1(b), 1e-11);
+            close(Special::bessel_i0(a), Special::bessel_i0(b), 1e-11);
+            close(Special::bessel_i1(a), Special::bessel_i1(b), 1e-11);
+            close(Special::erf(a), Special::erf(b), 1e-11);
+        }
+    }
+
+    /// A function on duals and the same function on `f64`.
+    type Pair = (fn(Dual<f64, 1>) -> Dual<f64, 1>, fn(f64) -> f64);
+
+    #[test]
+    fn derivatives() {
+        let h = 1e-6;
+        let fd = |f: fn(f64) -> f64, x: f64| (f(x + h) - f(x - h)) / (2. * h);
+        let x = Variables::<f64, 1>::new().gen(2.5).unwrap();
+        let unary: [Pair; 9] = [
+            (Special::erf, Special::erf),
+            (Special::erfc, Special::erfc),
+            (Special::gamma, Special::gamma),
+            (Special::ln_gamma, Special::ln_gamma),
+            (Special::digamma, Special::digamma),
+            (Special::bessel_j0, Special::bessel_j0),
+            (Special::bessel_j1, Special::bessel_j1),
+            (Special::bessel_i0, Special::bessel_i0),
+            (Special::bessel_i1, Special::bessel_i1),
+        ];
+        for (dual_f, f) in unary.iter() {
+            let y = dual_f(x);
+            close(*y.val(), f(2.5), 1e-15);
+            close(y.grad()[0], fd(*f, 2.5), 1e-8);
+        }
+        // Gamma' = Gamma psi
+        let g = Special::gamma(x);
+        close(
+            g.grad()[0],
+            Special::gamma(2.5) * Special::digamma(2.5),
+            1e-14,
+        );
+
+        let ab = Variables::<f64, 2>::new().gen_all(&[1.5, 2.5]);
+        let b = ab[0].beta(ab[1]);
+        let fb = |a: f64, b: f64| Special::beta(a, b);
+        close(
+            b.grad()[0],
+            (fb(1.5 + h, 2.5) - fb(1.5 - h, 2.5)) / (2. * h),
+            1e-8,
+        );
+        close(
+            b.grad()[1],
+            (fb(1.5, 2.5 + h) - fb(1.5, 2.5 - h)) / (2. * h),
+            1e-8,
+        );
+
+        // nested duals: (ln Gamma)'' = psi_1 and (J_1)'' at the origin
+        let inner = Variables::<f64, 1>::new().gen_all(&[2.5]);
+        let outer = Variables::<Dual<f64, 1>, 1>::new().gen_all(&inner);
+        let y = outer[0].ln_gamma();
+        close(y.grad()[0].grad()[0], Special::polygamma(2.5, 1), 1e-15);
+        let y = outer[0].digamma();
+        close(y.grad()[0].grad()[0], Special::polygamma(2.5, 2), 1e-14);
+        let zero = Variables::<f64, 1>::new().gen(0.).unwrap();
+        close(zero.bessel_j1().grad()[0], 0.5, 1e-15);
+        close(zero.bessel_i1().grad()[0], 0.5, 1e-15);
+    }
+}
@@ -0,0 +1,840 @@
+use ndarray::prelude::*;
+use num_traits::{Float, Num, NumCast, One, ToPrimitive, Zero};
+use std::num::FpCategory;
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+/// Truncated Taylor series `c_0 + c_1 t + ... + c_{N-1} t^{N-1}` of a function of one
+/// variable, i.e. of order `N - 1`, with `c_k = f^(k)(x) / k!`.
+///
+/// Arithmetic and the elementary functions propagate all coefficients by the usual
+/// recurrences in `O(N^2)`, so that a single evaluation gives every derivative up to order
+/// `N - 1`. Comparisons only look at the value, as for `Dual`. `N` must be at least 1.
+#[derive(Debug, Clone, Copy)]
+pub struct Taylor<T, const N: usize> {
+    c: [T; N],
+}
+
+impl<T, const N: usize> Taylor<T, N>
+where
+    T: Float,
+{
+    /// The independent variable at `x`, `x + t`.
+    pub fn variable(x: T) -> Self {
+        let mut c = [T::zero(); N];
+        c[0] = x;
+        if N > 1 {
+            c[1] = T::one();
+        }
+        Self { c }
+    }
+
+    pub fn constant(x: T) -> Self {
+        let mut c = [T::zero(); N];
+        c[0] = x;
+        Self { c }
+    }
+
+    pub fn from_coefficients(c: [T; N]) -> Self {
+        Self { c }
+    }
+
+    pub fn val(&self) -> &T {
+        &self.c[0]
+    }
+
+    /// The normalized coefficients `f^(k)(x) / k!`.
+    pub fn coefficients(&self) -> ArrayView1<'_, T> {
+        ArrayView1::from(&self.c)
+    }
+
+    /// The `k`-th derivative, `k! c_k`.
+    pub fn derivative(&self, k: usize) -> T {
+        (1..=k).fold(self.c[k], |acc, i| acc * T::from(i).unwrap())
+    }
+
+    /// Evaluates the polynomial at `t` by Horner's scheme.
+    pub fn eval(&self, t: T) -> T {
+        self.c.iter().rev().fold(T::zero(), |acc, &c| acc * t + c)
+    }
+
+    /// The series of `d/dt`, whose last coefficient is lost to the truncation.
+    fn differentiate(&self) -> Self {
+        let mut c = [T::zero(); N];
+        for k in 1..N {
+            c[k - 1] = self.c[k] * T::from(k).unwrap();
+        }
+        Self { c }
+    }
+
+    /// The antiderivative with value `c0`. The top coefficient of `self` is dropped, so
+    /// `f.differentiate().integrate(f0)` is exact.
+    fn integrate(&self, c0: T) -> Self {
+        let mut c = [T::zero(); N];
+        c[0] = c0;
+        for (k, (dst, &src)) in c.iter_mut().skip(1).zip(self.c.iter()).enumerate() {
+            *dst = src / T::from(k + 1).unwrap();
+        }
+        Self { c }
+    }
+
+    /// `f(self)` for a function given its value and derivative `f'(self)` as a series.
+    #[inline]
+    fn chain(&self, fx: T, dfx: &Self) -> Self {
+        (self.differentiate() * dfx).integrate(fx)
+    }
+
+    /// `self^r` for a constant exponent with the value `p0`, from `a p' = r a' p`.
+    fn power(&self, r: T, p0: T) -> Self {
+        let a = &self.c;
+        let mut p = [T::zero(); N];
+        p[0] = p0;
+        for k in 1..N {
+            let kt = T::from(k).unwrap();
+            let mut sum = T::zero();
+            for j in 1..=k {
+                sum = sum + ((r + T::one()) * T::from(j).unwrap() - kt) * a[j] * p[k - j];
+            }
+            p[k] = sum / (kt * a[0]);
+        }
+        Self { c: p }
+    }
+
+    /// `(sin, cos)` or, with `sign = 1`, `(sinh, cosh)` from `s' = a' c`, `c' = -sign a' s`.
+    fn trig(&self, s0: T, c0: T, sign: T) -> (Self, Self) {
+        let a = &self.c;
+        let (mut s, mut c) = ([T::zero(); N], [T::zero(); N]);
+        s[0] = s0;
+        c[0] = c0;
+        for k in 1..N {
+            let (mut ds, mut dc) = (T::zero(), T::zero());
+            for j in 1..=k {
+                let ja = T::from(j).unwrap() * a[j];
+                ds = ds + ja * c[k - j];
+                dc = dc + ja * s[k - j];
+            }
+            let kt = T::from(k).unwrap();
+            s[k] = ds / kt;
+            c[k] = sign * dc / kt;
+        }
+        (Self { c: s }, Self { c })
+    }
+
+    fn with_value(mut self, c0: T) -> Self {
+        self.c[0] = c0;
+        self
+    }
+}
+
+impl<T, const N: usize> Default for Taylor<T, N>
+where
+    T: Float,
+{
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl<T, const N: usize> PartialEq<Self> for Taylor<T, N>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.c[0] == other.c[0]
+    }
+}
+
+impl<T, const N: usize> PartialEq<T> for Taylor<T, N>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &T) -> bool {
+        self.c[0] == *other
+    }
+}
+
+impl<T, const N: usize> PartialOrd<Self> for Taylor<T, N>
+where
+    T: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.c[0].partial_cmp(&other.c[0])
+    }
+}
+
+impl<T, const N: usize> PartialOrd<T> for Taylor<T, N>
+where
+    T: PartialOrd,
+{
+    fn partial_cmp(&self, other: &T) -> Option<std::cmp::Ordering> {
+        self.c[0].partial_cmp(other)
+    }
+}
+
+impl<T, const N: usize> Add<&Self> for Taylor<T, N>
+where
+    T: Float,
+{
+    type Output = Taylor<T, N>;
+    fn add(mut self, rhs: &Self) -> Self::Output {
+        for (dst, &src) in self.c.iter_mut().zip(rhs.c.iter()) {
+            *dst = *dst + src;
+        }
+        self
+    }
+}
+
+impl<T, const N: usize> Sub<&Self> for Taylor<T, N>
+where
+    T: Float,
+{
+    type Output = Taylor<T, N>;
+    fn sub(mut self, rhs: &Self) -> Self::Output {
+        for (dst, &src) in self.c.iter_mut().zip(rhs.c.iter()) {
+            *dst = *dst - src;
+        }
+        self
+    }
+}
+
+impl<T, const N: usize> Mul<&Self> for Taylor<T, N>
+where
+    T: Float,
+{
+    type Output = Taylor<T, N>;
+    fn mul(self, rhs: &Self) -> Self::Output {
+        let mut c = [T::zero(); N];
+        for (k, dst) in c.iter_mut().enumerate() {
+            for j in 0..=k {
+                *dst = *dst + self.c[j] * rhs.c[k - j];
+            }
+        }
+        Self { c }
+    }
+}
+
+impl<T, const N: usize> Div<&Self> for Taylor<T, N>
+where
+    T: Float,
+{
+    type Output = Taylor<T, N>;
+    fn div(self, rhs: &Self) -> Self::Output {
+        // c b = a solved for c order by order
+        let mut c = [T::zero(); N];
+        for k in 0..N {
+            let mut sum = self.c[k];
+            for j in 1..=k {
+                sum = sum - rhs.c[j] * c[k - j];
+            }
+            c[k] = sum / rhs.c[0];
+        }
+        Self { c }
+    }
+}
+
+impl<T, const N: usize> Rem<&Self> for Taylor<T, N>
+where
+    T: Float,
+{
+    type Output = Taylor<T, N>;
+    fn rem(self, rhs: &Self) -> Self::Output {
+        let r = self.c[0] % rhs.c[0];
+        // x % y = x - q * y with the integer-valued quotient q held constant
+        let q = (self.c[0] - r) / rhs.c[0];
+        (self - *rhs * q).with_value(r)
+    }
+}
+
+/// The remaining owned/borrowed combinations in terms of `Taylor<T, N> op &Taylor<T, N>`.
+macro_rules! forward_binop {
+    ($imp:ident, $method:ident) => {
+        impl<T, const N: usize> $imp<Self> for Taylor<T, N>
+        where
+            T: Float,
+        {
+            type Output = Taylor<T, N>;
+            #[inline]
+            fn $method(self, rhs: Self) -> Self::Output {
+                $imp::$method(self, &rhs)
+            }
+        }
+
+        impl<'a, T, const N: usize> $imp<&'a Taylor<T, N>> for &Taylor<T, N>
+        where
+            T: Float,
+        {
+            type Output = Taylor<T, N>;
+            #[inline]
+            fn $method(self, rhs: &'a Taylor<T, N>) -> Self::Output {
+                $imp::$method(*self, rhs)
+            }
+        }
+
+        impl<T, const N: usize> $imp<Taylor<T, N>> for &Taylor<T, N>
+        where
+            T: Float,
+        {
+            type Output = Taylor<T, N>;
+            #[inline]
+            fn $method(self, rhs: Taylor<T, N>) -> Self::Output {
+                $imp::$method(*self, &rhs)
+            }
+        }
+    };
+}
+
+forward_binop!(Add, add);
+forward_binop!(Sub, sub);
+forward_binop!(Mul, mul);
+forward_binop!(Div, div);
+forward_binop!(Rem, rem);
+
+impl<T, const N: usize> Add<T> for Taylor<T, N>
+where
+    T: Float,
+{
+    type Output = Taylor<T, N>;
+    fn add(mut self, rhs: T) -> Self::Output {
+        self.c[0] = self.c[0] + rhs;
+        self
+    }
+}
+
+impl<T, const N: usize> Sub<T> for Taylor<T, N>
+where
+    T: Float,
+{
+    type Output = Taylor<T, N>;
+    fn sub(mut self, rhs: T) -> Self::Output {
+        self.c[0] = self.c[0] - rhs;
+        self
+    }
+}
+
+impl<T, const N: usize> Mul<T> for Taylor<T, N>
+where
+    T: Float,
+{
+    type Output = Taylor<T, N>;
+    fn mul(mut self, rhs: T) -> Self::Output {
+        for dst in self.c.iter_mut() {
+            *dst = *dst * rhs;
+        }
+        self
+    }
+}
+
+impl<T, const N: usize> Div<T> for Taylor<T, N>
+where
+    T: Float,
+{
+    type Output = Taylor<T, N>;
+    fn div(mut self, rhs: T) -> Self::Output {
+        for dst in self.c.iter_mut() {
+            *dst = *dst / rhs;
+        }
+        self
+    }
+}
+
+/// `&Taylor<T, N> op T` in terms of the owned implementation.
+macro_rules! forward_scalar_binop {
+    ($imp:ident, $method:ident) => {
+        impl<T, const N: usize> $imp<T> for &Taylor<T, N>
+        where
+            T: Float,
+        {
+            type Output = Taylor<T, N>;
+            #[inline]
+            fn $method(self, rhs: T) -> Self::Output {
+                $imp::$method(*self, rhs)
+            }
+        }
+    };
+}
+
+forward_scalar_binop!(Add, add);
+forward_scalar_binop!(Sub, sub);
+forward_scalar_binop!(Mul, mul);
+forward_scalar_binop!(Div, div);
+
+impl<T, const N: usize> Neg for Taylor<T, N>
+where
+    T: Float,
+{
+    type Output = Taylor<T, N>;
+    fn neg(mut self) -> Self::Output {
+        for dst in self.c.iter_mut() {
+            *dst = -*dst;
+        }
+        self
+    }
+}
+
+impl<T, const N: usize> Neg for &Taylor<T, N>
+where
+    T: Float,
+{
+    type Output = Taylor<T, N>;
+    #[inline]
+    fn neg(self) -> Self::Output {
+        -*self
+    }
+}
+
+impl<T, const N: usize> Zero for Taylor<T, N>
+where
+    T: Float,
+{
+    fn zero() -> Self {
+        Self::constant(T::zero())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.c[0].is_zero()
+    }
+}
+
+impl<T, const N: usize> One for Taylor<T, N>
+where
+    T: Float,
+{
+    fn one() -> Self {
+        Self::constant(T::one())
+    }
+}
+
+impl<T, const N: usize> ToPrimitive for Taylor<T, N>
+where
+    T: ToPrimitive,
+{
+    fn to_i64(&self) -> Option<i64> {
+        self.c[0].to_i64()
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        self.c[0].to_u64()
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        self.c[0].to_f64()
+    }
+}
+
+impl<T, const N: usize> NumCast for Taylor<T, N>
+where
+    T: Float,
+{
+    fn from<P: ToPrimitive>(n: P) -> Option<Self> {
+        <T as NumCast>::from(n).map(Self::constant)
+    }
+}
+
+impl<T, const N: usize> Num for Taylor<T, N>
+where
+    T: Float,
+{
+    type FromStrRadixErr = T::FromStrRadixErr;
+
+    fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        T::from_str_radix(s, radix).map(Self::constant)
+    }
+}
+
+impl<T, const N: usize> Float for Taylor<T, N>
+where
+    T: Float,
+{
+    fn nan() -> Self {
+        Self::constant(T::nan())
+    }
+
+    fn infinity() -> Self {
+        Self::constant(T::infinity())
+    }
+
+    fn neg_infinity() -> Self {
+        Self::constant(T::neg_infinity())
+    }
+
+    fn neg_zero() -> Self {
+        Self::constant(T::neg_zero())
+    }
+
+    fn min_value() -> Self {
+        Self::constant(T::min_value())
+    }
+
+    fn min_positive_value() -> Self {
+        Self::constant(T::min_positive_value())
+    }
+
+    fn epsilon() -> Self {
+        Self::constant(T::epsilon())
+    }
+
+    fn max_value() -> Self {
+        Self::constant(T::max_value())
+    }
+
+    fn is_nan(self) -> bool {
+        self.c[0].is_nan()
+    }
+
+    fn is_infinite(self) -> bool {
+        self.c[0].is_infinite()
+    }
+
+    fn is_finite(self) -> bool {
+        self.c[0].is_finite()
+    }
+
+    fn is_normal(self) -> bool {
+        self.c[0].is_normal()
+    }
+
+    fn classify(self) -> FpCategory {
+        self.c[0].classify()
+    }
+
+    fn floor(self) -> Self {
+        Self::constant(self.c[0].floor())
+    }
+
+    fn ceil(self) -> Self {
+        Self::constant(self.c[0].ceil())
+    }
+
+    fn round(self) -> Self {
+        Self::constant(self.c[0].round())
+    }
+
+    fn trunc(self) -> Self {
+        Self::constant(self.c[0].trunc())
+    }
+
+    fn fract(self) -> Self {
+        let x = self.c[0];
+        self.with_value(x.fract())
+    }
+
+    fn abs(self) -> Self {
+        if self.c[0].is_sign_negative() {
+            -self
+        } else {
+            self
+        }
+    }
+
+    fn signum(self) -> Self {
+        Self::constant(self.c[0].signum())
+    }
+
+    fn is_sign_positive(self) -> bool {
+        self.c[0].is_sign_positive()
+    }
+
+    fn is_sign_negative(self) -> bool {
+        self.c[0].is_sign_negative()
+    }
+
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        self * a + b
+    }
+
+    fn recip(self) -> Self {
+        Self::one() / self
+    }
+
+    fn powi(self, n: i32) -> Self {
+        let x = self.c[0];
+        if !x.is_zero() {
+            return self.power(T::from(n).unwrap(), x.powi(n));
+        }
+        // the recurrence divides by x, so square and multiply instead
+        let (mut base, mut e, mut p) = (self, n.unsigned_abs(), Self::one());
+        while e > 0 {
+            if e & 1 == 1 {
+                p = p * base;
+            }
+            base = base * base;
+            e >>= 1;
+        }
+        if n < 0 {
+            p.recip()
+        } else {
+            p
+        }
+    }
+
+    fn powf(self, n: Self) -> Self {
+        let (x, y) = (self.c[0], n.c[0]);
+        if n.c[1..].iter().any(|c| !c.is_zero()) {
+            (n * self.ln()).exp().with_value(x.powf(y))
+        } else if y.fract().is_zero() && y.abs() < T::from(i32::MAX).unwrap() {
+            self.powi(y.to_i32().unwrap())
+        } else {
+            self.power(y, x.powf(y))
+        }
+    }
+
+    fn sqrt(self) -> Self {
+        let a = &self.c;
+        let mut s = [T::zero(); N];
+        s[0] = a[0].sqrt();
+        let two = T::from(2.).unwrap();
+        for k in 1..N {
+            let mut sum = a[k];
+            for j in 1..k {
+                sum = sum - s[j] * s[k - j];
+            }
+            s[k] = sum / (two * s[0]);
+        }
+        Self { c: s }
+    }
+
+    fn exp(self) -> Self {
+        let a = &self.c;
+        let mut e = [T::zero(); N];
+        e[0] = a[0].exp();
+        for k in 1..N {
+            let mut sum = T::zero();
+            for j in 1..=k {
+                sum = sum + T::from(j).unwrap() * a[j] * e[k - j];
+            }
+            e[k] = sum / T::from(k).unwrap();
+        }
+        Self { c: e }
+    }
+
+    fn exp2(self) -> Self {
+        let x = self.c[0];
+        (self * T::from(std::f64::consts::LN_2).unwrap())
+            .exp()
+            .with_value(x.exp2())
+    }
+
+    fn ln(self) -> Self {
+        let x = self.c[0];
+        self.chain(x.ln(), &self.recip())
+    }
+
+    fn log(self, base: Self) -> Self {
+        self.ln() / base.ln()
+    }
+
+    fn log2(self) -> Self {
+        let x = self.c[0];
+        (self.ln() / T::from(std::f64::consts::LN_2).unwrap()).with_value(x.log2())
+    }
+
+    fn log10(self) -> Self {
+        let x = self.c[0];
+        (self.ln() / T::from(std::f64::consts::LN_10).unwrap()).with_value(x.log10())
+    }
+
+    fn max(self, other: Self) -> Self {
+        if self.c[0] >= other.c[0] || other.c[0].is_nan() {
+            self
+        } else {
+            other
+        }
+    }
+
+    fn min(self, other: Self) -> Self {
+        if self.c[0] <= other.c[0] || other.c[0].is_nan() {
+            self
+        } else {
+            other
+        }
+    }
+
+    fn abs_sub(self, other: Self) -> Self {
+        if self.c[0] <= other.c[0] {
+            Self::zero()
+        } else {
+            self - other
+        }
+    }
+
+    fn cbrt(self) -> Self {
+        let x = self.c[0];
+        self.power(T::from(1. / 3.).unwrap(), x.cbrt())
+    }
+
+    fn hypot(self, other: Self) -> Self {
+        let h = self.c[0].hypot(other.c[0]);
+        (self * self + other * other).sqrt().with_value(h)
+    }
+
+    fn sin(self) -> Self {
+        self.sin_cos().0
+    }
+
+    fn cos(self) -> Self {
+        self.sin_cos().1
+    }
+
+    fn tan(self) -> Self {
+        let (s, c) = self.sin_cos();
+        s / c
+    }
+
+    fn asin(self) -> Self {
+        let x = self.c[0];
+        self.chain(x.asin(), &(Self::one() - self * self).sqrt().recip())
+    }
+
+    fn acos(self) -> Self {
+        let x = self.c[0];
+        self.chain(x.acos(), &-(Self::one() - self * self).sqrt().recip())
+    }
+
+    fn atan(self) -> Self {
+        let x = self.c[0];
+        self.chain(x.atan(), &(Self::one() + self * self).recip())
+    }
+
+    fn atan2(self, other: Self) -> Self {
+        let (y, x) = (self, other);
+        // d atan2(y, x) = (x dy - y dx) / (x^2 + y^2)
+        let d = (x * y.differentiate() - y * x.differentiate()) / (x * x + y * y);
+        d.integrate(y.c[0].atan2(x.c[0]))
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        let x = self.c[0];
+        self.trig(x.sin(), x.cos(), -T::one())
+    }
+
+    fn exp_m1(self) -> Self {
+        let x = self.c[0];
+        self.exp().with_value(x.exp_m1())
+    }
+
+    fn ln_1p(self) -> Self {
+        let x = self.c[0];
+        self.chain(x.ln_1p(), &(self + T::one()).recip())
+    }
+
+    fn sinh(self) -> Self {
+        let x = self.c[0];
+        self.trig(x.sinh(), x.cosh(), T::one()).0
+    }
+
+    fn cosh(self) -> Self {
+        let x = self.c[0];
+        self.trig(x.sinh(), x.cosh(), T::one()).1
+    }
+
+    fn tanh(self) -> Self {
+        let x = self.c[0];
+        let (s, c) = self.trig(x.sinh(), x.cosh(), T::one());
+        s / c
+    }
+
+    fn asinh(self) -> Self {
+        let x = self.c[0];
+        self.chain(x.asinh(), &(self * self + T::one()).sqrt().recip())
+    }
+
+    fn acosh(self) -> Self {
+        let x = self.c[0];
+        self.chain(x.acosh(), &(self * self - T::one()).sqrt().recip())
+    }
+
+    fn atanh(self) -> Self {
+        let x = self.c[0];
+        self.chain(x.atanh(), &(Self::one() - self * self).recip())
+    }
+
+    fn integer_decode(self) -> (u64, i16, i8) {
+        self.c[0].integer_decode()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-12 * b.abs().max(1.)
+    }
+
+    #[test]
+    fn elementary_series() {
+        let x = Taylor::<f64, 6>::variable(0.4);
+        // k-th derivatives of exp(2 x) are 2^k exp(2 x)
+        let e = (x * 2.).exp();
+        for k in 0..6 {
+            assert!(close(e.derivative(k), 2f64.powi(k as i32) * 0.8f64.exp()));
+        }
+        // sin'''' = sin
+        let s = x.sin();
+        assert!(close(s.derivative(4), 0.4f64.sin()));
+        assert!(close(s.derivative(3), -0.4f64.cos()));
+        // identities between series
+        let one = Float::powi(x.sin(), 2) + Float::powi(x.cos(), 2);
+        assert!(close(*one.val(), 1.));
+        assert!(one.coefficients().iter().skip(1).all(|c| c.abs() < 1e-14));
+        for (a, b) in [
+            (x.ln().exp(), x),
+            (x.sqrt() * x.sqrt(), x),
+            (x.cbrt().powi(3), x),
+            (x.tan(), x.sin() / x.cos()),
+            (x.tanh(), x.sinh() / x.cosh()),
+            (x.asin().sin(), x),
+            (x.acos().cos(), x),
+            (x.atan().tan(), x),
+            (x.asinh().sinh(), x),
+            ((x + 1.5).acosh().cosh(), x + 1.5),
+            (x.atanh().tanh(), x),
+            (x.ln_1p(), (x + 1.).ln()),
+            (x.exp_m1(), x.exp() - 1.),
+            (x.exp2(), (x * std::f64::consts::LN_2).exp()),
+            (x.log10(), x.ln() / std::f64::consts::LN_10),
+            (x.powf(x), (x * x.ln()).exp()),
+            (x.powf(Taylor::constant(2.5)), x.sqrt() * x * x),
+            (x.powi(-2), (x * x).recip()),
+            (x.hypot(x * 2.), x * 5f64.sqrt()),
+            (x.atan2(Taylor::constant(1.) - x), (x / (-x + 1.)).atan()),
+        ] {
+            for (u, v) in a.coefficients().iter().zip(b.coefficients().iter()) {
+                assert!(close(*u, *v), "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn powers_at_zero() {
+        let x = Taylor::<f64, 5>::variable(0.);
+        let p = (x + 2.).powi(3) - 8.;
+        assert_eq!(array![0., 12., 6., 1., 0.], p.coefficients());
+        assert_eq!(array![0., 0., 0., 1., 0.], x.powi(3).coefficients());
+    }
+
+    #[test]
+    fn halley() {
+        // Halley's method for x^3 = 2 from the first two derivatives of one evaluation
+        let mut x = 1.;
+        for _ in 0..4 {
+            let f = Taylor::<f64, 3>::variable(x).powi(3) - 2.;
+            let (f0, f1, f2) = (*f.val(), f.derivative(1), f.derivative(2));
+            x -= 2. * f0 * f1 / (2. * f1 * f1 - f0 * f2);
+        }
+        assert!(close(x, 2f64.cbrt()));
+    }
+
+    #[test]
+    fn ode_step() {
+        // Taylor method for y' = -2 t y from the coefficients of y(t0 + s) order by order:
+        // (k + 1) y_{k+1} = -2 (t0 y_k + y_{k-1})
+        let (t0, y0, h) = (0.5, (-0.25f64).exp(), 0.1);
+        let t = Taylor::<f64, 16>::variable(t0);
+        let mut y = Taylor::<f64, 16>::constant(y0);
+        for k in 0..15 {
+            let rhs = t * y * -2.;
+            y.c[k + 1] = rhs.c[k] / (k + 1) as f64;
+        }
+        assert!(close(y.eval(h), (-(t0 + h) * (t0 + h)).exp()));
+    }
+}
@@ -0,0 +1,353 @@
+use num_traits::{Float, Num, NumCast, One, ToPrimitive, Zero};
+use std::cell::RefCell;
+use std::num::FpCategory;
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+thread_local! {
+    /// Dependency sets of the current trace, sorted and deduplicated. Set 0 is empty.
+    static SETS: RefCell<Vec<Vec<usize>>> = RefCell::new(vec![Vec::new()]);
+}
+
+/// Number carrying the set of input variables it depends on instead of derivatives, for
+/// detecting the sparsity pattern of a Jacobian with code written for `num_traits::Float`.
+///
+/// Every operation depends on all of its operands, including those with a vanishing
+/// derivative such as `floor`, so the detected pattern holds at any point as long as the
+/// traced code takes the same branches. Branches follow the value, which is carried along.
+///
+/// The sets live in a thread-local table, so tracers are only meaningful inside the `trace`
+/// call that created them.
+#[derive(Debug, Clone, Copy)]
+pub struct Tracer {
+    x: f64,
+    set: usize,
+}
+
+/// Drops the sets added since a trace started when it ends, including by a panic, and leaves
+/// those of the traces around it alone.
+struct Truncate(usize);
+
+impl Drop for Truncate {
+    fn drop(&mut self) {
+        SETS.with(|sets| sets.borrow_mut().truncate(self.0));
+    }
+}
+
+/// Evaluates `f` on variables with the values `x`, numbered by their position.
+///
+/// The dependencies of the results can be read with `Tracer::dependencies` inside `f`. Traces
+/// may be nested, e.g. by an `f` that detects the pattern of another function; each numbers
+/// its own variables.
+pub fn trace<F, R>(x: &[f64], f: F) -> R
+where
+    F: FnOnce(&[Tracer]) -> R,
+{
+    let start = SETS.with(|sets| sets.borrow().len());
+    let _truncate = Truncate(start);
+    let vars: Vec<_> = SETS.with(|sets| {
+        let mut sets = sets.borrow_mut();
+        x.iter()
+            .enumerate()
+            .map(|(i, &x)| {
+                sets.push(vec![i]);
+                Tracer {
+                    x,
+                    set: sets.len() - 1,
+                }
+            })
+            .collect()
+    });
+    f(&vars)
+}
+
+impl Tracer {
+    pub fn val(&self) -> f64 {
+        self.x
+    }
+
+    /// Indices of the variables this number depends on, in increasing order.
+    pub fn dependencies(&self) -> Vec<usize> {
+        SETS.with(|sets| sets.borrow()[self.set].clone())
+    }
+
+    fn constant(x: f64) -> Self {
+        Self { x, set: 0 }
+    }
+
+    /// The result `x` of an operation on `self` and `other`.
+    fn join(self, other: Self, x: f64) -> Self {
+        let set = if self.set == other.set || other.set == 0 {
+            self.set
+        } else if self.set == 0 {
+            other.set
+        } else {
+            SETS.with(|sets| {
+                let mut sets = sets.borrow_mut();
+                let (a, b) = (&sets[self.set], &sets[other.set]);
+                let mut union = Vec::with_capacity(a.len() + b.len());
+                let (mut i, mut j) = (0, 0);
+                while i < a.len() && j < b.len() {
+                    if a[i] < b[j] {
+                        union.push(a[i]);
+                        i += 1;
+                    } else if a[i] > b[j] {
+                        union.push(b[j]);
+                        j += 1;
+                    } else {
+                        union.push(a[i]);
+                        i += 1;
+                        j += 1;
+                    }
+                }
+                union.extend_from_slice(&a[i..]);
+                union.extend_from_slice(&b[j..]);
+                if union.len() == a.len() {
+                    self.set
+                } else if union.len() == b.len() {
+                    other.set
+                } else {
+                    sets.push(union);
+                    sets.len() - 1
+                }
+            })
+        };
+        Self { x, set }
+    }
+
+    fn map(self, x: f64) -> Self {
+        Self { x, set: self.set }
+    }
+}
+
+impl PartialEq for Tracer {
+    fn eq(&self, other: &Self) -> bool {
+        self.x == other.x
+    }
+}
+
+impl PartialOrd for Tracer {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.x.partial_cmp(&other.x)
+    }
+}
+
+macro_rules! binop {
+    ($imp:ident, $method:ident) => {
+        impl $imp for Tracer {
+            type Output = Tracer;
+            fn $method(self, rhs: Self) -> Self::Output {
+                self.join(rhs, $imp::$method(self.x, rhs.x))
+            }
+        }
+    };
+}
+
+binop!(Add, add);
+binop!(Sub, sub);
+binop!(Mul, mul);
+binop!(Div, div);
+binop!(Rem, rem);
+
+impl Neg for Tracer {
+    type Output = Tracer;
+    fn neg(self) -> Self::Output {
+        self.map(-self.x)
+    }
+}
+
+impl Zero for Tracer {
+    fn zero() -> Self {
+        Self::constant(0.)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.x == 0.
+    }
+}
+
+impl One for Tracer {
+    fn one() -> Self {
+        Self::constant(1.)
+    }
+}
+
+impl ToPrimitive for Tracer {
+    fn to_i64(&self) -> Option<i64> {
+        self.x.to_i64()
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        self.x.to_u64()
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(self.x)
+    }
+}
+
+impl NumCast for Tracer {
+    fn from<P: ToPrimitive>(n: P) -> Option<Self> {
+        n.to_f64().map(Self::constant)
+    }
+}
+
+impl Num for Tracer {
+    type FromStrRadixErr = <f64 as Num>::FromStrRadixErr;
+
+    fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        f64::from_str_radix(s, radix).map(Self::constant)
+    }
+}
+
+macro_rules! constants {
+    ($($name:ident),*) => {
+        $(
+            fn $name() -> Self {
+                Self::constant(f64::$name())
+            }
+        )*
+    };
+}
+
+macro_rules! predicates {
+    ($($name:ident -> $ret:ty),*) => {
+        $(
+            fn $name(self) -> $ret {
+                self.x.$name()
+            }
+        )*
+    };
+}
+
+macro_rules! unary {
+    ($($name:ident),*) => {
+        $(
+            fn $name(self) -> Self {
+                self.map(self.x.$name())
+            }
+        )*
+    };
+}
+
+macro_rules! binary {
+    ($($name:ident),*) => {
+        $(
+            fn $name(self, other: Self) -> Self {
+                self.join(other, self.x.$name(other.x))
+            }
+        )*
+    };
+}
+
+impl Float for Tracer {
+    constants!(
+        nan,
+        infinity,
+        neg_infinity,
+        neg_zero,
+        min_value,
+        min_positive_value,
+        epsilon,
+        max_value
+    );
+
+    predicates!(
+        is_nan -> bool,
+        is_infinite -> bool,
+        is_finite -> bool,
+        is_normal -> bool,
+        classify -> FpCategory,
+        is_sign_positive -> bool,
+        is_sign_negative -> bool,
+        integer_decode -> (u64, i16, i8)
+    );
+
+    unary!(
+        floor, ceil, round, trunc, fract, abs, signum, recip, sqrt, exp, exp2, ln, log2, log10,
+        cbrt, sin, cos, tan, asin, acos, atan, exp_m1, ln_1p, sinh, cosh, tanh, asinh, acosh,
+        atanh
+    );
+
+    // `max` and `min` depend on both operands, whichever is picked at the traced point
+    binary!(powf, log, max, min, hypot, atan2);
+
+    fn powi(self, n: i32) -> Self {
+        self.map(self.x.powi(n))
+    }
+
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        self * a + b
+    }
+
+    fn abs_sub(self, other: Self) -> Self {
+        self.join(other, (self.x - other.x).max(0.))
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        (self.sin(), self.cos())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn banded<T: Float>(x: &[T]) -> Vec<T> {
+        let n = x.len();
+        (0..n)
+            .map(|i| {
+                let left = if i > 0 { x[i - 1] } else { T::zero() };
+                let right = if i + 1 < n { x[i + 1].exp() } else { T::one() };
+                left * x[i] + right.max(x[0].floor())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dependencies() {
+        let pattern = trace(&[0.5, 1., 2., 3.], |x| {
+            banded(x)
+                .iter()
+                .map(Tracer::dependencies)
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(
+            vec![vec![0, 1], vec![0, 1, 2], vec![0, 1, 2, 3], vec![0, 2, 3]],
+            pattern
+        );
+
+        // values follow along, constants depend on nothing
+        let (value, constant) = trace(&[2.], |x| {
+            let y = x[0] * x[0] + <Tracer as NumCast>::from(1.).unwrap();
+            (y.val(), Tracer::one().dependencies())
+        });
+        assert_eq!(5., value);
+        assert!(constant.is_empty());
+    }
+
+    #[test]
+    fn nested() {
+        let (outer, inner) = trace(&[0.5, 1., 2., 3.], |x| {
+            let y = x[0] * x[3];
+            let inner = trace(&[1., 2., 3.], |z| (z[2] + z[0]).dependencies());
+            let w = y + x[1];
+            (vec![y.dependencies(), w.dependencies()], inner)
+        });
+        assert_eq!(vec![vec![0, 3], vec![0, 1, 3]], outer);
+        assert_eq!(vec![0, 2], inner);
+
+        // an inner trace that panics leaves the outer sets alone
+        let pattern = trace(&[0.5, 1.], |x| {
+            let y = x[0] + x[1];
+            let inner = std::panic::catch_unwind(|| {
+                trace(&[1., 2., 3.], |z| {
+                    let _ = z[0] * z[1] * z[2];
+                    panic!("inside the trace");
+                })
+            });
+            assert!(inner.is_err());
+            (y + x[0]).dependencies()
+        });
+        assert_eq!(vec![0, 1], pattern);
+    }
+}
@@ -0,0 +1,503 @@
+pub use crate::criteria::*;
+pub use crate::error::*;
+pub use crate::executor::*;
+pub use crate::traits::*;
+
+use crate::linalg;
+use crate::self_consistent::{
+    Differentiable, DifferentiableOp, SelfConsistentOp, SelfConsistentOpSolver, VectorReport,
+};
+use dual::{Dual, Variables};
+use ndarray::prelude::*;
+use serde::Serialize;
+use std::cell::Cell;
+use std::io;
+
+/// Self-consistent problem `x = F(x, p)` with parameters `p`, written for any float type so
+/// that derivatives with respect to both can be taken with `dual`.
+///
+/// `Continuation` follows a single parameter and always passes `p` with one component;
+/// `self_consistent::implicit` takes any number.
+pub trait ParametricOp {
+    fn apply<T: num_traits::Float>(&self, x: &[T], p: &[T]) -> Result<Vec<T>, Error>;
+}
+
+/// How the next point of the branch is parametrized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    /// Step the parameter and solve for `x` at the new value; stops at folds.
+    Natural,
+    /// Step along the arclength of the branch in `(x, p)`, solving for both; passes folds.
+    PseudoArclength,
+}
+
+/// Problem handed to the corrector at each step.
+///
+/// With `Method::Natural` the variable is `x` at a fixed parameter. With
+/// `Method::PseudoArclength` it is `(x, p)`, and the last component of the map enforces that
+/// the correction is orthogonal to the tangent at the predicted point.
+pub struct Corrector<'a, F> {
+    pub problem: &'a F,
+    pub parameter: f64,
+    /// Tangent and predicted point in `(x, p)` for the pseudo-arclength condition.
+    pub arclength: Option<(Array1<f64>, Array1<f64>)>,
+    /// Set once the map returns a value that is not finite, so that the error a solver
+    /// raises after diverging can be told from a hard one.
+    diverged: &'a Cell<bool>,
+}
+
+impl<F> DifferentiableOp for Corrector<'_, F>
+where
+    F: ParametricOp,
+{
+    fn apply<T: num_traits::Float>(&self, y: &[T]) -> Result<Vec<T>, Error> {
+        let next = match &self.arclength {
+            None => self.problem.apply(y, &[T::from(self.parameter).unwrap()])?,
+            Some((tangent, predicted)) => {
+                let n = y.len() - 1;
+                let mut next = self.problem.apply(&y[..n], &y[n..])?;
+                let constraint = y.iter().zip(tangent.iter().zip(predicted.iter())).fold(
+                    T::zero(),
+                    |acc, (&yi, (&ti, &pi))| {
+                        acc + T::from(ti).unwrap() * (yi - T::from(pi).unwrap())
+                    },
+                );
+                next.push(y[n] - constraint);
+                next
+            }
+        };
+        if !next.iter().all(|v| v.is_finite()) {
+            self.diverged.set(true);
+        }
+        Ok(next)
+    }
+}
+
+/// `dF/dx v + dF/dp w` at `(x, p)` by a single-direction dual.
+pub(crate) fn directional<F>(
+    problem: &F,
+    x: &Array1<f64>,
+    p: &[f64],
+    v: &Array1<f64>,
+    w: &[f64],
+) -> Result<Array1<f64>, Error>
+where
+    F: ParametricOp + ?Sized,
+{
+    let vars = Variables::<f64, 1>::new();
+    let seed = |(&value, &direction): (&f64, &f64)| -> Dual<f64, 1> {
+        let mut d = vars.constant(value);
+        d.grad_mut()[0] = direction;
+        d
+    };
+    let xs: Vec<_> = x.iter().zip(v.iter()).map(seed).collect();
+    let ps: Vec<_> = p.iter().zip(w.iter()).map(seed).collect();
+    let y = problem.apply(&xs, &ps)?;
+    if y.len() != x.len() {
+        return Err(Error::InvalidVariable);
+    }
+    Ok(y.iter().map(|d| d.grad()[0]).collect())
+}
+
+/// Jacobian `[I - dF/dx, -dF/dp]` of the residual `x - F(x, p)`, one column per evaluation.
+fn residual_jacobian<F>(problem: &F, x: &Array1<f64>, p: f64) -> Result<Array2<f64>, Error>
+where
+    F: ParametricOp,
+{
+    let n = x.len();
+    let mut jac = Array2::zeros((n, n + 1));
+    for j in 0..=n {
+        let mut v = Array1::zeros(n);
+        let vp = if j < n {
+            v[j] = 1.;
+            0.
+        } else {
+            1.
+        };
+        let column = directional(problem, x, &[p], &v, &[vp])?;
+        jac.column_mut(j).assign(&(v - column));
+    }
+    Ok(jac)
+}
+
+fn solve(a: &Array2<f64>, b: &Array1<f64>) -> Option<Array1<f64>> {
+    let (q, r) = linalg::qr(a, 1e-12)?;
+    Some(linalg::solve_upper(&r, &q.t().dot(b)))
+}
+
+/// A point of the solution branch, streamed as a row of a `table_dump::Table`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BranchPoint {
+    pub step: usize,
+    pub parameter: f64,
+    /// Accumulated length of the branch in `(x, p)`.
+    pub arclength: f64,
+    /// Step size that produced this point.
+    pub step_size: f64,
+    pub iterations: usize,
+    /// Infinity norm of `x - F(x, p)`.
+    pub residual: f64,
+    pub x: Vec<f64>,
+}
+
+/// Continuation of the solutions of a `ParametricOp` in its parameter.
+///
+/// Each step predicts the next point along the tangent of the branch, obtained from the
+/// derivatives of the problem by `dual`, and corrects it with a fresh solver made by
+/// `corrector`, run by an `Executor` until the relative change falls below the tolerance. A
+/// failed correction halves the step; quickly converging ones let it grow.
+pub struct Continuation<G> {
+    corrector: G,
+    method: Method,
+    step: f64,
+    min_step: f64,
+    max_step: f64,
+    growth: f64,
+    tolerance: f64,
+    max_iterations: usize,
+    max_points: usize,
+    bounds: (f64, f64),
+}
+
+impl<G> Continuation<G> {
+    pub fn new(corrector: G, method: Method) -> Self {
+        Self {
+            corrector,
+            method,
+            step: 0.1,
+            min_step: 1e-6,
+            max_step: 1.,
+            growth: 1.5,
+            tolerance: 1e-10,
+            max_iterations: 20,
+            max_points: 100,
+            bounds: (f64::NEG_INFINITY, f64::INFINITY),
+        }
+    }
+
+    /// Initial step, in the parameter for `Method::Natural` and along the branch otherwise;
+    /// its sign sets the initial direction. 0.1 by default.
+    pub fn step(mut self, step: f64) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Smallest step before the continuation gives up; 1e-6 by default.
+    pub fn min_step(mut self, step: f64) -> Self {
+        self.min_step = step;
+        self
+    }
+
+    /// 1 by default.
+    pub fn max_step(mut self, step: f64) -> Self {
+        self.max_step = step;
+        self
+    }
+
+    /// Factor applied to the step after a correction within a quarter of the iteration
+    /// limit; 1.5 by default.
+    pub fn growth(mut self, factor: f64) -> Self {
+        self.growth = factor;
+        self
+    }
+
+    /// Relative change at which a correction has converged; 1e-10 by default.
+    pub fn tolerance(mut self, tol: f64) -> Self {
+        self.tolerance = tol;
+        self
+    }
+
+    /// Iterations allowed to a correction before it counts as failed; 20 by default.
+    pub fn max_iterations(mut self, n: usize) -> Self {
+        self.max_iterations = n;
+        self
+    }
+
+    /// Number of points on the branch, including the initial one; 100 by default.
+    pub fn max_points(mut self, n: usize) -> Self {
+        self.max_points = n;
+        self
+    }
+
+    /// The continuation stops when the parameter leaves `[lower, upper]`.
+    pub fn bounds(mut self, lower: f64, upper: f64) -> Self {
+        self.bounds = (lower, upper);
+        self
+    }
+
+    /// Solves `x = F(x, p0)` from `x0`, then follows the branch, writing every point to
+    /// `table` and returning the whole branch.
+    pub fn run<F, S, W>(
+        &mut self,
+        problem: &F,
+        x0: Array1<f64>,
+        p0: f64,
+        table: &mut table_dump::Table<W>,
+    ) -> anyhow::Result<Vec<BranchPoint>>
+    where
+        F: ParametricOp,
+        G: FnMut() -> S,
+        S: for<'a> SelfConsistentOpSolver<Differentiable<Corrector<'a, F>>>,
+        W: io::Write,
+    {
+        let n = x0.len();
+        let (x, iterations) = self
+            .correct(problem, p0, None, x0)?
+            .ok_or_else(|| Error::Failure("the initial point did not converge".to_string()))?;
+        let mut point = self.point(problem, 0, p0, 0., 0., iterations, x)?;
+        table.serialize(&point)?;
+        let mut branch = vec![point.clone()];
+
+        let mut step = self.step;
+        // tangent in (x, p); initially along the parameter in the direction of the step
+        let mut tangent = Array1::zeros(n + 1);
+        tangent[n] = step.signum();
+        step = step.abs();
+        while branch.len() < self.max_points {
+            let x = Array1::from(point.x.clone());
+            let p = point.parameter;
+            let jac = residual_jacobian(problem, &x, p)?;
+            let next = match self.method {
+                Method::Natural => {
+                    // (I - dF/dx) dx/dp = dF/dp
+                    let dp = tangent[n] * step;
+                    let dxdp = solve(&jac.slice(s![.., ..n]).to_owned(), &-&jac.column(n))
+                        .unwrap_or_else(|| Array1::zeros(n));
+                    let predicted = &x + &(dp * &dxdp);
+                    self.correct(problem, p + dp, None, predicted)?
+                        .map(|(x, it)| (x, p + dp, it))
+                }
+                Method::PseudoArclength => {
+                    let mut bordered = Array2::zeros((n + 1, n + 1));
+                    bordered.slice_mut(s![..n, ..]).assign(&jac);
+                    bordered.row_mut(n).assign(&tangent);
+                    let mut rhs = Array1::zeros(n + 1);
+                    rhs[n] = 1.;
+                    let t = solve(&bordered, &rhs).ok_or_else(|| {
+                        Error::Failure("the tangent of the branch is not unique".to_string())
+                    })?;
+                    tangent = &t / t.dot(&t).sqrt();
+                    let mut y = x.clone().into_raw_vec();
+                    y.push(p);
+                    let predicted = Array1::from(y) + step * &tangent;
+                    let arclength = Some((tangent.clone(), predicted.clone()));
+                    self.correct(problem, p, arclength, predicted)?
+                        .map(|(y, it)| (y.slice(s![..n]).to_owned(), y[n], it))
+                }
+            };
+            match next {
+                Some((x_next, p_next, iterations)) => {
+                    let distance =
+                        ((&x_next - &x).mapv(|v| v * v).sum() + (p_next - p).powi(2)).sqrt();
+                    let arclength = point.arclength + distance;
+                    point = self.point(
+                        problem,
+                        branch.len(),
+                        p_next,
+                        arclength,
+                        step,
+                        iterations,
+                        x_next,
+                    )?;
+                    table.serialize(&point)?;
+                    branch.push(point.clone());
+                    if p_next < self.bounds.0 || p_next > self.bounds.1 {
+                        break;
+                    }
+                    if 4 * iterations <= self.max_iterations {
+                        step = (step * self.growth).min(self.max_step);
+                    }
+                }
+                None => {
+                    step *= 0.5;
+                    if step < self.min_step {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(branch)
+    }
+
+    /// Corrected variable and the number of iterations, or `None` if the corrector did not
+    /// converge within the iteration limit or diverged. Any other error is returned.
+    fn correct<F, S>(
+        &mut self,
+        problem: &F,
+        parameter: f64,
+        arclength: Option<(Array1<f64>, Array1<f64>)>,
+        y0: Array1<f64>,
+    ) -> anyhow::Result<Option<(Array1<f64>, usize)>>
+    where
+        F: ParametricOp,
+        G: FnMut() -> S,
+        S: for<'a> SelfConsistentOpSolver<Differentiable<Corrector<'a, F>>>,
+    {
+        let diverged = Cell::new(false);
+        let op = Differentiable(Corrector {
+            problem,
+            parameter,
+            arclength,
+            diverged: &diverged,
+        });
+        let tolerance = self.tolerance;
+        let max_iterations = self.max_iterations;
+        let mut report = (0, f64::NAN);
+        let y = match Executor::new((self.corrector)(), op)
+            .report(VectorReport::default())
+            .add_monitor(|r: &VectorReport| {
+                report = (r.count, r.error);
+                Ok(())
+            })
+            .terminate(when(move |r: &VectorReport| {
+                r.error < tolerance || r.count >= max_iterations
+            }))
+            .run(y0)
+        {
+            Ok(y) => y,
+            Err(_) if diverged.get() => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let (count, error) = report;
+        if error < tolerance && y.iter().all(|v| v.is_finite()) {
+            Ok(Some((y, count)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn point<F>(
+        &self,
+        problem: &F,
+        step: usize,
+        parameter: f64,
+        arclength: f64,
+        step_size: f64,
+        iterations: usize,
+        x: Array1<f64>,
+    ) -> Result<BranchPoint, Error>
+    where
+        F: ParametricOp,
+    {
+        let diverged = Cell::new(false);
+        let op = Differentiable(Corrector {
+            problem,
+            parameter,
+            arclength: None,
+            diverged: &diverged,
+        });
+        let residual = (op.apply(&x)? - &x)
+            .iter()
+            .fold(0f64, |acc, v| acc.max(v.abs()));
+        Ok(BranchPoint {
+            step,
+            parameter,
+            arclength,
+            step_size,
+            iterations,
+            residual,
+            x: x.into_raw_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::self_consistent::solver::{ForwardMode, Jfnk};
+    use approx::assert_abs_diff_eq;
+
+    /// Discretized Bratu problem `u'' + p exp(u) = 0` with zero boundaries, whose branch of
+    /// solutions has a fold near `p = 3.5`.
+    struct Bratu {
+        n: usize,
+    }
+
+    impl ParametricOp for Bratu {
+        fn apply<T: num_traits::Float>(&self, u: &[T], p: &[T]) -> Result<Vec<T>, Error> {
+            let h = 1. / (self.n + 1) as f64;
+            let h2 = T::from(h * h).unwrap();
+            let half = T::from(0.5).unwrap();
+            Ok((0..self.n)
+                .map(|i| {
+                    let left = if i == 0 { T::zero() } else { u[i - 1] };
+                    let right = if i + 1 == self.n { T::zero() } else { u[i + 1] };
+                    (left + right + h2 * p[0] * u[i].exp()) * half
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn through_the_fold() -> anyhow::Result<()> {
+        let problem = Bratu { n: 20 };
+        let mut buf = Vec::new();
+        let mut table = table_dump::Table::from_writer(&mut buf);
+        let branch = Continuation::new(|| Jfnk::new(ForwardMode), Method::PseudoArclength)
+            .step(0.5)
+            .max_points(40)
+            .run(&problem, Array1::zeros(20), 0., &mut table)?;
+        drop(table);
+
+        let (fold, p_max) = branch.iter().enumerate().fold((0, 0f64), |acc, (i, b)| {
+            if b.parameter > acc.1 {
+                (i, b.parameter)
+            } else {
+                acc
+            }
+        });
+        // the fold of the continuous problem is at 3.5138
+        assert!((p_max - 3.51).abs() < 0.05);
+        assert!(fold < branch.len() - 1);
+        let last = branch.last().unwrap();
+        assert!(last.parameter < p_max && last.x[9] > branch[fold].x[9]);
+        for b in &branch {
+            assert!(b.residual < 1e-8);
+        }
+        let text = String::from_utf8(buf)?;
+        assert!(text.starts_with("step\tparameter"));
+        assert_eq!(text.lines().count(), branch.len() + 1);
+        Ok(())
+    }
+
+    #[test]
+    fn natural_stops_at_the_fold() -> anyhow::Result<()> {
+        let problem = Bratu { n: 20 };
+        let mut table = table_dump::Table::from_writer(io::sink());
+        let branch = Continuation::new(|| Jfnk::new(ForwardMode), Method::Natural)
+            .step(0.5)
+            .min_step(1e-4)
+            .run(&problem, Array1::zeros(20), 0., &mut table)?;
+        let last = branch.last().unwrap();
+        assert!(last.parameter < 3.52 && last.parameter > 3.4);
+        for w in branch.windows(2) {
+            assert!(w[1].parameter > w[0].parameter);
+        }
+        assert_abs_diff_eq!(branch[0].x[0], 0., epsilon = 1e-12);
+        Ok(())
+    }
+
+    /// `Bratu` that fails outright beyond `p = 1`.
+    struct Cutoff(Bratu);
+
+    impl ParametricOp for Cutoff {
+        fn apply<T: num_traits::Float>(&self, u: &[T], p: &[T]) -> Result<Vec<T>, Error> {
+            if p[0] > T::one() {
+                return Err(Error::Failure(String::from("beyond the cutoff")));
+            }
+            self.0.apply(u, p)
+        }
+    }
+
+    #[test]
+    fn hard_errors_surface() {
+        let problem = Cutoff(Bratu { n: 20 });
+        let mut table = table_dump::Table::from_writer(io::sink());
+        let error = Continuation::new(|| Jfnk::new(ForwardMode), Method::Natural)
+            .step(0.5)
+            .run(&problem, Array1::zeros(20), 0., &mut table)
+            .unwrap_err();
+        assert!(error.to_string().contains("beyond the cutoff"));
+    }
+}
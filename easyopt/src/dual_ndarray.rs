@@ -1,4 +1,5 @@
 use ndarray::prelude::*;
+use ndarray::ScalarOperand;
 use num_traits::{One, Zero};
 use std::marker::PhantomData;
 
@@ -69,11 +70,11 @@ impl<T> Dual<T>
 where
     T: Clone,
 {
-    pub fn grad(&self) -> Option<ArrayView1<T>> {
+    pub fn grad(&self) -> Option<ArrayView1<'_, T>> {
         Some(self.dx.view())
     }
 
-    pub fn grad_mut(&mut self) -> Option<ArrayViewMut1<T>> {
+    pub fn grad_mut(&mut self) -> Option<ArrayViewMut1<'_, T>> {
         Some(self.dx.view_mut())
     }
 }
@@ -94,6 +95,8 @@ impl<T> Into<T> for Dual<T>
 }
 */
 
+impl<T> ScalarOperand for Dual<T> where T: 'static + Clone {}
+
 impl From<Dual<f64>> for f64 {
     fn from(item: Dual<f64>) -> f64 {
         item.x
@@ -126,8 +129,7 @@ where
 }
 */
 
-mod ops;
-pub use self::ops::*;
+pub mod ops;
 
 #[cfg(test)]
 mod test {
@@ -142,4 +144,59 @@ mod test {
         let loss = &(&x + &y[0]) * &y[1];
         assert_eq!(loss.grad(), Some(array![10., 10., 1.].view()));
     }
+
+    #[test]
+    fn operator_surface() {
+        let mut vars = Variables::new(2);
+        let x = vars.gen_all(&[3., 2.]);
+
+        let q = &x[0] / &x[1];
+        assert_eq!(1.5, f64::from(q.clone()));
+        assert_eq!(q.grad(), Some(array![0.5, -0.75].view()));
+        let q = 6. / &x[1];
+        assert_eq!(q.grad(), Some(array![0., -1.5].view()));
+        assert_eq!((1. - x[0].clone()).grad(), Some(array![-1., 0.].view()));
+        let r = 7. % &x[1];
+        assert_eq!(r, 1.);
+        assert_eq!(r.grad(), Some(array![0., -3.].view()));
+        assert_eq!((&x[0] % &x[1]).grad(), Some(array![1., -1.].view()));
+
+        // the gradient of a shared copy is left alone
+        let mut z = x[0].clone();
+        z += &x[1];
+        z *= x[0].clone();
+        z -= 1.;
+        z /= 2.;
+        assert_eq!(z, 7.);
+        assert_eq!(z.grad(), Some(array![4., 1.5].view()));
+        assert_eq!(x[0].grad(), Some(array![1., 0.].view()));
+
+        let sum: Dual = x.iter().sum();
+        assert_eq!(sum.grad(), Some(array![1., 1.].view()));
+        let product: Dual = x.into_iter().product();
+        assert_eq!(product.grad(), Some(array![2., 3.].view()));
+        let empty: Dual = std::iter::empty::<Dual>().sum();
+        assert_eq!(empty.grad(), Some(Array1::zeros(0).view()));
+    }
+
+    #[test]
+    fn empty_gradient_is_constant() {
+        let mut vars = Variables::new(2);
+        let x = vars.gen_all(&[3., 2.]);
+        let empty: Dual = std::iter::empty::<Dual>().sum();
+        assert_eq!((&empty + &x[0]).grad(), Some(array![1., 0.].view()));
+        assert_eq!((&x[1] - &empty).grad(), Some(array![0., 1.].view()));
+        let one: Dual = std::iter::empty::<Dual>().product();
+        assert_eq!((&one * &x[1]).grad(), Some(array![0., 1.].view()));
+        assert_eq!((&x[0] / &one).grad(), Some(array![1., 0.].view()));
+        assert_eq!((&x[0] * &empty).grad(), Some(array![0., 0.].view()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn mismatched_gradients() {
+        let x = Variables::new(2).gen(1.).unwrap();
+        let y = Variables::new(3).gen(1.).unwrap();
+        let _ = x + y;
+    }
 }
@@ -1,8 +1,10 @@
 use ndarray::prelude::*;
-use ndarray::ScalarOperand;
-use num_traits::{One, Zero};
+use num_traits::{Num, One, Zero};
 use std::cmp::Ordering;
-use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::iter::{Product, Sum};
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign,
+};
 
 use super::Dual;
 
@@ -13,10 +15,6 @@ where
     fn eq(&self, other: &Self) -> bool {
         self.x.eq(&other.x)
     }
-
-    fn ne(&self, other: &Self) -> bool {
-        self.x.ne(&other.x)
-    }
 }
 
 impl<T> PartialEq<T> for Dual<T>
@@ -24,11 +22,7 @@ where
     T: PartialEq,
 {
     fn eq(&self, other: &T) -> bool {
-        self.x.eq(&other)
-    }
-
-    fn ne(&self, other: &T) -> bool {
-        self.x.ne(&other)
+        self.x.eq(other)
     }
 }
 
@@ -46,127 +40,411 @@ where
     T: PartialOrd,
 {
     fn partial_cmp(&self, other: &T) -> Option<Ordering> {
-        self.x.partial_cmp(&other)
+        self.x.partial_cmp(other)
     }
 }
 
-impl<T> Add<Self> for &Dual<T>
+// The compound assignments update the gradient in place, copying it first only if it is
+// shared with another dual number. The binary operators are built on them.
+
+impl<T> Dual<T>
 where
-    T: Zero + Add + Copy,
+    T: Zero + Clone,
 {
-    type Output = Dual<T>;
-    fn add(self, rhs: Self) -> Self::Output {
-        Dual::<T> {
-            x: self.x + rhs.x,
-            dx: ArcArray::from(&self.dx + &rhs.dx),
+    /// Brings the gradients of `self` and `rhs` to the same length, where an empty one, as of
+    /// the sum or the product of nothing, stands for a constant. Returns the derivatives of
+    /// `rhs`, or `None` if it is such a constant.
+    ///
+    /// # Panics
+    /// If both gradients are non-empty and differ in length.
+    fn align<'a>(&mut self, rhs: &'a Self) -> Option<&'a ArcArray<T, Ix1>> {
+        if rhs.dx.is_empty() {
+            return None;
         }
+        if self.dx.is_empty() {
+            self.dx = ArcArray::zeros(rhs.dx.len());
+        }
+        assert_eq!(
+            self.dx.len(),
+            rhs.dx.len(),
+            "dual numbers with different numbers of variables"
+        );
+        Some(&rhs.dx)
     }
 }
 
-impl<T> Add<T> for &Dual<T>
+impl<T> AddAssign<&Self> for Dual<T>
 where
-    T: Zero + Add + Copy,
+    T: Zero + Add<Output = T> + Copy,
 {
-    type Output = Dual<T>;
-    fn add(self, rhs: T) -> Self::Output {
-        Self::Output {
-            x: self.x + rhs,
-            dx: self.dx.clone(),
+    fn add_assign(&mut self, rhs: &Self) {
+        if let Some(rhs_dx) = self.align(rhs) {
+            for (dst, &src) in self.dx.iter_mut().zip(rhs_dx.iter()) {
+                *dst = *dst + src;
+            }
         }
+        self.x = self.x + rhs.x;
     }
 }
 
-impl<T> Sub<Self> for Dual<T>
+impl<T> AddAssign<T> for Dual<T>
 where
-    T: Zero + Sub<Output = T> + Clone,
+    T: Add<Output = T> + Copy,
 {
-    type Output = Self;
-    fn sub(self, rhs: Self) -> Self::Output {
-        Self {
-            x: self.x - rhs.x,
-            dx: ArcArray::from(&self.dx - &rhs.dx),
+    fn add_assign(&mut self, rhs: T) {
+        self.x = self.x + rhs;
+    }
+}
+
+impl<T> SubAssign<&Self> for Dual<T>
+where
+    T: Zero + Sub<Output = T> + Copy,
+{
+    fn sub_assign(&mut self, rhs: &Self) {
+        if let Some(rhs_dx) = self.align(rhs) {
+            for (dst, &src) in self.dx.iter_mut().zip(rhs_dx.iter()) {
+                *dst = *dst - src;
+            }
         }
+        self.x = self.x - rhs.x;
     }
 }
 
-impl<T> Sub<T> for Dual<T>
+impl<T> SubAssign<T> for Dual<T>
 where
-    T: Zero + Sub<Output = T> + Clone,
+    T: Sub<Output = T> + Copy,
 {
-    type Output = Self;
-    fn sub(self, rhs: T) -> Self::Output {
-        Self {
-            x: self.x - rhs,
-            dx: self.dx,
+    fn sub_assign(&mut self, rhs: T) {
+        self.x = self.x - rhs;
+    }
+}
+
+impl<T> MulAssign<&Self> for Dual<T>
+where
+    T: Zero + Add<Output = T> + Mul<Output = T> + Copy,
+{
+    fn mul_assign(&mut self, rhs: &Self) {
+        let (x, y) = (self.x, rhs.x);
+        match self.align(rhs) {
+            Some(rhs_dx) => {
+                for (dst, &src) in self.dx.iter_mut().zip(rhs_dx.iter()) {
+                    *dst = *dst * y + src * x;
+                }
+            }
+            None => {
+                for dst in self.dx.iter_mut() {
+                    *dst = *dst * y;
+                }
+            }
         }
+        self.x = x * y;
     }
 }
 
-impl<T> Neg for Dual<T>
+impl<T> MulAssign<T> for Dual<T>
 where
-    T: Zero + Neg<Output = T> + Sub<Output = T> + Clone,
+    T: Mul<Output = T> + Copy,
 {
-    type Output = Self;
-    fn neg(self) -> Self::Output {
-        Self {
-            x: -self.x,
-            dx: ArcArray::from(-self.dx.to_owned()),
+    fn mul_assign(&mut self, rhs: T) {
+        for dst in self.dx.iter_mut() {
+            *dst = *dst * rhs;
         }
+        self.x = self.x * rhs;
     }
 }
 
-impl<T> Mul<Self> for &Dual<T>
+impl<T> DivAssign<&Self> for Dual<T>
 where
-    T: Zero + One + Add + Mul + ScalarOperand + Copy,
+    T: Zero + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + Copy,
 {
-    type Output = Dual<T>;
-    fn mul(self, rhs: Self) -> Self::Output {
-        Self::Output {
-            x: self.x.clone() * rhs.x.clone(),
-            dx: ArcArray::from(&self.dx * rhs.x + &rhs.dx * self.x),
+    fn div_assign(&mut self, rhs: &Self) {
+        let (x, y) = (self.x, rhs.x);
+        match self.align(rhs) {
+            Some(rhs_dx) => {
+                for (dst, &src) in self.dx.iter_mut().zip(rhs_dx.iter()) {
+                    *dst = (*dst * y - src * x) / (y * y);
+                }
+            }
+            None => {
+                for dst in self.dx.iter_mut() {
+                    *dst = *dst / y;
+                }
+            }
         }
+        self.x = x / y;
     }
 }
 
-impl<T> Mul<T> for &Dual<T>
+impl<T> DivAssign<T> for Dual<T>
 where
-    T: Zero + One + Add + Mul + ScalarOperand + Copy,
+    T: Div<Output = T> + Copy,
 {
-    type Output = Dual<T>;
-    fn mul(self, rhs: T) -> Self::Output {
-        Self::Output {
-            x: self.x * rhs.clone(),
-            dx: ArcArray::from(&self.dx * rhs),
+    fn div_assign(&mut self, rhs: T) {
+        for dst in self.dx.iter_mut() {
+            *dst = *dst / rhs;
         }
+        self.x = self.x / rhs;
     }
 }
 
-impl<T> Div<Self> for Dual<T>
+impl<T> RemAssign<&Self> for Dual<T>
 where
-    T: Zero + One + Add + Sub<Output = T> + Mul + Div<Output = T> + ScalarOperand + Clone,
+    T: Num + Copy,
 {
-    type Output = Self;
-    fn div(self, rhs: Self) -> Self::Output {
-        Self {
-            dx: ArcArray::from(
-                &self.dx * rhs.x.clone()
-                    - &rhs.dx * self.x.clone() / (rhs.x.clone() * rhs.x.clone()),
-            ),
-            x: self.x / rhs.x,
+    fn rem_assign(&mut self, rhs: &Self) {
+        let r = self.x % rhs.x;
+        // x % y = x - q * y with the integer-valued quotient q held constant
+        let q = (self.x - r) / rhs.x;
+        if let Some(rhs_dx) = self.align(rhs) {
+            for (dst, &src) in self.dx.iter_mut().zip(rhs_dx.iter()) {
+                *dst = *dst - q * src;
+            }
         }
+        self.x = r;
     }
 }
 
-impl<T> Div<T> for Dual<T>
+impl<T> RemAssign<T> for Dual<T>
+where
+    T: Num + Copy,
+{
+    fn rem_assign(&mut self, rhs: T) {
+        self.x = self.x % rhs;
+    }
+}
+
+macro_rules! binop {
+    ($imp:ident, $method:ident, $assign:ident, $assign_method:ident) => {
+        impl<T> $assign<Self> for Dual<T>
+        where
+            for<'a> Dual<T>: $assign<&'a Dual<T>>,
+        {
+            #[inline]
+            fn $assign_method(&mut self, rhs: Self) {
+                $assign::$assign_method(self, &rhs);
+            }
+        }
+
+        impl<T> $imp<&Self> for Dual<T>
+        where
+            for<'a> Dual<T>: $assign<&'a Dual<T>>,
+        {
+            type Output = Dual<T>;
+            #[inline]
+            fn $method(mut self, rhs: &Self) -> Self::Output {
+                $assign::$assign_method(&mut self, rhs);
+                self
+            }
+        }
+
+        impl<T> $imp<Self> for Dual<T>
+        where
+            for<'a> Dual<T>: $assign<&'a Dual<T>>,
+        {
+            type Output = Dual<T>;
+            #[inline]
+            fn $method(mut self, rhs: Self) -> Self::Output {
+                $assign::$assign_method(&mut self, &rhs);
+                self
+            }
+        }
+
+        impl<'a, T> $imp<&'a Dual<T>> for &Dual<T>
+        where
+            T: Clone,
+            for<'b> Dual<T>: $assign<&'b Dual<T>>,
+        {
+            type Output = Dual<T>;
+            #[inline]
+            fn $method(self, rhs: &'a Dual<T>) -> Self::Output {
+                $imp::$method(self.clone(), rhs)
+            }
+        }
+
+        impl<T> $imp<Dual<T>> for &Dual<T>
+        where
+            T: Clone,
+            for<'a> Dual<T>: $assign<&'a Dual<T>>,
+        {
+            type Output = Dual<T>;
+            #[inline]
+            fn $method(self, rhs: Dual<T>) -> Self::Output {
+                $imp::$method(self.clone(), &rhs)
+            }
+        }
+
+        impl<T> $imp<T> for Dual<T>
+        where
+            Dual<T>: $assign<T>,
+        {
+            type Output = Dual<T>;
+            #[inline]
+            fn $method(mut self, rhs: T) -> Self::Output {
+                $assign::$assign_method(&mut self, rhs);
+                self
+            }
+        }
+
+        impl<T> $imp<T> for &Dual<T>
+        where
+            T: Clone,
+            Dual<T>: $assign<T>,
+        {
+            type Output = Dual<T>;
+            #[inline]
+            fn $method(self, rhs: T) -> Self::Output {
+                $imp::$method(self.clone(), rhs)
+            }
+        }
+    };
+}
+
+binop!(Add, add, AddAssign, add_assign);
+binop!(Sub, sub, SubAssign, sub_assign);
+binop!(Mul, mul, MulAssign, mul_assign);
+binop!(Div, div, DivAssign, div_assign);
+binop!(Rem, rem, RemAssign, rem_assign);
+
+impl<T> Neg for Dual<T>
 where
-    T: Zero + One + Add + Sub<Output = T> + Mul + Div<Output = T> + ScalarOperand + Clone,
+    T: Neg<Output = T> + Copy,
 {
     type Output = Self;
-    fn div(self, rhs: T) -> Self::Output {
-        Self {
-            dx: ArcArray::from(&self.dx / rhs.clone()),
-            x: self.x / rhs,
+    fn neg(mut self) -> Self::Output {
+        for dst in self.dx.iter_mut() {
+            *dst = -*dst;
+        }
+        self.x = -self.x;
+        self
+    }
+}
+
+impl<T> Neg for &Dual<T>
+where
+    T: Neg<Output = T> + Copy,
+{
+    type Output = Dual<T>;
+    fn neg(self) -> Self::Output {
+        -self.clone()
+    }
+}
+
+/// `f32` and `f64` on the left of the arithmetic operators, which the generic impls above
+/// cannot cover.
+macro_rules! scalar_lhs {
+    ($($t:ty),*) => {
+        $(
+            impl Add<Dual<$t>> for $t {
+                type Output = Dual<$t>;
+                #[inline]
+                fn add(self, rhs: Dual<$t>) -> Self::Output {
+                    rhs + self
+                }
+            }
+
+            impl Sub<Dual<$t>> for $t {
+                type Output = Dual<$t>;
+                #[inline]
+                fn sub(self, rhs: Dual<$t>) -> Self::Output {
+                    -rhs + self
+                }
+            }
+
+            impl Mul<Dual<$t>> for $t {
+                type Output = Dual<$t>;
+                #[inline]
+                fn mul(self, rhs: Dual<$t>) -> Self::Output {
+                    rhs * self
+                }
+            }
+
+            impl Div<Dual<$t>> for $t {
+                type Output = Dual<$t>;
+                fn div(self, mut rhs: Dual<$t>) -> Self::Output {
+                    let x = rhs.x;
+                    for dst in rhs.dx.iter_mut() {
+                        *dst = -self * *dst / (x * x);
+                    }
+                    rhs.x = self / x;
+                    rhs
+                }
+            }
+
+            impl Rem<Dual<$t>> for $t {
+                type Output = Dual<$t>;
+                fn rem(self, mut rhs: Dual<$t>) -> Self::Output {
+                    let r = self % rhs.x;
+                    let q = (self - r) / rhs.x;
+                    for dst in rhs.dx.iter_mut() {
+                        *dst *= -q;
+                    }
+                    rhs.x = r;
+                    rhs
+                }
+            }
+
+            scalar_lhs!(@ref $t, Add, add);
+            scalar_lhs!(@ref $t, Sub, sub);
+            scalar_lhs!(@ref $t, Mul, mul);
+            scalar_lhs!(@ref $t, Div, div);
+            scalar_lhs!(@ref $t, Rem, rem);
+        )*
+    };
+    (@ref $t:ty, $imp:ident, $method:ident) => {
+        impl $imp<&Dual<$t>> for $t {
+            type Output = Dual<$t>;
+            #[inline]
+            fn $method(self, rhs: &Dual<$t>) -> Self::Output {
+                $imp::$method(self, rhs.clone())
+            }
         }
+    };
+}
+
+scalar_lhs!(f32, f64);
+
+// The number of variables is not known without an element, so the sum and the product of
+// nothing are constants without derivatives, which the arithmetic above treats as zeros of
+// the other operand's length.
+
+impl<T> Sum for Dual<T>
+where
+    T: Zero + Add<Output = T> + Copy,
+{
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.reduce(|acc, x| acc + &x).unwrap_or_else(|| Dual {
+            x: T::zero(),
+            dx: ArcArray::zeros(0),
+        })
     }
 }
 
+impl<'a, T> Sum<&'a Dual<T>> for Dual<T>
+where
+    T: Zero + Add<Output = T> + Copy + 'a,
+{
+    fn sum<I: Iterator<Item = &'a Dual<T>>>(iter: I) -> Self {
+        iter.cloned().sum()
+    }
+}
+
+impl<T> Product for Dual<T>
+where
+    T: Zero + One + Add<Output = T> + Mul<Output = T> + Copy,
+{
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.reduce(|acc, x| acc * &x).unwrap_or_else(|| Dual {
+            x: T::one(),
+            dx: ArcArray::zeros(0),
+        })
+    }
+}
+
+impl<'a, T> Product<&'a Dual<T>> for Dual<T>
+where
+    T: Zero + One + Add<Output = T> + Mul<Output = T> + Copy + 'a,
+{
+    fn product<I: Iterator<Item = &'a Dual<T>>>(iter: I) -> Self {
+        iter.cloned().product()
+    }
+}
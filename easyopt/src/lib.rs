@@ -4,6 +4,12 @@ pub mod executor;
 pub mod monitor;
 pub mod traits;
 
+mod linalg;
+
+pub mod continuation;
+pub mod dual_ndarray;
+pub mod minimize;
+pub mod scalar;
 pub mod self_consistent;
 
 #[cfg(test)]
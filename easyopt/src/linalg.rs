@@ -0,0 +1,173 @@
+use ndarray::prelude::*;
+
+/// Eigen decomposition of a symmetric matrix by the cyclic Jacobi method.
+///
+/// Returns the eigenvalues and a matrix whose columns are the corresponding eigenvectors.
+pub fn symmetric_eigen(a: &Array2<f64>) -> (Array1<f64>, Array2<f64>) {
+    let n = a.nrows();
+    let mut a = a.clone();
+    let mut v = Array2::<f64>::eye(n);
+    for _sweep in 0..100 {
+        let off: f64 = (0..n)
+            .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[[i, j]] * a[[i, j]])
+            .sum();
+        let scale: f64 = a.iter().map(|x| x * x).sum();
+        if off <= f64::EPSILON * f64::EPSILON * scale {
+            break;
+        }
+        for p in 0..n {
+            for q in (p + 1)..n {
+                if a[[p, q]] == 0. {
+                    continue;
+                }
+                let theta = (a[[q, q]] - a[[p, p]]) / (2. * a[[p, q]]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.).sqrt());
+                let c = 1. / (t * t + 1.).sqrt();
+                let s = t * c;
+                for k in 0..n {
+                    let akp = a[[k, p]];
+                    let akq = a[[k, q]];
+                    a[[k, p]] = c * akp - s * akq;
+                    a[[k, q]] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let apk = a[[p, k]];
+                    let aqk = a[[q, k]];
+                    a[[p, k]] = c * apk - s * aqk;
+                    a[[q, k]] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let vkp = v[[k, p]];
+                    let vkq = v[[k, q]];
+                    v[[k, p]] = c * vkp - s * vkq;
+                    v[[k, q]] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    (a.diag().to_owned(), v)
+}
+
+/// Cholesky factor `L` with `a = L L^T`, or `None` if `a` is not positive definite.
+pub fn cholesky(a: &Array2<f64>) -> Option<Array2<f64>> {
+    let n = a.nrows();
+    let mut l = Array2::<f64>::zeros((n, n));
+    for j in 0..n {
+        let d = a[[j, j]] - (0..j).map(|k| l[[j, k]] * l[[j, k]]).sum::<f64>();
+        if d <= 0. || !d.is_finite() {
+            return None;
+        }
+        l[[j, j]] = d.sqrt();
+        for i in (j + 1)..n {
+            let s = a[[i, j]] - (0..j).map(|k| l[[i, k]] * l[[j, k]]).sum::<f64>();
+            l[[i, j]] = s / l[[j, j]];
+        }
+    }
+    Some(l)
+}
+
+/// Solves `L L^T x = b` for a Cholesky factor `L`.
+pub fn cholesky_solve(l: &Array2<f64>, b: &Array1<f64>) -> Array1<f64> {
+    let n = l.nrows();
+    let mut y = b.clone();
+    for i in 0..n {
+        for k in 0..i {
+            y[i] -= l[[i, k]] * y[k];
+        }
+        y[i] /= l[[i, i]];
+    }
+    for i in (0..n).rev() {
+        for k in (i + 1)..n {
+            y[i] -= l[[k, i]] * y[k];
+        }
+        y[i] /= l[[i, i]];
+    }
+    y
+}
+
+/// Thin QR decomposition of a matrix with at least as many rows as columns by modified
+/// Gram-Schmidt.
+///
+/// Returns `None` when a column is numerically dependent on the previous ones, judged by
+/// `rcond` relative to the largest column norm.
+pub fn qr(a: &Array2<f64>, rcond: f64) -> Option<(Array2<f64>, Array2<f64>)> {
+    let (m, n) = a.dim();
+    let scale = a
+        .columns()
+        .into_iter()
+        .map(|c| c.dot(&c).sqrt())
+        .fold(0f64, f64::max);
+    if m < n || scale == 0. {
+        return None;
+    }
+    let mut q = a.clone();
+    let mut r = Array2::<f64>::zeros((n, n));
+    for j in 0..n {
+        for i in 0..j {
+            let rij = q.column(i).dot(&q.column(j));
+            r[[i, j]] = rij;
+            let qi = q.column(i).to_owned();
+            q.column_mut(j).scaled_add(-rij, &qi);
+        }
+        let norm = q.column(j).dot(&q.column(j)).sqrt();
+        if norm <= rcond * scale {
+            return None;
+        }
+        r[[j, j]] = norm;
+        q.column_mut(j).mapv_inplace(|v| v / norm);
+    }
+    Some((q, r))
+}
+
+/// Solves `R x = b` for an upper triangular `R`.
+pub fn solve_upper(r: &Array2<f64>, b: &Array1<f64>) -> Array1<f64> {
+    let n = r.nrows();
+    let mut x = b.clone();
+    for i in (0..n).rev() {
+        for k in (i + 1)..n {
+            x[i] -= r[[i, k]] * x[k];
+        }
+        x[i] /= r[[i, i]];
+    }
+    x
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn eigen_reconstructs_matrix() {
+        let a = array![[4., 1., 2.], [1., 3., 0.], [2., 0., 5.]];
+        let (w, v) = symmetric_eigen(&a);
+        let b = v.dot(&Array2::from_diag(&w)).dot(&v.t());
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert_abs_diff_eq!(x, y, epsilon = 1e-10);
+        }
+    }
+
+    #[test]
+    fn cholesky_solves_system() {
+        let a = array![[4., 1., 2.], [1., 3., 0.], [2., 0., 5.]];
+        let b = array![1., -2., 3.];
+        let x = cholesky_solve(&cholesky(&a).unwrap(), &b);
+        for (x, y) in a.dot(&x).iter().zip(b.iter()) {
+            assert_abs_diff_eq!(x, y, epsilon = 1e-12);
+        }
+        assert!(cholesky(&array![[1., 2.], [2., 1.]]).is_none());
+    }
+
+    #[test]
+    fn qr_least_squares() {
+        let a = array![[1., 1.], [1., 2.], [1., 3.]];
+        let b = array![1., 2., 2.];
+        let (q, r) = qr(&a, 1e-12).unwrap();
+        let x = solve_upper(&r, &q.t().dot(&b));
+        // normal equations of the line fit
+        assert_abs_diff_eq!(x[0], 2. / 3., epsilon = 1e-12);
+        assert_abs_diff_eq!(x[1], 0.5, epsilon = 1e-12);
+        assert!(qr(&array![[1., 2.], [2., 4.]], 1e-12).is_none());
+    }
+}
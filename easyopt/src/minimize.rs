@@ -0,0 +1,321 @@
+pub use crate::criteria::*;
+pub use crate::error::*;
+pub use crate::executor::*;
+pub use crate::monitor;
+pub use crate::traits::*;
+
+use dual::{Dual, SparseDual, Variables};
+use ndarray::prelude::*;
+
+pub trait CostFunction {
+    fn cost(&self, x: &Array1<f64>) -> Result<f64, Error>;
+}
+
+impl<F> CostFunction for F
+where
+    F: Fn(&Array1<f64>) -> f64,
+{
+    fn cost(&self, x: &Array1<f64>) -> Result<f64, Error> {
+        Ok(self(x))
+    }
+}
+
+/// Cost function written once for any float type, so that its gradient and Hessian can be
+/// obtained with `dual`.
+pub trait DifferentiableCost {
+    fn cost<T: num_traits::Float>(&self, x: &[T]) -> Result<T, Error>;
+}
+
+impl<F> DifferentiableCost for &F
+where
+    F: DifferentiableCost + ?Sized,
+{
+    fn cost<T: num_traits::Float>(&self, x: &[T]) -> Result<T, Error> {
+        (**self).cost(x)
+    }
+}
+
+/// Marks a cost function as a minimization problem so that it can be passed to `Executor`.
+///
+/// Derivative-free solvers take a `CostFunction`, the others a `DifferentiableCost`.
+pub struct Minimize<T>(pub T);
+
+impl<T> Op for Minimize<T> {
+    type Variable = Array1<f64>;
+}
+
+fn check_dimension<const N: usize>(x: &Array1<f64>) -> Result<(), Error> {
+    if x.len() == N {
+        Ok(())
+    } else {
+        Err(Error::Failure(format!(
+            "the variable has {} components but derivatives are taken for {}",
+            x.len(),
+            N
+        )))
+    }
+}
+
+/// Value and gradient of `f` at `x` by forward-mode differentiation with `Dual<f64, N>`.
+pub fn gradient<F, const N: usize>(f: &F, x: &Array1<f64>) -> Result<(f64, Array1<f64>), Error>
+where
+    F: DifferentiableCost + ?Sized,
+{
+    check_dimension::<N>(x)?;
+    let vars = Variables::<f64, N>::new().gen_all(&x.to_vec());
+    let y = f.cost(&vars)?;
+    Ok((*y.val(), y.grad().to_owned()))
+}
+
+/// Value and gradient of `f` at `x` of any dimension, by forward passes with
+/// `Dual<f64, CHUNK>` differentiating `CHUNK` components at a time.
+///
+/// Takes `ceil(n / CHUNK)` evaluations of `f` where `gradient` takes one, but the dimension
+/// need not be known at compile time. Chunks of 8 or so keep each pass cheap.
+///
+/// # Panics
+/// If `CHUNK` is zero.
+pub fn chunked_gradient<F, const CHUNK: usize>(
+    f: &F,
+    x: &Array1<f64>,
+) -> Result<(f64, Array1<f64>), Error>
+where
+    F: DifferentiableCost + ?Sized,
+{
+    assert!(CHUNK > 0);
+    let n = x.len();
+    let values = x.to_vec();
+    let vars = Variables::<f64, CHUNK>::new();
+    let mut value = f64::NAN;
+    let mut grad = Array1::zeros(n);
+    for offset in (0..n.div_ceil(CHUNK).max(1)).map(|k| k * CHUNK) {
+        let y = f.cost(&vars.chunk(&values, offset))?;
+        value = *y.val();
+        let len = CHUNK.min(n - offset);
+        grad.slice_mut(s![offset..offset + len])
+            .assign(&y.grad().slice(s![..len]));
+    }
+    Ok((value, grad))
+}
+
+/// Cost function written against `SparseDual<f64>`.
+///
+/// `SparseDual` is not `num_traits::Float`, so a `DifferentiableCost` cannot be evaluated on
+/// it and the cost has to be spelled out with its operators and inherent methods.
+pub trait SparseCost {
+    fn cost(&self, x: &[SparseDual<f64>]) -> Result<SparseDual<f64>, Error>;
+}
+
+/// Value and gradient of `f` at `x` of any dimension in a single pass with `SparseDual`.
+///
+/// Pays off when each intermediate depends on few of the variables, as in sums of terms
+/// coupling a few neighbours: on such a banded cost it overtakes `Dual<f64, N>` from about
+/// `N = 128` (see the `sparse` benchmark of `dual`), and `chunked_gradient` needs
+/// `ceil(n / CHUNK)` passes where this takes one.
+pub fn sparse_gradient<F>(f: &F, x: &Array1<f64>) -> Result<(f64, Array1<f64>), Error>
+where
+    F: SparseCost + ?Sized,
+{
+    let y = f.cost(&SparseDual::variables(&x.to_vec()))?;
+    if y.grad().last().is_some_and(|&(i, _)| i >= x.len()) {
+        return Err(Error::InvalidVariable);
+    }
+    Ok((*y.val(), y.dense_grad(x.len())))
+}
+
+/// Value, gradient and Hessian of `f` at `x` with nested dual numbers `Dual<Dual<f64, N>, N>`.
+pub fn hessian<F, const N: usize>(
+    f: &F,
+    x: &Array1<f64>,
+) -> Result<(f64, Array1<f64>, Array2<f64>), Error>
+where
+    F: DifferentiableCost + ?Sized,
+{
+    check_dimension::<N>(x)?;
+    let inner = Variables::<f64, N>::new().gen_all(&x.to_vec());
+    let vars = Variables::<Dual<f64, N>, N>::new().gen_all(&inner);
+    let y = f.cost(&vars)?;
+    let hess = Array2::from_shape_fn((N, N), |(i, j)| y.grad()[i].grad()[j]);
+    Ok((*y.val().val(), y.val().grad().to_owned(), hess))
+}
+
+/// Value, gradient and Hessian-vector product `H v` of `f` at `x`, without forming `H`.
+///
+/// The gradient is carried by `Dual<f64, N>` and differentiated along `v` by an outer
+/// single-direction dual, i.e. `Dual<Dual<f64, N>, 1>`.
+pub fn hessian_vector<F, const N: usize>(
+    f: &F,
+    x: &Array1<f64>,
+    v: &Array1<f64>,
+) -> Result<(f64, Array1<f64>, Array1<f64>), Error>
+where
+    F: DifferentiableCost + ?Sized,
+{
+    check_dimension::<N>(x)?;
+    check_dimension::<N>(v)?;
+    let inner = Variables::<f64, N>::new().gen_all(&x.to_vec());
+    let outer = Variables::<Dual<f64, N>, 1>::new();
+    let direction = Variables::<f64, N>::new();
+    let vars: Vec<_> = inner
+        .iter()
+        .zip(v.iter())
+        .map(|(&xi, &vi)| {
+            let mut d = outer.constant(xi);
+            d.grad_mut()[0] = direction.constant(vi);
+            d
+        })
+        .collect();
+    let y = f.cost(&vars)?;
+    Ok((
+        *y.val().val(),
+        y.val().grad().to_owned(),
+        y.grad()[0].grad().to_owned(),
+    ))
+}
+
+/// Value, gradient and Hessian-vector product `H v` of `f` at `x` of any dimension, with the
+/// inner dual of `hessian_vector` taken `CHUNK` components at a time as in
+/// `chunked_gradient`.
+///
+/// Each product takes `ceil(n / CHUNK)` evaluations of `f` on `Dual<Dual<f64, CHUNK>, 1>`, so
+/// its cost still grows with `n` evaluations' worth of work, but the numbers stay small and
+/// the dimension need not be known at compile time.
+///
+/// # Panics
+/// If `CHUNK` is zero.
+pub fn chunked_hessian_vector<F, const CHUNK: usize>(
+    f: &F,
+    x: &Array1<f64>,
+    v: &Array1<f64>,
+) -> Result<(f64, Array1<f64>, Array1<f64>), Error>
+where
+    F: DifferentiableCost + ?Sized,
+{
+    assert!(CHUNK > 0);
+    let n = x.len();
+    if v.len() != n {
+        return Err(Error::Failure(format!(
+            "the direction has {} components but the variable has {}",
+            v.len(),
+            n
+        )));
+    }
+    let values = x.to_vec();
+    let inner = Variables::<f64, CHUNK>::new();
+    let outer = Variables::<Dual<f64, CHUNK>, 1>::new();
+    let mut value = f64::NAN;
+    let mut grad = Array1::zeros(n);
+    let mut hv = Array1::zeros(n);
+    for offset in (0..n.div_ceil(CHUNK).max(1)).map(|k| k * CHUNK) {
+        let vars: Vec<_> = inner
+            .chunk(&values, offset)
+            .into_iter()
+            .zip(v.iter())
+            .map(|(xi, &vi)| {
+                let mut d = outer.constant(xi);
+                d.grad_mut()[0] = inner.constant(vi);
+                d
+            })
+            .collect();
+        let y = f.cost(&vars)?;
+        value = *y.val().val();
+        let len = CHUNK.min(n - offset);
+        grad.slice_mut(s![offset..offset + len])
+            .assign(&y.val().grad().slice(s![..len]));
+        hv.slice_mut(s![offset..offset + len])
+            .assign(&y.grad()[0].grad().slice(s![..len]));
+    }
+    Ok((value, grad, hv))
+}
+
+pub mod bounded;
+pub mod check;
+pub mod constrained;
+pub mod multistart;
+pub mod newton_cg;
+pub mod population;
+pub mod proximal;
+pub mod stochastic;
+pub mod trust_region;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use num_traits::Float;
+
+    /// `sum 100 (x_{i+1} - x_i^2)^2 + (1 - x_i)^2`.
+    struct ChainedRosenbrock;
+
+    impl DifferentiableCost for ChainedRosenbrock {
+        fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+            let hundred = T::from(100.).unwrap();
+            Ok(x.windows(2).fold(T::zero(), |acc, w| {
+                acc + hundred * (w[1] - w[0] * w[0]).powi(2) + (T::one() - w[0]).powi(2)
+            }))
+        }
+    }
+
+    #[test]
+    fn chunked_against_full_gradient() -> anyhow::Result<()> {
+        let x = Array1::from_shape_fn(19, |i| (i as f64 * 0.7).sin());
+        let (value, full) = gradient::<_, 19>(&ChainedRosenbrock, &x)?;
+        for (v, g) in [
+            chunked_gradient::<_, 1>(&ChainedRosenbrock, &x)?,
+            chunked_gradient::<_, 8>(&ChainedRosenbrock, &x)?,
+            chunked_gradient::<_, 19>(&ChainedRosenbrock, &x)?,
+            chunked_gradient::<_, 32>(&ChainedRosenbrock, &x)?,
+        ] {
+            assert_eq!(value, v);
+            assert_eq!(full, g);
+        }
+        let (v, g) = chunked_gradient::<_, 4>(&ChainedRosenbrock, &Array1::zeros(0))?;
+        assert_eq!(0., v);
+        assert!(g.is_empty());
+        Ok(())
+    }
+
+    impl SparseCost for ChainedRosenbrock {
+        fn cost(&self, x: &[SparseDual<f64>]) -> Result<SparseDual<f64>, Error> {
+            Ok(x.windows(2).fold(SparseDual::constant(0.), |acc, w| {
+                let a = &w[1] - &w[0] * &w[0];
+                acc + &a * &a * 100. + (&w[0] - 1.).powi(2)
+            }))
+        }
+    }
+
+    #[test]
+    fn sparse_against_full_gradient() -> anyhow::Result<()> {
+        let x = Array1::from_shape_fn(19, |i| (i as f64 * 0.7).sin());
+        let (value, full) = gradient::<_, 19>(&ChainedRosenbrock, &x)?;
+        let (v, g) = sparse_gradient(&ChainedRosenbrock, &x)?;
+        assert_abs_diff_eq!(value, v, epsilon = 1e-12);
+        for (a, b) in full.iter().zip(g.iter()) {
+            assert_abs_diff_eq!(*a, *b, epsilon = 1e-10);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn chunked_against_full_hessian_vector() -> anyhow::Result<()> {
+        let x = Array1::from_shape_fn(19, |i| (i as f64 * 0.7).sin());
+        let v = Array1::from_shape_fn(19, |i| (i as f64 * 1.3).cos());
+        let (value, grad, hv) = hessian_vector::<_, 19>(&ChainedRosenbrock, &x, &v)?;
+        for (c, g, h) in [
+            chunked_hessian_vector::<_, 1>(&ChainedRosenbrock, &x, &v)?,
+            chunked_hessian_vector::<_, 8>(&ChainedRosenbrock, &x, &v)?,
+            chunked_hessian_vector::<_, 32>(&ChainedRosenbrock, &x, &v)?,
+        ] {
+            assert_eq!(value, c);
+            assert_eq!(grad, g);
+            assert_eq!(hv, h);
+        }
+        assert!(chunked_hessian_vector::<_, 4>(
+            &ChainedRosenbrock,
+            &x,
+            &v.slice(s![..3]).to_owned()
+        )
+        .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,649 @@
+use super::{gradient, DifferentiableCost, Minimize};
+use crate::error::*;
+use crate::linalg;
+use crate::traits::*;
+use ndarray::prelude::*;
+use ndarray::Zip;
+use serde::Serialize;
+
+/// Lower and upper bounds on each component of the variable.
+///
+/// Infinite bounds are allowed, so that `f64::NEG_INFINITY` and `f64::INFINITY` leave a
+/// component free.
+#[derive(Debug, Clone)]
+pub struct Bounds {
+    lower: Array1<f64>,
+    upper: Array1<f64>,
+}
+
+impl Bounds {
+    pub fn new(lower: Array1<f64>, upper: Array1<f64>) -> Self {
+        assert_eq!(lower.len(), upper.len());
+        assert!(lower.iter().zip(upper.iter()).all(|(l, u)| l <= u));
+        Self { lower, upper }
+    }
+
+    /// Bounds `[0, inf)` on every component.
+    pub fn nonnegative(n: usize) -> Self {
+        Self::new(Array1::zeros(n), Array1::from_elem(n, f64::INFINITY))
+    }
+
+    pub fn len(&self) -> usize {
+        self.lower.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lower.is_empty()
+    }
+
+    pub fn lower(&self) -> &Array1<f64> {
+        &self.lower
+    }
+
+    pub fn upper(&self) -> &Array1<f64> {
+        &self.upper
+    }
+
+    /// Closest point of the box to `x`.
+    pub fn project(&self, x: &Array1<f64>) -> Array1<f64> {
+        Zip::from(x)
+            .and(&self.lower)
+            .and(&self.upper)
+            .map_collect(|&x, &l, &u| x.max(l).min(u))
+    }
+
+    pub fn contains(&self, x: &Array1<f64>) -> bool {
+        Zip::from(x)
+            .and(&self.lower)
+            .and(&self.upper)
+            .fold(true, |acc, &x, &l, &u| acc && l <= x && x <= u)
+    }
+
+    /// Which bound, if any, each component of `x` lies on.
+    pub fn activity(&self, x: &Array1<f64>) -> Vec<Activity> {
+        Zip::from(x)
+            .and(&self.lower)
+            .and(&self.upper)
+            .map_collect(|&x, &l, &u| {
+                if x <= l {
+                    Activity::Lower
+                } else if x >= u {
+                    Activity::Upper
+                } else {
+                    Activity::Free
+                }
+            })
+            .to_vec()
+    }
+
+    /// Projected gradient `P(x - g) - x`, which vanishes at a stationary point in the box.
+    pub fn projected_gradient(&self, x: &Array1<f64>, g: &Array1<f64>) -> Array1<f64> {
+        self.project(&(x - g)) - x
+    }
+
+    fn check(&self, x: &Array1<f64>) -> Result<(), Error> {
+        if x.len() == self.len() {
+            Ok(())
+        } else {
+            Err(Error::Failure(format!(
+                "the variable has {} components but the bounds have {}",
+                x.len(),
+                self.len()
+            )))
+        }
+    }
+}
+
+/// State of a component with respect to its bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Activity {
+    Free,
+    Lower,
+    Upper,
+}
+
+/// Quantities describing the last iteration of a bounded solver.
+#[derive(Debug, Clone)]
+pub struct BoundedState {
+    pub cost: f64,
+    /// Infinity norm of the projected gradient.
+    pub proj_grad_norm: f64,
+    pub step_norm: f64,
+    /// Number of cost evaluations in the line search.
+    pub evaluations: usize,
+    pub active: Vec<Activity>,
+}
+
+impl BoundedState {
+    fn new(n: usize) -> Self {
+        Self {
+            cost: f64::NAN,
+            proj_grad_norm: f64::NAN,
+            step_norm: f64::NAN,
+            evaluations: 0,
+            active: vec![Activity::Free; n],
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct BoundedReport {
+    pub count: usize,
+    pub cost: f64,
+    pub proj_grad_norm: f64,
+    pub step_norm: f64,
+    pub evaluations: usize,
+    pub n_active: usize,
+    pub active: Vec<Activity>,
+}
+
+impl Report for BoundedReport {
+    type Arg = BoundedState;
+
+    fn init(&mut self, s: &BoundedState) -> Result<(), Error> {
+        *self = Self::default();
+        self.active = s.active.clone();
+        Ok(())
+    }
+
+    fn update(&mut self, s: &BoundedState) -> Result<(), Error> {
+        self.count += 1;
+        self.cost = s.cost;
+        self.proj_grad_norm = s.proj_grad_norm;
+        self.step_norm = s.step_norm;
+        self.evaluations = s.evaluations;
+        self.n_active = s.active.iter().filter(|&&a| a != Activity::Free).count();
+        self.active.clone_from(&s.active);
+        Ok(())
+    }
+}
+
+impl Default for BoundedReport {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            cost: f64::NAN,
+            proj_grad_norm: f64::NAN,
+            step_norm: f64::NAN,
+            evaluations: 0,
+            n_active: 0,
+            active: Vec::new(),
+        }
+    }
+}
+
+/// Cost and gradient at the current point.
+struct Model {
+    x: Array1<f64>,
+    cost: f64,
+    grad: Array1<f64>,
+}
+
+/// Shared bookkeeping of the bounded solvers: the cached gradient and the reported state.
+struct Common<const N: usize> {
+    bounds: Bounds,
+    current: Option<Model>,
+    state: BoundedState,
+}
+
+impl<const N: usize> Common<N> {
+    fn new(bounds: Bounds) -> Self {
+        let state = BoundedState::new(bounds.len());
+        Self {
+            bounds,
+            current: None,
+            state,
+        }
+    }
+
+    /// Projects `x` into the box and evaluates the gradient there unless it is cached.
+    fn prepare<F>(&mut self, f: &F, x: &Array1<f64>) -> Result<Array1<f64>, Error>
+    where
+        F: DifferentiableCost,
+    {
+        self.bounds.check(x)?;
+        let x = self.bounds.project(x);
+        if self.current.as_ref().is_none_or(|m| m.x != x) {
+            self.evaluate(f, x.clone())?;
+        }
+        Ok(x)
+    }
+
+    fn evaluate<F>(&mut self, f: &F, x: Array1<f64>) -> Result<(), Error>
+    where
+        F: DifferentiableCost,
+    {
+        let (cost, grad) = gradient::<F, N>(f, &x)?;
+        if !grad.iter().all(|g| g.is_finite()) {
+            return Err(Error::Failure(String::from(
+                "the gradient is not finite inside the bounds",
+            )));
+        }
+        let pg = self.bounds.projected_gradient(&x, &grad);
+        self.state.cost = cost;
+        self.state.proj_grad_norm = pg.iter().fold(0., |acc, v| v.abs().max(acc));
+        self.state.active = self.bounds.activity(&x);
+        self.current = Some(Model { x, cost, grad });
+        Ok(())
+    }
+
+    /// Backtracking along the path `t -> P(x + t d)` until the Armijo condition holds.
+    fn line_search<F>(
+        &mut self,
+        f: &F,
+        d: &Array1<f64>,
+        mut t: f64,
+    ) -> Result<Option<(f64, Array1<f64>)>, Error>
+    where
+        F: DifferentiableCost,
+    {
+        let Model { x, cost, grad } = self.current.as_ref().unwrap();
+        self.state.evaluations = 0;
+        for _ in 0..60 {
+            let trial = self.bounds.project(&(x + &(d * t)));
+            let step = &trial - x;
+            let decrease = grad.dot(&step);
+            if decrease >= 0. {
+                // the projected path is no longer a descent direction
+                return Ok(None);
+            }
+            self.state.evaluations += 1;
+            let accept = match f.cost::<f64>(&trial.to_vec()) {
+                Ok(v) => v <= cost + 1e-4 * decrease,
+                Err(Error::InvalidVariable) => false,
+                Err(e) => return Err(e),
+            };
+            if accept {
+                return Ok(Some((t, trial)));
+            }
+            t *= 0.5;
+        }
+        Ok(None)
+    }
+
+    /// Moves to `trial` and records the step.
+    fn accept<F>(&mut self, f: &F, trial: Array1<f64>) -> Result<Array1<f64>, Error>
+    where
+        F: DifferentiableCost,
+    {
+        let x = &self.current.as_ref().unwrap().x;
+        let step = &trial - x;
+        self.state.step_norm = step.dot(&step).sqrt();
+        self.evaluate(f, trial.clone())?;
+        Ok(trial)
+    }
+
+    fn stay(&mut self) -> Array1<f64> {
+        self.state.step_norm = 0.;
+        self.current.as_ref().unwrap().x.clone()
+    }
+}
+
+/// Projected gradient descent with an Armijo backtracking search along the projected path.
+///
+/// The trial step starts from twice the last accepted one.
+pub struct ProjectedGradient<const N: usize> {
+    common: Common<N>,
+    step: f64,
+}
+
+impl<const N: usize> ProjectedGradient<N> {
+    pub fn new(bounds: Bounds) -> Self {
+        Self {
+            common: Common::new(bounds),
+            step: 1.,
+        }
+    }
+
+    /// Initial trial step; 1 by default.
+    pub fn step(mut self, step: f64) -> Self {
+        self.step = step;
+        self
+    }
+}
+
+impl<F, const N: usize> Solver<Minimize<F>> for ProjectedGradient<N>
+where
+    F: DifferentiableCost,
+{
+    type ReportArg = BoundedState;
+
+    fn next_iter(&mut self, op: &Minimize<F>, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+        self.common.prepare(&op.0, x)?;
+        if self.common.state.proj_grad_norm == 0. {
+            return Ok(self.common.stay());
+        }
+        let d = -&self.common.current.as_ref().unwrap().grad;
+        match self.common.line_search(&op.0, &d, self.step)? {
+            Some((t, trial)) => {
+                self.step = 2. * t;
+                self.common.accept(&op.0, trial)
+            }
+            None => Ok(self.common.stay()),
+        }
+    }
+
+    fn init_report<R: Report<Arg = BoundedState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.init(&self.common.state)
+    }
+
+    fn update_report<R: Report<Arg = BoundedState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.update(&self.common.state)
+    }
+}
+
+/// Limited-memory BFGS with bounds (Byrd, Lu, Nocedal and Zhu).
+///
+/// Each iteration finds the generalized Cauchy point along the projected steepest-descent
+/// path, minimizes the quasi-Newton model over the variables left free there and searches
+/// along the direction to the result. The quasi-Newton matrix is kept dense, which suits the
+/// small dimensions handled by the const-generic gradient.
+pub struct LBfgsB<const N: usize> {
+    common: Common<N>,
+    memory: usize,
+    pairs: Vec<(Array1<f64>, Array1<f64>)>,
+}
+
+impl<const N: usize> LBfgsB<N> {
+    pub fn new(bounds: Bounds) -> Self {
+        Self {
+            common: Common::new(bounds),
+            memory: 10,
+            pairs: Vec::new(),
+        }
+    }
+
+    /// Number of correction pairs kept; 10 by default.
+    pub fn memory(mut self, m: usize) -> Self {
+        self.memory = m.max(1);
+        self
+    }
+
+    /// Dense limited-memory BFGS matrix built from the stored pairs.
+    fn hessian(&self, n: usize) -> Array2<f64> {
+        let theta = self.pairs.last().map_or(1., |(s, y)| y.dot(y) / s.dot(y));
+        let mut b = Array2::eye(n) * theta;
+        for (s, y) in self.pairs.iter() {
+            let bs = b.dot(s);
+            let sbs = s.dot(&bs);
+            let ys = y.dot(s);
+            for i in 0..n {
+                for j in 0..n {
+                    b[[i, j]] += y[i] * y[j] / ys - bs[i] * bs[j] / sbs;
+                }
+            }
+        }
+        b
+    }
+
+    /// Generalized Cauchy point: first local minimizer of the model along the projected
+    /// steepest-descent path.
+    fn cauchy_point(&self, x: &Array1<f64>, g: &Array1<f64>, b: &Array2<f64>) -> Array1<f64> {
+        let bounds = &self.common.bounds;
+        let n = x.len();
+        let breaks: Vec<f64> = (0..n)
+            .map(|i| {
+                if g[i] < 0. {
+                    (x[i] - bounds.upper[i]) / g[i]
+                } else if g[i] > 0. {
+                    (x[i] - bounds.lower[i]) / g[i]
+                } else {
+                    f64::INFINITY
+                }
+            })
+            .collect();
+        let mut d = Array1::from_shape_fn(n, |i| if breaks[i] > 0. { -g[i] } else { 0. });
+        let mut order: Vec<usize> = (0..n).filter(|&i| breaks[i] > 0.).collect();
+        order.sort_by(|&i, &j| breaks[i].total_cmp(&breaks[j]));
+
+        let mut xc = x.clone();
+        let mut t_prev = 0.;
+        let mut next = order.iter().peekable();
+        loop {
+            let t_next = next.peek().map_or(f64::INFINITY, |&&i| breaks[i]);
+            let bd = b.dot(&d);
+            let df = g.dot(&d) + bd.dot(&(&xc - x));
+            let ddf = d.dot(&bd);
+            if df >= 0. {
+                break;
+            }
+            let dt = if ddf > 0. { -df / ddf } else { f64::INFINITY };
+            if dt < t_next - t_prev {
+                xc.scaled_add(dt, &d);
+                break;
+            }
+            if t_next.is_infinite() {
+                // unbounded descent of the model along a free direction
+                break;
+            }
+            xc.scaled_add(t_next - t_prev, &d);
+            t_prev = t_next;
+            while let Some(&&i) = next.peek() {
+                if breaks[i] > t_next {
+                    break;
+                }
+                xc[i] = if d[i] > 0. {
+                    bounds.upper[i]
+                } else {
+                    bounds.lower[i]
+                };
+                d[i] = 0.;
+                next.next();
+            }
+        }
+        bounds.project(&xc)
+    }
+
+    /// Minimizes the model over the components free at the Cauchy point `xc` and truncates
+    /// the result to the box.
+    fn subspace_min(
+        &self,
+        x: &Array1<f64>,
+        g: &Array1<f64>,
+        b: &Array2<f64>,
+        xc: &Array1<f64>,
+    ) -> Array1<f64> {
+        let bounds = &self.common.bounds;
+        let free: Vec<usize> = (0..x.len())
+            .filter(|&i| xc[i] > bounds.lower[i] && xc[i] < bounds.upper[i])
+            .collect();
+        if free.is_empty() {
+            return xc.clone();
+        }
+        let r = g + &b.dot(&(xc - x));
+        let bff = Array2::from_shape_fn((free.len(), free.len()), |(i, j)| b[[free[i], free[j]]]);
+        let rf = Array1::from_shape_fn(free.len(), |i| r[free[i]]);
+        let du = match linalg::cholesky(&bff) {
+            Some(l) => -linalg::cholesky_solve(&l, &rf),
+            None => return xc.clone(),
+        };
+        let mut alpha = 1f64;
+        for (k, &i) in free.iter().enumerate() {
+            if du[k] > 0. {
+                alpha = alpha.min((bounds.upper[i] - xc[i]) / du[k]);
+            } else if du[k] < 0. {
+                alpha = alpha.min((bounds.lower[i] - xc[i]) / du[k]);
+            }
+        }
+        let mut xbar = xc.clone();
+        for (k, &i) in free.iter().enumerate() {
+            xbar[i] += alpha * du[k];
+        }
+        bounds.project(&xbar)
+    }
+}
+
+impl<F, const N: usize> Solver<Minimize<F>> for LBfgsB<N>
+where
+    F: DifferentiableCost,
+{
+    type ReportArg = BoundedState;
+
+    fn next_iter(&mut self, op: &Minimize<F>, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+        let x = self.common.prepare(&op.0, x)?;
+        if self.common.state.proj_grad_norm == 0. {
+            return Ok(self.common.stay());
+        }
+        let g = self.common.current.as_ref().unwrap().grad.clone();
+        let b = self.hessian(x.len());
+        let xc = self.cauchy_point(&x, &g, &b);
+        let d = self.subspace_min(&x, &g, &b, &xc) - &x;
+
+        let found = if g.dot(&d) < 0. {
+            self.common.line_search(&op.0, &d, 1.)?
+        } else {
+            None
+        };
+        let trial = match found {
+            Some((_, trial)) => trial,
+            None if !self.pairs.is_empty() => {
+                // the memory gives no descent; restart from steepest descent
+                self.pairs.clear();
+                return self.next_iter(op, &x);
+            }
+            None => return Ok(self.common.stay()),
+        };
+        let x_new = self.common.accept(&op.0, trial)?;
+
+        let s = &x_new - &x;
+        let y = &self.common.current.as_ref().unwrap().grad - &g;
+        if s.dot(&y) > f64::EPSILON * y.dot(&y) {
+            if self.pairs.len() == self.memory {
+                self.pairs.remove(0);
+            }
+            self.pairs.push((s, y));
+        }
+        Ok(x_new)
+    }
+
+    fn init_report<R: Report<Arg = BoundedState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.init(&self.common.state)
+    }
+
+    fn update_report<R: Report<Arg = BoundedState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.update(&self.common.state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::*;
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use num_traits::Float;
+
+    struct Rosenbrock;
+
+    impl DifferentiableCost for Rosenbrock {
+        fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+            let b = T::from(100.).unwrap();
+            Ok((T::one() - x[0]).powi(2) + b * (x[1] - x[0] * x[0]).powi(2))
+        }
+    }
+
+    /// `sum_i (i + 1) (x_i - c_i)^2` with the minimum outside the box.
+    struct Quadratic;
+
+    impl DifferentiableCost for Quadratic {
+        fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+            let c = [-1., 0.5, 3.];
+            Ok(x.iter().enumerate().fold(T::zero(), |acc, (i, &xi)| {
+                acc + T::from(i + 1).unwrap() * (xi - T::from(c[i]).unwrap()).powi(2)
+            }))
+        }
+    }
+
+    #[test]
+    fn bounded_rosenbrock() -> anyhow::Result<()> {
+        // the unconstrained minimum (1, 1) lies outside; the solution is (0.5, 0.25)
+        let bounds = Bounds::new(array![-2., -2.], array![0.5, 2.]);
+        let criteria = || when(|r: &BoundedReport| r.proj_grad_norm < 1e-9 || r.count >= 5000);
+        let x = Executor::new(
+            ProjectedGradient::<2>::new(bounds.clone()),
+            Minimize(Rosenbrock),
+        )
+        .report(BoundedReport::default())
+        .terminate(criteria())
+        .run(array![-1.2, 1.])?;
+        assert_abs_diff_eq!(x[0], 0.5, epsilon = 1e-6);
+        assert_abs_diff_eq!(x[1], 0.25, epsilon = 1e-6);
+
+        let mut iterations = 0;
+        let x = Executor::new(LBfgsB::<2>::new(bounds.clone()), Minimize(Rosenbrock))
+            .report(BoundedReport::default())
+            .add_monitor(|r: &BoundedReport| {
+                iterations = r.count;
+                Ok(())
+            })
+            .terminate(criteria())
+            .run(array![-1.2, 1.])?;
+        assert_abs_diff_eq!(x[0], 0.5, epsilon = 1e-8);
+        assert_abs_diff_eq!(x[1], 0.25, epsilon = 1e-8);
+        assert_eq!(bounds.activity(&x), vec![Activity::Upper, Activity::Free]);
+        assert!(iterations < 100);
+        Ok(())
+    }
+
+    #[test]
+    fn active_set_in_report() -> anyhow::Result<()> {
+        let bounds = Bounds::new(array![0., 0., 0.], array![1., 1., 1.]);
+        let mut buf = Vec::new();
+        let x = {
+            let mut table = table_dump::Table::from_writer(&mut buf);
+            Executor::new(LBfgsB::<3>::new(bounds), Minimize(Quadratic))
+                .report(BoundedReport::default())
+                .add_monitor(move |r: &BoundedReport| table.serialize(r))
+                .terminate(when(|r: &BoundedReport| {
+                    r.proj_grad_norm < 1e-12 || r.count >= 100
+                }))
+                .run(array![0.5, 0.5, 0.5])?
+        };
+        for (x, y) in x.iter().zip([0., 0.5, 1.].iter()) {
+            assert_abs_diff_eq!(x, y, epsilon = 1e-12);
+        }
+        let out = String::from_utf8(buf)?;
+        let mut lines = out.lines();
+        assert_eq!(
+            lines.next(),
+            Some("count\tcost\tproj_grad_norm\tstep_norm\tevaluations\tn_active\tactive[0]\tactive[1]\tactive[2]")
+        );
+        let last: Vec<&str> = lines.last().unwrap().split('\t').collect();
+        assert_eq!(&last[5..], &["2", "Lower", "Free", "Upper"]);
+        Ok(())
+    }
+
+    #[test]
+    fn non_finite_gradient() {
+        // the derivative of sqrt(x) is infinite at the lower bound
+        struct Sqrt;
+
+        impl DifferentiableCost for Sqrt {
+            fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+                Ok(x[0].sqrt() + x[1] * x[1])
+            }
+        }
+
+        let bounds = Bounds::nonnegative(2);
+        let result = Executor::new(LBfgsB::<2>::new(bounds), Minimize(Sqrt))
+            .report(BoundedReport::default())
+            .terminate(when(|r: &BoundedReport| r.count >= 10))
+            .run(array![0., 1.]);
+        assert!(result.is_err());
+    }
+}
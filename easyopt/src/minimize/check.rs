@@ -0,0 +1,197 @@
+use super::{gradient, DifferentiableCost};
+use crate::error::*;
+use ndarray::prelude::*;
+use num_complex::{Complex64, ComplexFloat};
+
+/// Comparison of the `Dual` gradient of a cost with central finite differences.
+#[derive(Debug, Clone)]
+pub struct GradientCheck {
+    pub analytic: Array1<f64>,
+    pub numeric: Array1<f64>,
+    /// `|analytic - numeric| / max(1, |analytic|, |numeric|)` per component.
+    pub errors: Array1<f64>,
+    pub max_error: f64,
+    /// Components whose error exceeds the tolerance.
+    pub offending: Vec<usize>,
+}
+
+impl GradientCheck {
+    pub fn passed(&self) -> bool {
+        self.offending.is_empty()
+    }
+
+    /// Turns a failed check into an error naming the offending components, for use before
+    /// starting an `Executor` run.
+    pub fn ensure(self) -> Result<Self, Error> {
+        if self.passed() {
+            Ok(self)
+        } else {
+            Err(Error::Failure(format!(
+                "gradient disagrees with finite differences at components {:?} (max relative error {:e})",
+                self.offending, self.max_error
+            )))
+        }
+    }
+}
+
+/// Checks the gradient of `f` at `x` from `gradient` against central differences with the
+/// step `eps^(1/3) max(1, |x_i|)` for each component.
+///
+/// The truncation error of the differences is of order `eps^(2/3)` relative to the third
+/// derivative, so `tolerance` should not be much tighter than 1e-6.
+pub fn check_gradient<F, const N: usize>(
+    f: &F,
+    x: &Array1<f64>,
+    tolerance: f64,
+) -> Result<GradientCheck, Error>
+where
+    F: DifferentiableCost + ?Sized,
+{
+    let (_, analytic) = gradient::<F, N>(f, x)?;
+    let mut numeric = Array1::zeros(N);
+    let mut point = x.to_vec();
+    for i in 0..N {
+        let h = f64::EPSILON.cbrt() * x[i].abs().max(1.);
+        point[i] = x[i] + h;
+        let forward = f.cost::<f64>(&point)?;
+        point[i] = x[i] - h;
+        let backward = f.cost::<f64>(&point)?;
+        point[i] = x[i];
+        numeric[i] = (forward - backward) / (2. * h);
+    }
+    Ok(compare(analytic, numeric, tolerance))
+}
+
+/// A cost written for real and complex scalars alike, so that it can be differentiated by
+/// complex steps.
+pub trait ComplexStepCost {
+    fn cost<T: ComplexFloat<Real = f64>>(&self, x: &[T]) -> Result<T, Error>;
+}
+
+/// Value and gradient of `f` at `x` by complex steps, `df/dx_i = Im f(x + i h e_i) / h`.
+///
+/// Nothing is subtracted, so `h` can be tiny, e.g. 1e-20, and the derivatives are accurate to
+/// rounding, provided `f` is real-analytic: no `abs`, comparisons or conjugation of the
+/// argument.
+pub fn complex_step_gradient<F>(f: &F, x: &Array1<f64>, h: f64) -> Result<(f64, Array1<f64>), Error>
+where
+    F: ComplexStepCost + ?Sized,
+{
+    let mut point: Vec<Complex64> = x.iter().map(|&v| Complex64::new(v, 0.)).collect();
+    let value = f.cost::<f64>(&x.to_vec())?;
+    let mut grad = Array1::zeros(x.len());
+    for i in 0..x.len() {
+        point[i].im = h;
+        grad[i] = f.cost(&point)?.im / h;
+        point[i].im = 0.;
+    }
+    Ok((value, grad))
+}
+
+/// Checks the gradient of `f` at `x` from `gradient` against complex steps of 1e-20.
+///
+/// Both are exact up to rounding, so `tolerance` can be close to machine precision, unlike
+/// with `check_gradient`.
+pub fn check_gradient_complex_step<F, const N: usize>(
+    f: &F,
+    x: &Array1<f64>,
+    tolerance: f64,
+) -> Result<GradientCheck, Error>
+where
+    F: DifferentiableCost + ComplexStepCost + ?Sized,
+{
+    let (_, analytic) = gradient::<F, N>(f, x)?;
+    let (_, numeric) = complex_step_gradient(f, x, 1e-20)?;
+    Ok(compare(analytic, numeric, tolerance))
+}
+
+fn compare(analytic: Array1<f64>, numeric: Array1<f64>, tolerance: f64) -> GradientCheck {
+    let errors = ndarray::Zip::from(&analytic)
+        .and(&numeric)
+        .map_collect(|&a, &n| (a - n).abs() / a.abs().max(n.abs()).max(1.));
+    let max_error = errors.iter().fold(0f64, |acc, &e| acc.max(e));
+    let offending = errors
+        .iter()
+        .enumerate()
+        .filter(|(_, &e)| e.is_nan() || e > tolerance)
+        .map(|(i, _)| i)
+        .collect();
+    GradientCheck {
+        analytic,
+        numeric,
+        errors,
+        max_error,
+        offending,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use num_traits::Float;
+
+    struct Smooth;
+
+    impl DifferentiableCost for Smooth {
+        fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+            Ok(x[0] / (T::one() + x[1] * x[1]) + x[2].exp() * x[0].sin() + x[1].atan2(x[2]))
+        }
+    }
+
+    /// The third component goes through `f64`, which silently drops its derivative.
+    struct Detached;
+
+    impl DifferentiableCost for Detached {
+        fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+            let z = T::from(x[2].to_f64().unwrap().sin()).unwrap();
+            Ok(x[0] * x[1] + z)
+        }
+    }
+
+    /// `x0 / (1 + x1^2) + exp(x2) sin(x0) + x1^3 / x2`.
+    struct Analytic;
+
+    impl DifferentiableCost for Analytic {
+        fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+            Ok(x[0] / (T::one() + x[1] * x[1]) + x[2].exp() * x[0].sin() + x[1].powi(3) / x[2])
+        }
+    }
+
+    impl ComplexStepCost for Analytic {
+        fn cost<T: ComplexFloat<Real = f64>>(&self, x: &[T]) -> Result<T, Error> {
+            Ok(x[0] / (T::one() + x[1] * x[1]) + x[2].exp() * x[0].sin() + x[1].powi(3) / x[2])
+        }
+    }
+
+    #[test]
+    fn complex_step() -> anyhow::Result<()> {
+        let x = array![0.7, -1.3, 0.4];
+        let (value, grad) = complex_step_gradient(&Analytic, &x, 1e-20)?;
+        let (expected, dual) = gradient::<_, 3>(&Analytic, &x)?;
+        assert_eq!(expected, value);
+        for (a, b) in grad.iter().zip(dual.iter()) {
+            assert!((a - b).abs() <= 4. * f64::EPSILON * b.abs().max(1.));
+        }
+        let check = check_gradient_complex_step::<_, 3>(&Analytic, &x, 1e-14)?;
+        assert!(check.passed());
+        Ok(())
+    }
+
+    #[test]
+    fn smooth() -> anyhow::Result<()> {
+        let check = check_gradient::<_, 3>(&Smooth, &array![0.7, -1.3, 0.4], 1e-7)?;
+        assert!(check.passed());
+        assert!(check.max_error < 1e-8);
+        check.ensure()?;
+        Ok(())
+    }
+
+    #[test]
+    fn detached_component() -> anyhow::Result<()> {
+        let check = check_gradient::<_, 3>(&Detached, &array![1., 2., 0.5], 1e-6)?;
+        assert_eq!(check.offending, vec![2]);
+        assert!((check.numeric[2] - 0.5f64.cos()).abs() < 1e-8);
+        assert!(check.ensure().is_err());
+        Ok(())
+    }
+}
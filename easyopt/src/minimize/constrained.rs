@@ -0,0 +1,684 @@
+use super::{gradient, DifferentiableCost, Minimize};
+use crate::error::*;
+use crate::linalg;
+use crate::traits::*;
+use dual::Variables;
+use ndarray::prelude::*;
+use ndarray::Zip;
+use num_traits::Float;
+use serde::Serialize;
+
+/// Constraints `equality(x) = 0` and `inequality(x) <= 0` of a minimization problem.
+///
+/// Like `DifferentiableCost::cost`, the constraints are written once for any float type so
+/// that their Jacobians can be taken with `dual`. Both default to no constraint.
+pub trait Constraints {
+    fn equality<T: Float>(&self, _x: &[T]) -> Result<Vec<T>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn inequality<T: Float>(&self, _x: &[T]) -> Result<Vec<T>, Error> {
+        Ok(Vec::new())
+    }
+}
+
+/// Cost, constraints and their derivatives at a point.
+struct Point {
+    x: Array1<f64>,
+    cost: f64,
+    grad: Array1<f64>,
+    eq: Array1<f64>,
+    jac_eq: Array2<f64>,
+    ineq: Array1<f64>,
+    jac_ineq: Array2<f64>,
+}
+
+impl Point {
+    fn new<F, const N: usize>(f: &F, x: &Array1<f64>) -> Result<Self, Error>
+    where
+        F: DifferentiableCost + Constraints,
+    {
+        let (cost, grad) = gradient::<F, N>(f, x)?;
+        let vars = Variables::<f64, N>::new().gen_all(&x.to_vec());
+        let eq = f.equality(&vars)?;
+        let ineq = f.inequality(&vars)?;
+        Ok(Self {
+            x: x.clone(),
+            cost,
+            grad,
+            eq: eq.iter().map(|c| *c.val()).collect(),
+            jac_eq: Array2::from_shape_fn((eq.len(), N), |(i, j)| eq[i].grad()[j]),
+            ineq: ineq.iter().map(|c| *c.val()).collect(),
+            jac_ineq: Array2::from_shape_fn((ineq.len(), N), |(i, j)| ineq[i].grad()[j]),
+        })
+    }
+
+    /// Gradient of the Lagrangian `f + lambda^T eq + nu^T ineq`.
+    fn lagrangian_grad(&self, lambda: &Array1<f64>, nu: &Array1<f64>) -> Array1<f64> {
+        &self.grad + &self.jac_eq.t().dot(lambda) + &self.jac_ineq.t().dot(nu)
+    }
+
+    /// Infinity norm of the constraint violation.
+    fn violation(&self) -> f64 {
+        let eq = self.eq.iter().fold(0f64, |acc, c| acc.max(c.abs()));
+        self.ineq.iter().fold(eq, |acc, &c| acc.max(c))
+    }
+
+    /// Records the KKT quantities for the multipliers `lambda` and `nu`.
+    fn measure(&self, lambda: &Array1<f64>, nu: &Array1<f64>, state: &mut ConstrainedState) {
+        let stationarity = max_abs(&self.lagrangian_grad(lambda, nu));
+        let complementarity = Zip::from(nu)
+            .and(&self.ineq)
+            .fold(0f64, |acc, v, c| acc.max((v * c).abs()));
+        state.cost = self.cost;
+        state.violation = self.violation();
+        state.kkt = stationarity.max(state.violation).max(complementarity);
+        state.eq_multipliers = lambda.clone();
+        state.ineq_multipliers = nu.clone();
+    }
+}
+
+fn max_abs(x: &Array1<f64>) -> f64 {
+    x.iter().fold(0f64, |acc, v| acc.max(v.abs()))
+}
+
+/// Quantities describing the last iteration of a constrained solver.
+#[derive(Debug, Clone)]
+pub struct ConstrainedState {
+    pub cost: f64,
+    /// Infinity norm of the constraint violation.
+    pub violation: f64,
+    /// Largest of the stationarity, feasibility and complementarity residuals.
+    pub kkt: f64,
+    /// Penalty parameter of the augmented Lagrangian or of the merit function.
+    pub penalty: f64,
+    /// Iterations of the inner solver, or line-search trials of SQP.
+    pub inner_iterations: usize,
+    pub eq_multipliers: Array1<f64>,
+    pub ineq_multipliers: Array1<f64>,
+}
+
+impl ConstrainedState {
+    fn new(penalty: f64) -> Self {
+        Self {
+            cost: f64::NAN,
+            violation: f64::NAN,
+            kkt: f64::NAN,
+            penalty,
+            inner_iterations: 0,
+            eq_multipliers: Array1::zeros(0),
+            ineq_multipliers: Array1::zeros(0),
+        }
+    }
+}
+
+/// Report of the constrained solvers.
+///
+/// The multipliers are not written to tables, since their number is unknown before the first
+/// iteration; `multiplier_norm` is written instead.
+#[derive(Serialize)]
+pub struct ConstrainedReport {
+    pub count: usize,
+    pub cost: f64,
+    pub violation: f64,
+    pub kkt: f64,
+    pub penalty: f64,
+    pub inner_iterations: usize,
+    pub multiplier_norm: f64,
+    #[serde(skip)]
+    pub eq_multipliers: Array1<f64>,
+    #[serde(skip)]
+    pub ineq_multipliers: Array1<f64>,
+}
+
+impl Report for ConstrainedReport {
+    type Arg = ConstrainedState;
+
+    fn init(&mut self, s: &ConstrainedState) -> Result<(), Error> {
+        *self = Self::default();
+        self.penalty = s.penalty;
+        Ok(())
+    }
+
+    fn update(&mut self, s: &ConstrainedState) -> Result<(), Error> {
+        self.count += 1;
+        self.cost = s.cost;
+        self.violation = s.violation;
+        self.kkt = s.kkt;
+        self.penalty = s.penalty;
+        self.inner_iterations = s.inner_iterations;
+        self.multiplier_norm = max_abs(&s.eq_multipliers).max(max_abs(&s.ineq_multipliers));
+        self.eq_multipliers.clone_from(&s.eq_multipliers);
+        self.ineq_multipliers.clone_from(&s.ineq_multipliers);
+        Ok(())
+    }
+}
+
+impl Default for ConstrainedReport {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            cost: f64::NAN,
+            violation: f64::NAN,
+            kkt: f64::NAN,
+            penalty: f64::NAN,
+            inner_iterations: 0,
+            multiplier_norm: f64::NAN,
+            eq_multipliers: Array1::zeros(0),
+            ineq_multipliers: Array1::zeros(0),
+        }
+    }
+}
+
+/// Augmented Lagrangian of a constrained problem, minimized by the inner solver of
+/// `AugmentedLagrangian`.
+///
+/// Up to a constant, it is
+/// `f + rho / 2 (|eq + lambda / rho|^2 + |max(0, ineq + nu / rho)|^2)`.
+pub struct AugmentedCost<'a, F> {
+    pub problem: &'a F,
+    pub eq_multipliers: Array1<f64>,
+    pub ineq_multipliers: Array1<f64>,
+    pub penalty: f64,
+}
+
+impl<F> DifferentiableCost for AugmentedCost<'_, F>
+where
+    F: DifferentiableCost + Constraints,
+{
+    fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+        let rho = T::from(self.penalty).unwrap();
+        let half = T::from(0.5).unwrap();
+        let eq = self.problem.equality(x)?;
+        let ineq = self.problem.inequality(x)?;
+        let mut sum = T::zero();
+        for (c, l) in eq.into_iter().zip(self.eq_multipliers.iter()) {
+            sum = sum + (c + T::from(l / self.penalty).unwrap()).powi(2);
+        }
+        for (c, v) in ineq.into_iter().zip(self.ineq_multipliers.iter()) {
+            sum = sum
+                + (c + T::from(v / self.penalty).unwrap())
+                    .max(T::zero())
+                    .powi(2);
+        }
+        Ok(self.problem.cost(x)? + half * rho * sum)
+    }
+}
+
+/// Augmented Lagrangian method; every iteration minimizes `AugmentedCost` approximately with
+/// a fresh inner solver made by `inner`, then updates the multipliers.
+///
+/// The penalty grows whenever the violation fails to halve, and the inner tolerance on the
+/// gradient tightens by a factor of ten per iteration down to `inner_tolerance`.
+pub struct AugmentedLagrangian<G, const N: usize> {
+    inner: G,
+    max_inner: usize,
+    growth: f64,
+    inner_tolerance: f64,
+    omega: f64,
+    lambda: Option<Array1<f64>>,
+    nu: Option<Array1<f64>>,
+    last_violation: f64,
+    state: ConstrainedState,
+}
+
+impl<G, const N: usize> AugmentedLagrangian<G, N> {
+    pub fn new(inner: G) -> Self {
+        Self {
+            inner,
+            max_inner: 1000,
+            growth: 10.,
+            inner_tolerance: 1e-10,
+            omega: 1.,
+            lambda: None,
+            nu: None,
+            last_violation: f64::INFINITY,
+            state: ConstrainedState::new(10.),
+        }
+    }
+
+    /// Initial penalty parameter; 10 by default.
+    pub fn penalty(mut self, rho: f64) -> Self {
+        self.state.penalty = rho;
+        self
+    }
+
+    /// Factor by which the penalty grows; 10 by default.
+    pub fn growth(mut self, factor: f64) -> Self {
+        self.growth = factor;
+        self
+    }
+
+    /// Limit of the inner iterations per outer iteration; 1000 by default.
+    pub fn max_inner(mut self, n: usize) -> Self {
+        self.max_inner = n;
+        self
+    }
+
+    /// Final tolerance on the gradient of the augmented Lagrangian; 1e-10 by default.
+    pub fn inner_tolerance(mut self, tol: f64) -> Self {
+        self.inner_tolerance = tol;
+        self
+    }
+
+    /// Initial estimates of the multipliers; zero by default.
+    pub fn multipliers(mut self, eq: Array1<f64>, ineq: Array1<f64>) -> Self {
+        self.lambda = Some(eq);
+        self.nu = Some(ineq);
+        self
+    }
+}
+
+impl<F, G, S, const N: usize> Solver<Minimize<F>> for AugmentedLagrangian<G, N>
+where
+    F: DifferentiableCost + Constraints,
+    G: FnMut() -> S,
+    S: for<'a> Solver<Minimize<AugmentedCost<'a, F>>>,
+{
+    type ReportArg = ConstrainedState;
+
+    fn next_iter(&mut self, op: &Minimize<F>, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+        if self.lambda.is_none() || self.nu.is_none() {
+            let p = Point::new::<F, N>(&op.0, x)?;
+            self.lambda.get_or_insert_with(|| Array1::zeros(p.eq.len()));
+            self.nu.get_or_insert_with(|| Array1::zeros(p.ineq.len()));
+        }
+        let rho = self.state.penalty;
+        let aug = Minimize(AugmentedCost {
+            problem: &op.0,
+            eq_multipliers: self.lambda.clone().unwrap(),
+            ineq_multipliers: self.nu.clone().unwrap(),
+            penalty: rho,
+        });
+
+        let mut solver = (self.inner)();
+        let mut x = x.clone();
+        let mut unchanged = 0;
+        self.state.inner_iterations = 0;
+        while self.state.inner_iterations < self.max_inner {
+            let (_, g) = gradient::<_, N>(&aug.0, &x)?;
+            if max_abs(&g) <= self.omega {
+                break;
+            }
+            let next = solver.next_iter(&aug, &x)?;
+            self.state.inner_iterations += 1;
+            // a solver that keeps rejecting steps has stalled
+            unchanged = if next == x { unchanged + 1 } else { 0 };
+            if unchanged >= 20 {
+                break;
+            }
+            x = next;
+        }
+
+        let p = Point::new::<F, N>(&op.0, &x)?;
+        let lambda = &aug.0.eq_multipliers + &(&p.eq * rho);
+        let nu = (&aug.0.ineq_multipliers + &(&p.ineq * rho)).mapv(|v| v.max(0.));
+        // violation measured with the complementarity of the new multipliers
+        let violation = Zip::from(&p.ineq)
+            .and(&aug.0.ineq_multipliers)
+            .fold(max_abs(&p.eq), |acc, &c, &v| acc.max(c.max(-v / rho).abs()));
+        if violation > 0.5 * self.last_violation {
+            self.state.penalty = rho * self.growth;
+        }
+        self.last_violation = violation;
+        self.omega = (0.1 * self.omega).max(self.inner_tolerance);
+        p.measure(&lambda, &nu, &mut self.state);
+        self.lambda = Some(lambda);
+        self.nu = Some(nu);
+        Ok(x)
+    }
+
+    fn init_report<R: Report<Arg = ConstrainedState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.init(&self.state)
+    }
+
+    fn update_report<R: Report<Arg = ConstrainedState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.update(&self.state)
+    }
+}
+
+/// Solves the convex QP `min d^T b d / 2 + c^T d` subject to `a_eq d = b_eq` and
+/// `a_in d <= b_in` by coordinate ascent on its dual (Hildreth's method), starting from the
+/// multipliers `y`.
+///
+/// Returns `Ok(None)` if `b` is not positive definite, and an error if the sweeps do not
+/// converge, which is how inconsistent constraints show up as the dual is then unbounded.
+fn solve_qp(
+    b: &Array2<f64>,
+    c: &Array1<f64>,
+    a: &Array2<f64>,
+    rhs: &Array1<f64>,
+    n_eq: usize,
+    y: &mut Array1<f64>,
+) -> Result<Option<Array1<f64>>, Error> {
+    let l = match linalg::cholesky(b) {
+        Some(l) => l,
+        None => return Ok(None),
+    };
+    let binv_c = linalg::cholesky_solve(&l, c);
+    let mut binv_at = Array2::zeros((b.nrows(), a.nrows()));
+    for (i, row) in a.outer_iter().enumerate() {
+        binv_at
+            .column_mut(i)
+            .assign(&linalg::cholesky_solve(&l, &row.to_owned()));
+    }
+    let p = a.dot(&binv_at);
+    let q = a.dot(&binv_c) + rhs;
+    for _sweep in 0..10000 {
+        let mut change = 0f64;
+        for i in 0..a.nrows() {
+            if p[[i, i]] <= 0. {
+                continue;
+            }
+            let grad = p.row(i).dot(y) + q[i];
+            let mut yi = y[i] - grad / p[[i, i]];
+            if i >= n_eq {
+                yi = yi.max(0.);
+            }
+            change = change.max((yi - y[i]).abs() / y[i].abs().max(1.));
+            y[i] = yi;
+        }
+        if change <= 1e-15 {
+            return Ok(Some(-(binv_c + binv_at.dot(y))));
+        }
+        if !change.is_finite() {
+            break;
+        }
+    }
+    Err(Error::Failure(String::from(
+        "the QP subproblem did not converge; the linearized constraints may be inconsistent",
+    )))
+}
+
+/// Sequential quadratic programming with a damped BFGS approximation of the Hessian of the
+/// Lagrangian and a backtracking search on the l1 merit function.
+pub struct Sqp<const N: usize> {
+    hess: Option<Array2<f64>>,
+    current: Option<Point>,
+    lambda: Array1<f64>,
+    nu: Array1<f64>,
+    state: ConstrainedState,
+}
+
+impl<const N: usize> Default for Sqp<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Sqp<N> {
+    pub fn new() -> Self {
+        Self {
+            hess: None,
+            current: None,
+            lambda: Array1::zeros(0),
+            nu: Array1::zeros(0),
+            state: ConstrainedState::new(0.),
+        }
+    }
+
+    fn merit<F>(&self, f: &F, x: &Array1<f64>) -> Result<f64, Error>
+    where
+        F: DifferentiableCost + Constraints,
+    {
+        let x = x.to_vec();
+        let eq: f64 = f.equality(&x)?.iter().map(|c| c.abs()).sum();
+        let ineq: f64 = f.inequality(&x)?.iter().map(|c| c.max(0.)).sum();
+        Ok(f.cost(&x)? + self.state.penalty * (eq + ineq))
+    }
+}
+
+impl<F, const N: usize> Solver<Minimize<F>> for Sqp<N>
+where
+    F: DifferentiableCost + Constraints,
+{
+    type ReportArg = ConstrainedState;
+
+    fn next_iter(&mut self, op: &Minimize<F>, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+        if self.current.as_ref().is_none_or(|p| p.x != x) {
+            let p = Point::new::<F, N>(&op.0, x)?;
+            if self.lambda.len() != p.eq.len() || self.nu.len() != p.ineq.len() {
+                self.lambda = Array1::zeros(p.eq.len());
+                self.nu = Array1::zeros(p.ineq.len());
+            }
+            self.current = Some(p);
+        }
+        let p = self.current.as_ref().unwrap();
+        let b = self.hess.get_or_insert_with(|| Array2::eye(N)).clone();
+
+        let a = ndarray::concatenate![Axis(0), p.jac_eq, p.jac_ineq];
+        let rhs = -ndarray::concatenate![Axis(0), p.eq, p.ineq];
+        let n_eq = p.eq.len();
+        let mut y = ndarray::concatenate![Axis(0), self.lambda, self.nu];
+        let d = match solve_qp(&b, &p.grad, &a, &rhs, n_eq, &mut y)? {
+            Some(d) => d,
+            None => {
+                self.hess = None;
+                return Ok(x.clone());
+            }
+        };
+        let lambda = y.slice(s![..n_eq]).to_owned();
+        let nu = y.slice(s![n_eq..]).to_owned();
+
+        self.state.penalty = self.state.penalty.max(1.5 * max_abs(&y));
+        let infeasibility: f64 = p.eq.iter().map(|c| c.abs()).sum::<f64>()
+            + p.ineq.iter().map(|c| c.max(0.)).sum::<f64>();
+        let slope = (p.grad.dot(&d) - self.state.penalty * infeasibility).min(0.);
+        let phi = p.cost + self.state.penalty * infeasibility;
+
+        let mut t = 1.;
+        let mut trial = None;
+        self.state.inner_iterations = 0;
+        for _ in 0..40 {
+            let xt = x + &(&d * t);
+            self.state.inner_iterations += 1;
+            match self.merit(&op.0, &xt) {
+                Ok(v) if v <= phi + 1e-4 * t * slope => {
+                    trial = Some(xt);
+                    break;
+                }
+                Ok(_) | Err(Error::InvalidVariable) => t *= 0.5,
+                Err(e) => return Err(e),
+            }
+        }
+        let x_new = match trial {
+            Some(x_new) => x_new,
+            None => {
+                // no progress along the QP step; restart the quasi-Newton matrix
+                self.hess = None;
+                p.measure(&lambda, &nu, &mut self.state);
+                return Ok(x.clone());
+            }
+        };
+
+        let p_new = Point::new::<F, N>(&op.0, &x_new)?;
+        let s = &x_new - x;
+        let r = p_new.lagrangian_grad(&lambda, &nu) - p.lagrangian_grad(&lambda, &nu);
+        let bs = b.dot(&s);
+        let sbs = s.dot(&bs);
+        if sbs > 0. {
+            // Powell's damping keeps the approximation positive definite
+            let sr = s.dot(&r);
+            let theta = if sr >= 0.2 * sbs {
+                1.
+            } else {
+                0.8 * sbs / (sbs - sr)
+            };
+            let r = &r * theta + &(&bs * (1. - theta));
+            let sr = s.dot(&r);
+            let mut b = b;
+            for i in 0..N {
+                for j in 0..N {
+                    b[[i, j]] += r[i] * r[j] / sr - bs[i] * bs[j] / sbs;
+                }
+            }
+            self.hess = Some(b);
+        }
+
+        p_new.measure(&lambda, &nu, &mut self.state);
+        self.lambda = lambda;
+        self.nu = nu;
+        self.current = Some(p_new);
+        Ok(x_new)
+    }
+
+    fn init_report<R: Report<Arg = ConstrainedState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.init(&self.state)
+    }
+
+    fn update_report<R: Report<Arg = ConstrainedState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.update(&self.state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::trust_region::*;
+    use super::super::*;
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use num_traits::Float;
+
+    /// `min x0 + x1` on the circle `x0^2 + x1^2 = 2`, solved by `(-1, -1)` with `lambda = 1/2`.
+    struct Circle;
+
+    impl DifferentiableCost for Circle {
+        fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+            Ok(x[0] + x[1])
+        }
+    }
+
+    impl Constraints for Circle {
+        fn equality<T: Float>(&self, x: &[T]) -> Result<Vec<T>, Error> {
+            Ok(vec![x[0] * x[0] + x[1] * x[1] - T::from(2.).unwrap()])
+        }
+    }
+
+    /// `min (x0 - 2)^2 + (x1 - 1)^2` with `x0^2 <= x1` and `x0 + x1 <= 2`, solved by `(1, 1)`
+    /// with both constraints active and `nu = (2/3, 2/3)`.
+    struct Parabola;
+
+    impl DifferentiableCost for Parabola {
+        fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+            Ok((x[0] - T::from(2.).unwrap()).powi(2) + (x[1] - T::one()).powi(2))
+        }
+    }
+
+    impl Constraints for Parabola {
+        fn inequality<T: Float>(&self, x: &[T]) -> Result<Vec<T>, Error> {
+            Ok(vec![x[0] * x[0] - x[1], x[0] + x[1] - T::from(2.).unwrap()])
+        }
+    }
+
+    /// `x0 = 0` and `x0 = 1` at once, which no step can satisfy.
+    struct Inconsistent;
+
+    impl DifferentiableCost for Inconsistent {
+        fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+            Ok(x[0] * x[0] + x[1] * x[1])
+        }
+    }
+
+    impl Constraints for Inconsistent {
+        fn equality<T: Float>(&self, x: &[T]) -> Result<Vec<T>, Error> {
+            Ok(vec![x[0], x[0] - T::one()])
+        }
+    }
+
+    fn converged(r: &ConstrainedReport) -> bool {
+        r.kkt < 1e-8 || r.count >= 100
+    }
+
+    #[test]
+    fn augmented_lagrangian() -> anyhow::Result<()> {
+        let inner = || TrustRegion::<2>::new(Subproblem::Exact);
+        let mut report = ConstrainedReport::default();
+        let x = Executor::new(AugmentedLagrangian::<_, 2>::new(inner), Minimize(Circle))
+            .report(ConstrainedReport::default())
+            .add_monitor(|r: &ConstrainedReport| {
+                report.count = r.count;
+                report.eq_multipliers = r.eq_multipliers.clone();
+                Ok(())
+            })
+            .terminate(when(converged))
+            .run(array![1., 0.])?;
+        assert_abs_diff_eq!(x[0], -1., epsilon = 1e-8);
+        assert_abs_diff_eq!(x[1], -1., epsilon = 1e-8);
+        assert_abs_diff_eq!(report.eq_multipliers[0], 0.5, epsilon = 1e-8);
+        assert!(report.count < 100);
+
+        let inner = || TrustRegion::<2>::new(Subproblem::Dogleg);
+        let mut nu = Array1::zeros(0);
+        let x = Executor::new(AugmentedLagrangian::<_, 2>::new(inner), Minimize(Parabola))
+            .report(ConstrainedReport::default())
+            .add_monitor(|r: &ConstrainedReport| {
+                nu = r.ineq_multipliers.clone();
+                Ok(())
+            })
+            .terminate(when(converged))
+            .run(array![0., 0.])?;
+        assert_abs_diff_eq!(x[0], 1., epsilon = 1e-8);
+        assert_abs_diff_eq!(x[1], 1., epsilon = 1e-8);
+        assert_abs_diff_eq!(nu[0], 2. / 3., epsilon = 1e-7);
+        assert_abs_diff_eq!(nu[1], 2. / 3., epsilon = 1e-7);
+        Ok(())
+    }
+
+    #[test]
+    fn sqp() -> anyhow::Result<()> {
+        let mut report = ConstrainedReport::default();
+        let x = Executor::new(Sqp::<2>::new(), Minimize(Circle))
+            .report(ConstrainedReport::default())
+            .add_monitor(|r: &ConstrainedReport| {
+                report.count = r.count;
+                report.eq_multipliers = r.eq_multipliers.clone();
+                Ok(())
+            })
+            .terminate(when(converged))
+            .run(array![1., 0.])?;
+        assert_abs_diff_eq!(x[0], -1., epsilon = 1e-8);
+        assert_abs_diff_eq!(x[1], -1., epsilon = 1e-8);
+        assert_abs_diff_eq!(report.eq_multipliers[0], 0.5, epsilon = 1e-8);
+        assert!(report.count < 100);
+
+        let mut nu = Array1::zeros(0);
+        let x = Executor::new(Sqp::<2>::new(), Minimize(Parabola))
+            .report(ConstrainedReport::default())
+            .add_monitor(|r: &ConstrainedReport| {
+                nu = r.ineq_multipliers.clone();
+                Ok(())
+            })
+            .terminate(when(converged))
+            .run(array![0., 0.])?;
+        assert_abs_diff_eq!(x[0], 1., epsilon = 1e-8);
+        assert_abs_diff_eq!(x[1], 1., epsilon = 1e-8);
+        assert_abs_diff_eq!(nu[0], 2. / 3., epsilon = 1e-7);
+        assert_abs_diff_eq!(nu[1], 2. / 3., epsilon = 1e-7);
+        Ok(())
+    }
+
+    #[test]
+    fn sqp_inconsistent() {
+        let result = Executor::new(Sqp::<2>::new(), Minimize(Inconsistent))
+            .report(ConstrainedReport::default())
+            .terminate(when(converged))
+            .run(array![0.5, 0.]);
+        assert!(result.unwrap_err().to_string().contains("QP subproblem"));
+    }
+}
@@ -0,0 +1,412 @@
+use super::bounded::Bounds;
+use super::population::new_rng;
+use super::{DifferentiableCost, Minimize};
+use crate::criteria::when;
+use crate::error::*;
+use crate::executor::Executor;
+use crate::traits::*;
+use ndarray::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Serialize, Serializer};
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// `count` points of a Latin hypercube in `bounds`: each coordinate takes every one of
+/// `count` equal strata exactly once.
+pub fn latin_hypercube(bounds: &Bounds, count: usize, seed: Option<u64>) -> Vec<Array1<f64>> {
+    let mut rng = new_rng(seed);
+    let mut points = vec![Array1::zeros(bounds.len()); count];
+    for j in 0..bounds.len() {
+        let (lower, upper) = (bounds.lower()[j], bounds.upper()[j]);
+        let mut strata: Vec<usize> = (0..count).collect();
+        strata.shuffle(&mut rng);
+        for (point, &k) in points.iter_mut().zip(strata.iter()) {
+            let u = (k as f64 + rng.gen::<f64>()) / count as f64;
+            point[j] = lower + u * (upper - lower);
+        }
+    }
+    points
+}
+
+/// Degree `s`, coefficients `a` and initial direction numbers `m` of the Sobol sequence for
+/// the dimensions after the first (Joe and Kuo).
+const SOBOL: [(u32, u32, &[u32]); 20] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+    (6, 19, &[1, 1, 1, 15, 7, 5]),
+    (6, 22, &[1, 3, 1, 15, 13, 25]),
+    (6, 25, &[1, 1, 5, 5, 19, 61]),
+    (7, 1, &[1, 3, 7, 11, 23, 15, 103]),
+    (7, 4, &[1, 3, 7, 13, 13, 15, 69]),
+];
+
+const SOBOL_BITS: usize = 32;
+
+fn sobol_directions(dim: usize) -> [u32; SOBOL_BITS] {
+    let mut v = [0u32; SOBOL_BITS];
+    if dim == 0 {
+        for (k, vk) in v.iter_mut().enumerate() {
+            *vk = 1 << (SOBOL_BITS - 1 - k);
+        }
+        return v;
+    }
+    let (s, a, m) = SOBOL[dim - 1];
+    let s = s as usize;
+    for k in 0..SOBOL_BITS {
+        v[k] = if k < s {
+            m[k] << (SOBOL_BITS - 1 - k)
+        } else {
+            let mut vk = v[k - s] ^ (v[k - s] >> s);
+            for j in 1..s {
+                if (a >> (s - 1 - j)) & 1 == 1 {
+                    vk ^= v[k - j];
+                }
+            }
+            vk
+        };
+    }
+    v
+}
+
+/// The first `count` points of the Sobol sequence scaled to `bounds`, starting with the lower
+/// corner. Direction numbers are tabulated up to 21 dimensions.
+pub fn sobol(bounds: &Bounds, count: usize) -> Result<Vec<Array1<f64>>, Error> {
+    let n = bounds.len();
+    if n > SOBOL.len() + 1 {
+        return Err(Error::Failure(format!(
+            "Sobol points are available up to {} dimensions",
+            SOBOL.len() + 1
+        )));
+    }
+    let directions: Vec<_> = (0..n).map(sobol_directions).collect();
+    let scale = (1u64 << SOBOL_BITS) as f64;
+    let mut state = vec![0u32; n];
+    let mut points = Vec::with_capacity(count);
+    for i in 0..count {
+        if i > 0 {
+            // Gray code order: flip the direction of the lowest zero bit of i - 1
+            let c = (!(i - 1)).trailing_zeros() as usize;
+            for (x, v) in state.iter_mut().zip(directions.iter()) {
+                *x ^= v[c];
+            }
+        }
+        points.push(Array1::from_shape_fn(n, |j| {
+            let u = state[j] as f64 / scale;
+            bounds.lower()[j] + u * (bounds.upper()[j] - bounds.lower()[j])
+        }));
+    }
+    Ok(points)
+}
+
+/// Outcome of one start, written as one row of the table.
+#[derive(Debug, Clone, Serialize)]
+pub struct StartResult {
+    pub run: usize,
+    /// Whether the executor returned without an error at a point of finite cost. The run
+    /// stopped because `criteria` held, which need not mean it converged.
+    pub finished: bool,
+    /// Why the run did not finish; an empty column if it did.
+    #[serde(serialize_with = "empty_if_none")]
+    pub error: Option<String>,
+    pub cost: f64,
+    /// Rank of the distinct solution reached, 0 for the lowest cost. Failed runs rank after
+    /// every solution.
+    pub rank: usize,
+    /// Whether a run with a lower cost reached the same solution.
+    pub duplicate: bool,
+    pub start: Vec<f64>,
+    pub x: Vec<f64>,
+}
+
+fn empty_if_none<S: Serializer>(error: &Option<String>, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(error.as_deref().unwrap_or(""))
+}
+
+/// Runs an `Executor` from each of many initial points on a pool of threads, then groups the
+/// results into distinct solutions and ranks them by cost.
+///
+/// Two results are the same solution when they differ by less than `tolerance` in the
+/// infinity norm, relative to the larger of one and the norm of the better one.
+pub struct MultiStart<G> {
+    solver: G,
+    threads: usize,
+    tolerance: f64,
+}
+
+impl<G> MultiStart<G> {
+    /// `solver` makes a fresh solver for every start.
+    pub fn new(solver: G) -> Self {
+        Self {
+            solver,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            tolerance: 1e-6,
+        }
+    }
+
+    /// Number of worker threads; the available parallelism by default.
+    pub fn threads(mut self, n: usize) -> Self {
+        self.threads = n.max(1);
+        self
+    }
+
+    /// 1e-6 by default.
+    pub fn tolerance(mut self, tol: f64) -> Self {
+        self.tolerance = tol;
+        self
+    }
+
+    /// Minimizes `problem` from every point of `starts` until `criteria` holds for the report
+    /// `R`, writing one row per start to `table` in the order of `starts`.
+    pub fn run<'p, F, S, R, C, W>(
+        &self,
+        problem: &'p F,
+        starts: &[Array1<f64>],
+        criteria: C,
+        table: &mut table_dump::Table<W>,
+    ) -> anyhow::Result<Vec<StartResult>>
+    where
+        F: DifferentiableCost + Sync,
+        G: Fn() -> S + Sync,
+        S: Solver<Minimize<&'p F>>,
+        R: Report<Arg = S::ReportArg> + Default,
+        C: Fn(&R) -> bool + Sync,
+        W: io::Write,
+    {
+        let next = AtomicUsize::new(0);
+        let outcomes = Mutex::new(Vec::with_capacity(starts.len()));
+        std::thread::scope(|scope| {
+            for _ in 0..self.threads.min(starts.len()) {
+                scope.spawn(|| loop {
+                    let run = next.fetch_add(1, Ordering::Relaxed);
+                    if run >= starts.len() {
+                        break;
+                    }
+                    let x = Executor::new((self.solver)(), Minimize(problem))
+                        .report(R::default())
+                        .terminate(when(|r: &R| criteria(r)))
+                        .run(starts[run].clone());
+                    outcomes.lock().unwrap().push((run, x));
+                });
+            }
+        });
+        let mut outcomes = outcomes.into_inner().unwrap();
+        outcomes.sort_by_key(|(run, _)| *run);
+
+        let n = starts.first().map_or(0, |x| x.len());
+        let mut results: Vec<StartResult> = outcomes
+            .into_iter()
+            .map(|(run, x)| {
+                let x = x.map_err(|e| e.to_string());
+                let cost = x.as_ref().map_err(Clone::clone).and_then(|x| {
+                    match problem.cost::<f64>(&x.to_vec()) {
+                        Ok(c) if !c.is_nan() => Ok(c),
+                        Ok(_) => Err(String::from("the cost is not defined at the result")),
+                        Err(e) => Err(e.to_string()),
+                    }
+                });
+                StartResult {
+                    run,
+                    finished: cost.is_ok(),
+                    error: cost.as_ref().err().map(|e| e.replace(['\t', '\n'], " ")),
+                    cost: cost.unwrap_or(f64::NAN),
+                    rank: 0,
+                    duplicate: false,
+                    start: starts[run].to_vec(),
+                    x: x.map_or_else(|_| vec![f64::NAN; n], |x| x.to_vec()),
+                }
+            })
+            .collect();
+
+        let mut order: Vec<usize> = (0..results.len())
+            .filter(|&i| results[i].finished)
+            .collect();
+        order.sort_by(|&i, &j| results[i].cost.total_cmp(&results[j].cost));
+        let mut solutions: Vec<usize> = Vec::new();
+        for i in order {
+            let same = solutions.iter().position(|&k| {
+                let (a, b) = (&results[k].x, &results[i].x);
+                let scale = a.iter().fold(1f64, |acc, v| acc.max(v.abs()));
+                let distance = a
+                    .iter()
+                    .zip(b.iter())
+                    .fold(0f64, |acc, (u, v)| acc.max((u - v).abs()));
+                distance <= self.tolerance * scale
+            });
+            match same {
+                Some(rank) => {
+                    results[i].rank = rank;
+                    results[i].duplicate = true;
+                }
+                None => {
+                    results[i].rank = solutions.len();
+                    solutions.push(i);
+                }
+            }
+        }
+        for r in results.iter_mut().filter(|r| !r.finished) {
+            r.rank = solutions.len();
+        }
+        for r in results.iter() {
+            table.serialize(r)?;
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::trust_region::*;
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use num_traits::Float;
+
+    /// Four local minima near `(+-1, +-1)`, the lowest at `(-1, -1)`.
+    struct FourWells;
+
+    impl DifferentiableCost for FourWells {
+        fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+            let c = |v: f64| T::from(v).unwrap();
+            Ok((x[0] * x[0] - T::one()).powi(2)
+                + c(0.1) * x[0]
+                + (x[1] * x[1] - T::one()).powi(2)
+                + c(0.2) * x[1])
+        }
+    }
+
+    #[test]
+    fn four_wells() -> anyhow::Result<()> {
+        let bounds = Bounds::new(array![-2., -2.], array![2., 2.]);
+        let starts = sobol(&bounds, 16)?;
+        let mut buf = Vec::new();
+        let mut table = table_dump::Table::from_writer(&mut buf);
+        let results = MultiStart::new(|| TrustRegion::<2>::new(Subproblem::Exact))
+            .threads(4)
+            .run(
+                &FourWells,
+                &starts,
+                |r: &TrustRegionReport| r.grad_norm < 1e-10 || r.count >= 100,
+                &mut table,
+            )?;
+        drop(table);
+
+        assert_eq!(results.len(), 16);
+        let distinct: Vec<_> = results.iter().filter(|r| !r.duplicate).collect();
+        assert!(results.iter().all(|r| r.finished && r.error.is_none()));
+        let mut ranks: Vec<usize> = distinct.iter().map(|r| r.rank).collect();
+        ranks.sort_unstable();
+        assert_eq!(ranks, vec![0, 1, 2, 3]);
+        let best = results.iter().find(|r| r.rank == 0).unwrap();
+        assert!(best.x[0] < -0.9 && best.x[1] < -0.9);
+        for r in &results {
+            let same = results
+                .iter()
+                .find(|d| !d.duplicate && d.rank == r.rank)
+                .unwrap();
+            assert!(same.cost <= r.cost);
+            assert_abs_diff_eq!(same.x[0], r.x[0], epsilon = 1e-6);
+        }
+        let text = String::from_utf8(buf)?;
+        assert!(text.starts_with("run\tfinished\terror\tcost\trank"));
+        let ids: Vec<&str> = text
+            .lines()
+            .skip(1)
+            .map(|l| l.split('\t').next().unwrap())
+            .collect();
+        assert_eq!(ids, (0..16).map(|i| i.to_string()).collect::<Vec<_>>());
+        Ok(())
+    }
+
+    /// `FourWells` for `x[0] <= 0`; an error beyond.
+    struct HalfWells;
+
+    impl DifferentiableCost for HalfWells {
+        fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+            if x[0] > T::zero() {
+                return Err(Error::Failure(String::from("past the wall")));
+            }
+            FourWells.cost(x)
+        }
+    }
+
+    #[test]
+    fn failed_starts() -> anyhow::Result<()> {
+        let bounds = Bounds::new(array![-2., -2.], array![2., 2.]);
+        let starts = sobol(&bounds, 8)?;
+        let mut buf = Vec::new();
+        let mut table = table_dump::Table::from_writer(&mut buf);
+        let results = MultiStart::new(|| TrustRegion::<2>::new(Subproblem::Exact)).run(
+            &HalfWells,
+            &starts,
+            |r: &TrustRegionReport| r.grad_norm < 1e-10 || r.count >= 100,
+            &mut table,
+        )?;
+        drop(table);
+
+        assert!(results.iter().any(|r| r.finished));
+        for r in &results {
+            assert_eq!(r.finished, r.error.is_none());
+            if r.start[0] > 0. {
+                assert!(r.error.as_deref().unwrap().contains("past the wall"));
+                assert!(r.cost.is_nan());
+            }
+        }
+        let text = String::from_utf8(buf)?;
+        for (line, r) in text.lines().skip(1).zip(&results) {
+            let error = line.split('\t').nth(2).unwrap();
+            assert_eq!(error, r.error.as_deref().unwrap_or(""));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn sobol_points() -> anyhow::Result<()> {
+        let bounds = Bounds::new(array![0., 0.], array![1., 1.]);
+        let points = sobol(&bounds, 8)?;
+        let expected = [
+            [0., 0.],
+            [0.5, 0.5],
+            [0.75, 0.25],
+            [0.25, 0.75],
+            [0.375, 0.375],
+            [0.875, 0.875],
+            [0.625, 0.125],
+            [0.125, 0.625],
+        ];
+        for (p, e) in points.iter().zip(expected.iter()) {
+            assert_abs_diff_eq!(p[0], e[0]);
+            assert_abs_diff_eq!(p[1], e[1]);
+        }
+        assert!(sobol(&Bounds::new(Array1::zeros(22), Array1::ones(22)), 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn latin_hypercube_strata() {
+        let bounds = Bounds::new(array![0., -1., 10.], array![1., 1., 20.]);
+        let points = latin_hypercube(&bounds, 10, Some(7));
+        for j in 0..3 {
+            let (lower, upper) = (bounds.lower()[j], bounds.upper()[j]);
+            let mut strata: Vec<usize> = points
+                .iter()
+                .map(|p| ((p[j] - lower) / (upper - lower) * 10.) as usize)
+                .collect();
+            strata.sort_unstable();
+            assert_eq!(strata, (0..10).collect::<Vec<_>>());
+        }
+    }
+}
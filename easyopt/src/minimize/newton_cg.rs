@@ -0,0 +1,309 @@
+use super::{chunked_gradient, chunked_hessian_vector, DifferentiableCost, Minimize};
+use crate::error::*;
+use crate::traits::*;
+use ndarray::prelude::*;
+use serde::Serialize;
+
+/// Quantities describing the last Newton–CG iteration.
+#[derive(Debug, Clone)]
+pub struct NewtonCgState {
+    pub cost: f64,
+    pub grad_norm: f64,
+    pub step_norm: f64,
+    /// Step length accepted by the line search along the Newton–CG direction.
+    pub step_length: f64,
+    pub cg_iterations: usize,
+    /// Whether the inner iteration stopped at a direction of non-positive curvature.
+    pub negative_curvature: bool,
+}
+
+/// Truncated Newton method that never forms the Hessian.
+///
+/// The Newton equation `H p = -g` is solved by conjugate gradients to a relative residual of
+/// `min(0.5, sqrt(|g|))`, where each product `H d` comes from `chunked_hessian_vector`. The
+/// inner iteration stops at directions of non-positive curvature, and the step is chosen by
+/// backtracking on the Armijo condition.
+///
+/// Derivatives are taken `N` components at a time, so the dimension is only known at run
+/// time. With `N` at least the dimension each product is a single pass of the cost; for large
+/// problems a chunk of 8 to 32 keeps the dual numbers small, at `ceil(n / N)` passes per
+/// product.
+pub struct NewtonCg<const N: usize> {
+    max_cg: Option<usize>,
+    c1: f64,
+    current: Option<(Array1<f64>, f64, Array1<f64>)>,
+    state: NewtonCgState,
+}
+
+impl<const N: usize> Default for NewtonCg<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> NewtonCg<N> {
+    pub fn new() -> Self {
+        Self {
+            max_cg: None,
+            c1: 1e-4,
+            current: None,
+            state: NewtonCgState {
+                cost: f64::NAN,
+                grad_norm: f64::NAN,
+                step_norm: f64::NAN,
+                step_length: f64::NAN,
+                cg_iterations: 0,
+                negative_curvature: false,
+            },
+        }
+    }
+
+    /// Maximum number of conjugate gradient iterations per step; the dimension by default.
+    pub fn max_cg(mut self, n: usize) -> Self {
+        self.max_cg = Some(n.max(1));
+        self
+    }
+
+    /// Sufficient decrease parameter of the line search; 1e-4 by default.
+    pub fn armijo(mut self, c1: f64) -> Self {
+        self.c1 = c1;
+        self
+    }
+
+    fn evaluate<F>(&mut self, f: &F, x: &Array1<f64>) -> Result<(), Error>
+    where
+        F: DifferentiableCost,
+    {
+        let (cost, grad) = chunked_gradient::<F, N>(f, x)?;
+        self.state.cost = cost;
+        self.state.grad_norm = grad.dot(&grad).sqrt();
+        self.current = Some((x.clone(), cost, grad));
+        Ok(())
+    }
+
+    /// Approximate solution of `H p = -g` by conjugate gradients.
+    fn direction<F>(
+        &mut self,
+        f: &F,
+        x: &Array1<f64>,
+        g: &Array1<f64>,
+    ) -> Result<Array1<f64>, Error>
+    where
+        F: DifferentiableCost,
+    {
+        let g_norm = self.state.grad_norm;
+        let tol = g_norm.sqrt().min(0.5) * g_norm;
+        let mut z = Array1::zeros(g.len());
+        let mut r = g.clone();
+        let mut d = -g;
+        let mut rr = r.dot(&r);
+        self.state.cg_iterations = 0;
+        self.state.negative_curvature = false;
+        for j in 0..self.max_cg.unwrap_or(g.len()).max(1) {
+            let (_, _, hd) = chunked_hessian_vector::<F, N>(f, x, &d)?;
+            self.state.cg_iterations += 1;
+            let curvature = d.dot(&hd);
+            if curvature <= 0. {
+                self.state.negative_curvature = true;
+                return Ok(if j == 0 { -g } else { z });
+            }
+            let alpha = rr / curvature;
+            z.scaled_add(alpha, &d);
+            r.scaled_add(alpha, &hd);
+            let rr_next = r.dot(&r);
+            if rr_next.sqrt() <= tol {
+                break;
+            }
+            d = -&r + &(rr_next / rr * &d);
+            rr = rr_next;
+        }
+        Ok(z)
+    }
+}
+
+impl<F, const N: usize> Solver<Minimize<F>> for NewtonCg<N>
+where
+    F: DifferentiableCost,
+{
+    type ReportArg = NewtonCgState;
+
+    fn next_iter(&mut self, op: &Minimize<F>, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+        if self.current.as_ref().is_none_or(|c| c.0 != x) {
+            self.evaluate(&op.0, x)?;
+        }
+        let (_, fx, g) = self.current.clone().unwrap();
+        if self.state.grad_norm == 0. {
+            self.state.step_norm = 0.;
+            self.state.step_length = 0.;
+            self.state.cg_iterations = 0;
+            return Ok(x.clone());
+        }
+
+        let p = self.direction(&op.0, x, &g)?;
+        let slope = g.dot(&p);
+        let mut t = 1.;
+        for _ in 0..60 {
+            let trial = x + &(t * &p);
+            let accepted = match op.0.cost::<f64>(&trial.to_vec()) {
+                Ok(v) => v <= fx + self.c1 * t * slope,
+                Err(Error::InvalidVariable) => false,
+                Err(e) => return Err(e),
+            };
+            if accepted {
+                self.state.step_length = t;
+                self.state.step_norm = t * p.dot(&p).sqrt();
+                self.evaluate(&op.0, &trial)?;
+                return Ok(trial);
+            }
+            t *= 0.5;
+        }
+        self.state.step_length = 0.;
+        self.state.step_norm = 0.;
+        Ok(x.clone())
+    }
+
+    fn init_report<R: Report<Arg = NewtonCgState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.init(&self.state)
+    }
+
+    fn update_report<R: Report<Arg = NewtonCgState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.update(&self.state)
+    }
+}
+
+#[derive(Serialize)]
+pub struct NewtonCgReport {
+    pub count: usize,
+    pub cost: f64,
+    pub grad_norm: f64,
+    pub step_norm: f64,
+    pub step_length: f64,
+    pub cg_iterations: usize,
+    pub negative_curvature: bool,
+}
+
+impl Report for NewtonCgReport {
+    type Arg = NewtonCgState;
+
+    fn init(&mut self, _s: &NewtonCgState) -> Result<(), Error> {
+        *self = Self::default();
+        Ok(())
+    }
+
+    fn update(&mut self, s: &NewtonCgState) -> Result<(), Error> {
+        self.count += 1;
+        self.cost = s.cost;
+        self.grad_norm = s.grad_norm;
+        self.step_norm = s.step_norm;
+        self.step_length = s.step_length;
+        self.cg_iterations = s.cg_iterations;
+        self.negative_curvature = s.negative_curvature;
+        Ok(())
+    }
+}
+
+impl Default for NewtonCgReport {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            cost: f64::NAN,
+            grad_norm: f64::NAN,
+            step_norm: f64::NAN,
+            step_length: f64::NAN,
+            cg_iterations: 0,
+            negative_curvature: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::*;
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use num_traits::Float;
+
+    /// Chained Rosenbrock function with its minimum at `(1, ..., 1)`.
+    struct Chained;
+
+    impl DifferentiableCost for Chained {
+        fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+            let hundred = T::from(100.).unwrap();
+            Ok(x.windows(2).fold(T::zero(), |acc, w| {
+                acc + hundred * (w[1] - w[0] * w[0]).powi(2) + (T::one() - w[0]).powi(2)
+            }))
+        }
+    }
+
+    /// `sum (x_i - 1)^2 + (x_{i+1} - x_i)^4`, minimized by `(1, ..., 1)`.
+    struct Coupled;
+
+    impl DifferentiableCost for Coupled {
+        fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+            let coupling = x.windows(2).map(|w| (w[1] - w[0]).powi(4));
+            Ok(x.iter()
+                .map(|&v| (v - T::one()).powi(2))
+                .chain(coupling)
+                .fold(T::zero(), |a, b| a + b))
+        }
+    }
+
+    #[test]
+    fn hessian_vector_product() -> anyhow::Result<()> {
+        let x = array![0.3, -0.5, 1.2, 0.7];
+        let v = array![1., 2., -1., 0.5];
+        let (cost, grad, hess) = hessian::<_, 4>(&Chained, &x)?;
+        let (c, g, hv) = hessian_vector::<_, 4>(&Chained, &x, &v)?;
+        assert_abs_diff_eq!(c, cost, epsilon = 1e-12);
+        for i in 0..4 {
+            assert_abs_diff_eq!(g[i], grad[i], epsilon = 1e-10);
+            assert_abs_diff_eq!(hv[i], hess.row(i).dot(&v), epsilon = 1e-10);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn chained_rosenbrock() -> anyhow::Result<()> {
+        let x0 = Array1::from_shape_fn(40, |i| if i % 2 == 0 { -1.2 } else { 1. });
+        let mut negative = false;
+        let x = Executor::new(NewtonCg::<40>::new(), Minimize(Chained))
+            .report(NewtonCgReport::default())
+            .add_monitor(|r: &NewtonCgReport| {
+                negative |= r.negative_curvature;
+                Ok(())
+            })
+            .terminate(when(|r: &NewtonCgReport| {
+                r.grad_norm < 1e-9 || r.count >= 500
+            }))
+            .run(x0)?;
+        for v in x.iter() {
+            assert_abs_diff_eq!(*v, 1., epsilon = 1e-8);
+        }
+        // the starting point lies in a region of negative curvature
+        assert!(negative);
+        Ok(())
+    }
+
+    #[test]
+    fn large_dimension() -> anyhow::Result<()> {
+        let x0 = Array1::from_shape_fn(1000, |i| (i as f64).sin());
+        let x = Executor::new(NewtonCg::<64>::new(), Minimize(Coupled))
+            .report(NewtonCgReport::default())
+            .terminate(when(|r: &NewtonCgReport| {
+                r.grad_norm < 1e-9 || r.count >= 100
+            }))
+            .run(x0)?;
+        for v in x.iter() {
+            assert_abs_diff_eq!(*v, 1., epsilon = 1e-8);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,828 @@
+use super::{CostFunction, Minimize};
+use crate::error::*;
+use crate::linalg;
+use crate::traits::*;
+use ndarray::prelude::*;
+use ndarray::Zip;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::StandardNormal;
+use serde::Serialize;
+
+/// Members of the current generation together with their costs.
+///
+/// The best member found so far is kept even if the solver discards it from the generation.
+#[derive(Debug, Clone, Default)]
+pub struct Population {
+    members: Vec<Array1<f64>>,
+    costs: Vec<f64>,
+    best: Option<(Array1<f64>, f64)>,
+    evaluations: usize,
+}
+
+impl Population {
+    pub fn members(&self) -> &[Array1<f64>] {
+        &self.members
+    }
+
+    pub fn costs(&self) -> &[f64] {
+        &self.costs
+    }
+
+    pub fn evaluations(&self) -> usize {
+        self.evaluations
+    }
+
+    pub fn best(&self) -> Option<&Array1<f64>> {
+        self.best.as_ref().map(|(x, _)| x)
+    }
+
+    pub fn best_cost(&self) -> f64 {
+        self.best.as_ref().map_or(f64::NAN, |(_, c)| *c)
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.costs.is_empty() {
+            f64::NAN
+        } else {
+            self.costs.iter().sum::<f64>() / self.costs.len() as f64
+        }
+    }
+
+    /// Standard deviation of the costs in the current generation.
+    pub fn spread(&self) -> f64 {
+        let mean = self.mean();
+        if self.costs.is_empty() {
+            f64::NAN
+        } else {
+            let var = self
+                .costs
+                .iter()
+                .map(|c| (c - mean) * (c - mean))
+                .sum::<f64>()
+                / self.costs.len() as f64;
+            var.sqrt()
+        }
+    }
+
+    fn replace(&mut self, members: Vec<Array1<f64>>, costs: Vec<f64>) {
+        self.members = members;
+        self.costs = costs;
+        self.update_best();
+    }
+
+    fn update_best(&mut self) {
+        let current = self
+            .costs
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| !c.is_nan())
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+        if let Some((i, &c)) = current {
+            if self.best.as_ref().is_none_or(|(_, best)| c < *best) {
+                self.best = Some((self.members[i].clone(), c));
+            }
+        }
+    }
+
+    fn best_or(&self, x: &Array1<f64>) -> Array1<f64> {
+        self.best().unwrap_or(x).clone()
+    }
+}
+
+/// Evaluates the cost of every candidate, splitting the work over `threads` scoped threads.
+///
+/// A candidate rejected with `Error::InvalidVariable` is given an infinite cost.
+fn evaluate<F>(op: &Minimize<F>, xs: &[Array1<f64>], threads: usize) -> Result<Vec<f64>, Error>
+where
+    F: CostFunction + Sync,
+{
+    let eval = |x: &Array1<f64>| match op.0.cost(x) {
+        Err(Error::InvalidVariable) => Ok(f64::INFINITY),
+        res => res,
+    };
+    if threads <= 1 || xs.len() < 2 {
+        return xs.iter().map(eval).collect();
+    }
+    let chunk = xs.len().div_ceil(threads);
+    std::thread::scope(|s| {
+        let handles: Vec<_> = xs
+            .chunks(chunk)
+            .map(|c| s.spawn(move || c.iter().map(eval).collect::<Result<Vec<_>, _>>()))
+            .collect();
+        let mut costs = Vec::with_capacity(xs.len());
+        for h in handles {
+            let part = h
+                .join()
+                .map_err(|_| Error::Failure(String::from("a worker thread panicked")))??;
+            costs.extend(part);
+        }
+        Ok(costs)
+    })
+}
+
+pub(super) fn new_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+fn uniform_in(rng: &mut StdRng, lower: &Array1<f64>, upper: &Array1<f64>) -> Array1<f64> {
+    Zip::from(lower)
+        .and(upper)
+        .map_collect(|&l, &u| l + (u - l) * rng.gen::<f64>())
+}
+
+/// Rejects an initial variable that is empty or does not match the bounds.
+fn check_start(x: &Array1<f64>, lower: &Array1<f64>) -> Result<(), Error> {
+    if x.is_empty() || x.len() != lower.len() {
+        Err(Error::InvalidVariable)
+    } else {
+        Ok(())
+    }
+}
+
+fn clip(x: &mut Array1<f64>, lower: &Array1<f64>, upper: &Array1<f64>) {
+    Zip::from(x)
+        .and(lower)
+        .and(upper)
+        .for_each(|x, &l, &u| *x = x.max(l).min(u));
+}
+
+#[derive(Serialize)]
+pub struct PopulationReport {
+    pub count: usize,
+    pub evaluations: usize,
+    pub best: f64,
+    pub mean: f64,
+    pub spread: f64,
+}
+
+impl Report for PopulationReport {
+    type Arg = Population;
+
+    fn init(&mut self, p: &Population) -> Result<(), Error> {
+        self.count = 0;
+        self.evaluations = p.evaluations();
+        self.best = p.best_cost();
+        self.mean = p.mean();
+        self.spread = p.spread();
+        Ok(())
+    }
+
+    fn update(&mut self, p: &Population) -> Result<(), Error> {
+        self.count += 1;
+        self.evaluations = p.evaluations();
+        self.best = p.best_cost();
+        self.mean = p.mean();
+        self.spread = p.spread();
+        Ok(())
+    }
+}
+
+impl Default for PopulationReport {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            evaluations: 0,
+            best: f64::NAN,
+            mean: f64::NAN,
+            spread: f64::NAN,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    /// DE/rand/1/bin
+    Rand1Bin,
+    /// DE/best/1/bin
+    Best1Bin,
+}
+
+/// Differential evolution within the box `[lower, upper]`.
+pub struct DifferentialEvolution {
+    lower: Array1<f64>,
+    upper: Array1<f64>,
+    size: Option<usize>,
+    mutation: f64,
+    crossover: f64,
+    strategy: Strategy,
+    threads: usize,
+    rng: StdRng,
+    population: Population,
+}
+
+impl DifferentialEvolution {
+    pub fn new(lower: Array1<f64>, upper: Array1<f64>) -> Self {
+        assert_eq!(lower.len(), upper.len());
+        Self {
+            lower,
+            upper,
+            size: None,
+            mutation: 0.8,
+            crossover: 0.9,
+            strategy: Strategy::Rand1Bin,
+            threads: 1,
+            rng: new_rng(None),
+            population: Population::default(),
+        }
+    }
+
+    /// Number of members; `10 * dim` by default.
+    pub fn population_size(mut self, size: usize) -> Self {
+        assert!(size >= 4);
+        self.size = Some(size);
+        self
+    }
+
+    pub fn mutation(mut self, f: f64) -> Self {
+        self.mutation = f;
+        self
+    }
+
+    pub fn crossover(mut self, cr: f64) -> Self {
+        self.crossover = cr;
+        self
+    }
+
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = new_rng(Some(seed));
+        self
+    }
+
+    /// Evaluates the population on `n` threads.
+    ///
+    /// Random numbers are drawn on the calling thread, so a seeded run does not depend on `n`.
+    pub fn threads(mut self, n: usize) -> Self {
+        self.threads = n;
+        self
+    }
+
+    pub fn population(&self) -> &Population {
+        &self.population
+    }
+
+    fn initialize<F>(&mut self, op: &Minimize<F>, x: &Array1<f64>) -> Result<(), Error>
+    where
+        F: CostFunction + Sync,
+    {
+        check_start(x, &self.lower)?;
+        let size = self.size.unwrap_or(10 * x.len()).max(4);
+        let mut members = Vec::with_capacity(size);
+        let mut x = x.clone();
+        clip(&mut x, &self.lower, &self.upper);
+        members.push(x);
+        while members.len() < size {
+            members.push(uniform_in(&mut self.rng, &self.lower, &self.upper));
+        }
+        let costs = evaluate(op, &members, self.threads)?;
+        self.population.evaluations += costs.len();
+        self.population.replace(members, costs);
+        Ok(())
+    }
+
+    fn pick(&mut self, n: usize, exclude: &[usize]) -> usize {
+        loop {
+            let r = self.rng.gen_range(0..n);
+            if !exclude.contains(&r) {
+                return r;
+            }
+        }
+    }
+}
+
+impl<F> Solver<Minimize<F>> for DifferentialEvolution
+where
+    F: CostFunction + Sync,
+{
+    type ReportArg = Population;
+
+    fn next_iter(&mut self, op: &Minimize<F>, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+        if self.population.members.is_empty() {
+            self.initialize(op, x)?;
+        }
+        let n = self.population.members.len();
+        let dim = x.len();
+        let best = self
+            .population
+            .costs
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i)
+            .unwrap();
+
+        let mut trials = Vec::with_capacity(n);
+        for i in 0..n {
+            let r1 = self.pick(n, &[i]);
+            let r2 = self.pick(n, &[i, r1]);
+            let base = match self.strategy {
+                Strategy::Rand1Bin => self.pick(n, &[i, r1, r2]),
+                Strategy::Best1Bin => best,
+            };
+            let members = &self.population.members;
+            let mutant = &members[base] + &((&members[r1] - &members[r2]) * self.mutation);
+            let jrand = self.rng.gen_range(0..dim);
+            let mut trial = members[i].clone();
+            for j in 0..dim {
+                if j == jrand || self.rng.gen::<f64>() < self.crossover {
+                    trial[j] = mutant[j];
+                }
+            }
+            clip(&mut trial, &self.lower, &self.upper);
+            trials.push(trial);
+        }
+
+        let trial_costs = evaluate(op, &trials, self.threads)?;
+        self.population.evaluations += trial_costs.len();
+        let mut members = std::mem::take(&mut self.population.members);
+        let mut costs = std::mem::take(&mut self.population.costs);
+        for (i, (trial, c)) in trials.into_iter().zip(trial_costs).enumerate() {
+            if c <= costs[i] {
+                members[i] = trial;
+                costs[i] = c;
+            }
+        }
+        self.population.replace(members, costs);
+        Ok(self.population.best_or(x))
+    }
+
+    fn init_report<R: Report<Arg = Population>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.init(&self.population)
+    }
+
+    fn update_report<R: Report<Arg = Population>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.update(&self.population)
+    }
+}
+
+/// Particle swarm optimization within the box `[lower, upper]`.
+pub struct ParticleSwarm {
+    lower: Array1<f64>,
+    upper: Array1<f64>,
+    size: Option<usize>,
+    inertia: f64,
+    cognitive: f64,
+    social: f64,
+    threads: usize,
+    rng: StdRng,
+    velocities: Vec<Array1<f64>>,
+    personal: Vec<(Array1<f64>, f64)>,
+    population: Population,
+}
+
+impl ParticleSwarm {
+    pub fn new(lower: Array1<f64>, upper: Array1<f64>) -> Self {
+        assert_eq!(lower.len(), upper.len());
+        Self {
+            lower,
+            upper,
+            size: None,
+            inertia: 0.7298,
+            cognitive: 1.49618,
+            social: 1.49618,
+            threads: 1,
+            rng: new_rng(None),
+            velocities: Vec::new(),
+            personal: Vec::new(),
+            population: Population::default(),
+        }
+    }
+
+    /// Number of particles; `10 + 2 * dim` by default.
+    pub fn population_size(mut self, size: usize) -> Self {
+        assert!(size >= 2);
+        self.size = Some(size);
+        self
+    }
+
+    pub fn inertia(mut self, w: f64) -> Self {
+        self.inertia = w;
+        self
+    }
+
+    /// Attraction towards the personal best (`c1`) and towards the global best (`c2`).
+    pub fn acceleration(mut self, c1: f64, c2: f64) -> Self {
+        self.cognitive = c1;
+        self.social = c2;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = new_rng(Some(seed));
+        self
+    }
+
+    /// Evaluates the swarm on `n` threads.
+    pub fn threads(mut self, n: usize) -> Self {
+        self.threads = n;
+        self
+    }
+
+    pub fn population(&self) -> &Population {
+        &self.population
+    }
+
+    fn initialize<F>(&mut self, op: &Minimize<F>, x: &Array1<f64>) -> Result<(), Error>
+    where
+        F: CostFunction + Sync,
+    {
+        check_start(x, &self.lower)?;
+        let size = self.size.unwrap_or(10 + 2 * x.len());
+        let mut members = Vec::with_capacity(size);
+        let mut x = x.clone();
+        clip(&mut x, &self.lower, &self.upper);
+        members.push(x);
+        while members.len() < size {
+            members.push(uniform_in(&mut self.rng, &self.lower, &self.upper));
+        }
+        let range = &self.upper - &self.lower;
+        self.velocities = (0..size)
+            .map(|_| range.map(|r| r * (2. * self.rng.gen::<f64>() - 1.) * 0.1))
+            .collect();
+        let costs = evaluate(op, &members, self.threads)?;
+        self.population.evaluations += costs.len();
+        self.personal = members.iter().cloned().zip(costs.iter().cloned()).collect();
+        self.population.replace(members, costs);
+        Ok(())
+    }
+}
+
+impl<F> Solver<Minimize<F>> for ParticleSwarm
+where
+    F: CostFunction + Sync,
+{
+    type ReportArg = Population;
+
+    fn next_iter(&mut self, op: &Minimize<F>, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+        if self.population.members.is_empty() {
+            self.initialize(op, x)?;
+        }
+        let global = self.population.best_or(x);
+        let vmax = &self.upper - &self.lower;
+        let mut members = std::mem::take(&mut self.population.members);
+        for ((pos, vel), (pbest, _)) in members
+            .iter_mut()
+            .zip(self.velocities.iter_mut())
+            .zip(self.personal.iter())
+        {
+            for j in 0..pos.len() {
+                let r1: f64 = self.rng.gen();
+                let r2: f64 = self.rng.gen();
+                let v = self.inertia * vel[j]
+                    + self.cognitive * r1 * (pbest[j] - pos[j])
+                    + self.social * r2 * (global[j] - pos[j]);
+                vel[j] = v.max(-vmax[j]).min(vmax[j]);
+                pos[j] += vel[j];
+                if pos[j] < self.lower[j] || pos[j] > self.upper[j] {
+                    pos[j] = pos[j].max(self.lower[j]).min(self.upper[j]);
+                    vel[j] = 0.;
+                }
+            }
+        }
+
+        let costs = evaluate(op, &members, self.threads)?;
+        self.population.evaluations += costs.len();
+        for ((pbest, pcost), (pos, &c)) in self
+            .personal
+            .iter_mut()
+            .zip(members.iter().zip(costs.iter()))
+        {
+            if c < *pcost {
+                *pbest = pos.clone();
+                *pcost = c;
+            }
+        }
+        self.population.replace(members, costs);
+        Ok(self.population.best_or(x))
+    }
+
+    fn init_report<R: Report<Arg = Population>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.init(&self.population)
+    }
+
+    fn update_report<R: Report<Arg = Population>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.update(&self.population)
+    }
+}
+
+/// Covariance matrix adaptation evolution strategy, (mu/mu_w, lambda) variant.
+///
+/// The search starts from the initial variable passed to `Executor::run` with step size `sigma`.
+pub struct CmaEs {
+    sigma: f64,
+    lambda: Option<usize>,
+    threads: usize,
+    rng: StdRng,
+    state: Option<CmaState>,
+    population: Population,
+}
+
+struct CmaState {
+    mean: Array1<f64>,
+    sigma: f64,
+    weights: Array1<f64>,
+    mueff: f64,
+    cc: f64,
+    cs: f64,
+    c1: f64,
+    cmu: f64,
+    damps: f64,
+    chi_n: f64,
+    pc: Array1<f64>,
+    ps: Array1<f64>,
+    cov: Array2<f64>,
+    b: Array2<f64>,
+    d: Array1<f64>,
+    generation: usize,
+}
+
+impl CmaState {
+    fn new(mean: Array1<f64>, sigma: f64, lambda: usize) -> Self {
+        let n = mean.len() as f64;
+        let mu = lambda / 2;
+        let weights = Array1::from_shape_fn(mu, |i| {
+            (lambda as f64 / 2. + 0.5).ln() - ((i + 1) as f64).ln()
+        });
+        let weights = &weights / weights.sum();
+        let mueff = 1. / weights.dot(&weights);
+        let cc = (4. + mueff / n) / (n + 4. + 2. * mueff / n);
+        let cs = (mueff + 2.) / (n + mueff + 5.);
+        let c1 = 2. / ((n + 1.3) * (n + 1.3) + mueff);
+        let cmu = (1. - c1).min(2. * (mueff - 2. + 1. / mueff) / ((n + 2.) * (n + 2.) + mueff));
+        let damps = 1. + 2. * (((mueff - 1.) / (n + 1.)).sqrt() - 1.).max(0.) + cs;
+        let chi_n = n.sqrt() * (1. - 1. / (4. * n) + 1. / (21. * n * n));
+        let dim = mean.len();
+        Self {
+            mean,
+            sigma,
+            weights,
+            mueff,
+            cc,
+            cs,
+            c1,
+            cmu,
+            damps,
+            chi_n,
+            pc: Array1::zeros(dim),
+            ps: Array1::zeros(dim),
+            cov: Array2::eye(dim),
+            b: Array2::eye(dim),
+            d: Array1::ones(dim),
+            generation: 0,
+        }
+    }
+}
+
+impl CmaEs {
+    pub fn new(sigma: f64) -> Self {
+        Self {
+            sigma,
+            lambda: None,
+            threads: 1,
+            rng: new_rng(None),
+            state: None,
+            population: Population::default(),
+        }
+    }
+
+    /// Number of offspring per generation; `4 + floor(3 ln(dim))` by default.
+    pub fn population_size(mut self, lambda: usize) -> Self {
+        assert!(lambda >= 4);
+        self.lambda = Some(lambda);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = new_rng(Some(seed));
+        self
+    }
+
+    /// Evaluates the offspring on `n` threads.
+    pub fn threads(mut self, n: usize) -> Self {
+        self.threads = n;
+        self
+    }
+
+    pub fn population(&self) -> &Population {
+        &self.population
+    }
+
+    /// Current step size.
+    pub fn sigma(&self) -> f64 {
+        self.state.as_ref().map_or(self.sigma, |s| s.sigma)
+    }
+}
+
+impl<F> Solver<Minimize<F>> for CmaEs
+where
+    F: CostFunction + Sync,
+{
+    type ReportArg = Population;
+
+    fn next_iter(&mut self, op: &Minimize<F>, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+        let n = x.len();
+        if n == 0 {
+            return Err(Error::InvalidVariable);
+        }
+        let lambda = self
+            .lambda
+            .unwrap_or(4 + (3. * (n as f64).ln()).floor() as usize);
+        let sigma = self.sigma;
+        let rng = &mut self.rng;
+        let st = self
+            .state
+            .get_or_insert_with(|| CmaState::new(x.clone(), sigma, lambda));
+
+        let mut ys = Vec::with_capacity(lambda);
+        let mut members = Vec::with_capacity(lambda);
+        for _ in 0..lambda {
+            let z = Array1::from_shape_fn(n, |_| rng.sample::<f64, _>(StandardNormal));
+            let y = st.b.dot(&(&st.d * &z));
+            members.push(&st.mean + &(&y * st.sigma));
+            ys.push(y);
+        }
+        let costs = evaluate(op, &members, self.threads)?;
+        self.population.evaluations += costs.len();
+
+        let mut order: Vec<usize> = (0..lambda).collect();
+        order.sort_by(|&i, &j| costs[i].total_cmp(&costs[j]));
+        let mut yw = Array1::<f64>::zeros(n);
+        for (w, &i) in st.weights.iter().zip(order.iter()) {
+            yw.scaled_add(*w, &ys[i]);
+        }
+        st.mean.scaled_add(st.sigma, &yw);
+
+        // C^{-1/2} y_w = B D^{-1} B^T y_w
+        let inv_sqrt_c_yw = st.b.dot(&(&st.b.t().dot(&yw) / &st.d));
+        st.ps = &st.ps * (1. - st.cs) + &(inv_sqrt_c_yw * (st.cs * (2. - st.cs) * st.mueff).sqrt());
+        st.generation += 1;
+        let ps_norm = st.ps.dot(&st.ps).sqrt();
+        let hsig = ps_norm / (1. - (1. - st.cs).powi(2 * st.generation as i32)).sqrt() / st.chi_n
+            < 1.4 + 2. / (n as f64 + 1.);
+        let hsig = if hsig { 1. } else { 0. };
+        st.pc = &st.pc * (1. - st.cc) + &(&yw * (hsig * (st.cc * (2. - st.cc) * st.mueff).sqrt()));
+
+        let pc = st.pc.view().insert_axis(Axis(1));
+        let mut cov = &st.cov * (1. - st.c1 - st.cmu)
+            + &((&pc.dot(&pc.t()) + &(&st.cov * ((1. - hsig) * st.cc * (2. - st.cc)))) * st.c1);
+        for (w, &i) in st.weights.iter().zip(order.iter()) {
+            let y = ys[i].view().insert_axis(Axis(1));
+            cov.scaled_add(st.cmu * w, &y.dot(&y.t()));
+        }
+        st.cov = cov;
+        st.sigma *= ((st.cs / st.damps) * (ps_norm / st.chi_n - 1.)).exp();
+
+        let (eigenvalues, b) = linalg::symmetric_eigen(&st.cov);
+        st.d = eigenvalues.mapv(|v| v.max(0.).sqrt());
+        st.b = b;
+
+        self.population.replace(members, costs);
+        Ok(self.population.best_or(x))
+    }
+
+    fn init_report<R: Report<Arg = Population>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.init(&self.population)
+    }
+
+    fn update_report<R: Report<Arg = Population>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.update(&self.population)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::*;
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    fn rosenbrock(x: &Array1<f64>) -> f64 {
+        (1. - x[0]).powi(2) + 100. * (x[1] - x[0] * x[0]).powi(2)
+    }
+
+    #[test]
+    fn differential_evolution_rosenbrock() -> anyhow::Result<()> {
+        let solver = DifferentialEvolution::new(array![-5., -5.], array![5., 5.])
+            .population_size(30)
+            .seed(1);
+        let x = Executor::new(solver, Minimize(rosenbrock))
+            .report(PopulationReport::default())
+            .terminate(when(|r: &PopulationReport| {
+                r.best < 1e-12 || r.count >= 2000
+            }))
+            .run(array![-1., 2.])?;
+        assert_abs_diff_eq!(x[0], 1., epsilon = 1e-5);
+        assert_abs_diff_eq!(x[1], 1., epsilon = 1e-5);
+        Ok(())
+    }
+
+    #[test]
+    fn seeded_runs_do_not_depend_on_threads() -> anyhow::Result<()> {
+        let run = |threads| -> anyhow::Result<Array1<f64>> {
+            let solver = DifferentialEvolution::new(array![-5., -5.], array![5., 5.])
+                .seed(7)
+                .threads(threads);
+            Executor::new(solver, Minimize(rosenbrock))
+                .report(PopulationReport::default())
+                .terminate(when(|r: &PopulationReport| r.count >= 20))
+                .run(array![0., 0.])
+        };
+        assert_eq!(run(1)?, run(4)?);
+        Ok(())
+    }
+
+    #[test]
+    fn start_outside_the_dimension() {
+        let sphere = |x: &Array1<f64>| x.dot(x);
+        let run = |solver, x0| {
+            Executor::new(solver, Minimize(sphere))
+                .report(PopulationReport::default())
+                .terminate(when(|r: &PopulationReport| r.count >= 5))
+                .run(x0)
+        };
+        let bounds = || (array![-1., -1.], array![1., 1.]);
+        let (lower, upper) = bounds();
+        assert!(run(DifferentialEvolution::new(lower, upper), array![0., 0., 0.]).is_err());
+        let (lower, upper) = bounds();
+        assert!(run(DifferentialEvolution::new(lower, upper), array![]).is_err());
+        let (lower, upper) = bounds();
+        assert!(
+            Executor::new(ParticleSwarm::new(lower, upper), Minimize(sphere))
+                .report(PopulationReport::default())
+                .terminate(when(|r: &PopulationReport| r.count >= 5))
+                .run(array![0.])
+                .is_err()
+        );
+        assert!(Executor::new(CmaEs::new(0.5), Minimize(sphere))
+            .report(PopulationReport::default())
+            .terminate(when(|r: &PopulationReport| r.count >= 5))
+            .run(array![])
+            .is_err());
+    }
+
+    #[test]
+    fn cmaes_rosenbrock() -> anyhow::Result<()> {
+        let solver = CmaEs::new(0.5).seed(3).threads(2);
+        let x = Executor::new(solver, Minimize(rosenbrock))
+            .report(PopulationReport::default())
+            .terminate(when(|r: &PopulationReport| {
+                r.best < 1e-14 || r.count >= 2000
+            }))
+            .run(array![-1., 2.])?;
+        assert_abs_diff_eq!(x[0], 1., epsilon = 1e-5);
+        assert_abs_diff_eq!(x[1], 1., epsilon = 1e-5);
+        Ok(())
+    }
+
+    #[test]
+    fn particle_swarm_reports_to_table() -> anyhow::Result<()> {
+        let sphere = |x: &Array1<f64>| x.dot(x);
+        let mut buf = Vec::<u8>::new();
+        {
+            let mut table = table_dump::Table::from_writer(&mut buf);
+            let solver = ParticleSwarm::new(array![-3., -3., -3.], array![3., 3., 3.]).seed(5);
+            let x = Executor::new(solver, Minimize(sphere))
+                .report(PopulationReport::default())
+                .add_monitor(move |r: &PopulationReport| table.serialize(r))
+                .terminate(when(|r: &PopulationReport| {
+                    r.best < 1e-10 || r.count >= 1000
+                }))
+                .run(array![1., 2., -1.])?;
+            assert!(x.dot(&x) < 1e-10);
+        }
+        let log = String::from_utf8(buf)?;
+        assert!(log.starts_with("count\tevaluations\tbest\tmean\tspread\n"));
+        Ok(())
+    }
+}
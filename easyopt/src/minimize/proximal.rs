@@ -0,0 +1,504 @@
+use super::bounded::Bounds;
+use super::{gradient, DifferentiableCost, Minimize};
+use crate::error::*;
+use crate::traits::*;
+use ndarray::prelude::*;
+use serde::Serialize;
+
+/// Nonsmooth convex term `g` of a composite objective, given by its value and its proximal
+/// operator `prox_{t g}(x) = argmin_z g(z) + |z - x|^2 / (2 t)`.
+pub trait Prox {
+    /// Value of `g`, infinite outside the domain of an indicator function.
+    fn value(&self, x: &Array1<f64>) -> f64;
+
+    fn prox(&self, x: &Array1<f64>, t: f64) -> Array1<f64>;
+}
+
+fn soft_threshold(x: &Array1<f64>, threshold: f64) -> Array1<f64> {
+    x.mapv(|v| v.signum() * (v.abs() - threshold).max(0.))
+}
+
+/// `lambda |x|_1`.
+#[derive(Debug, Clone, Copy)]
+pub struct L1(pub f64);
+
+impl Prox for L1 {
+    fn value(&self, x: &Array1<f64>) -> f64 {
+        self.0 * x.iter().map(|v| v.abs()).sum::<f64>()
+    }
+
+    fn prox(&self, x: &Array1<f64>, t: f64) -> Array1<f64> {
+        soft_threshold(x, t * self.0)
+    }
+}
+
+/// `l1 |x|_1 + l2 |x|^2 / 2`.
+#[derive(Debug, Clone, Copy)]
+pub struct ElasticNet {
+    pub l1: f64,
+    pub l2: f64,
+}
+
+impl Prox for ElasticNet {
+    fn value(&self, x: &Array1<f64>) -> f64 {
+        self.l1 * x.iter().map(|v| v.abs()).sum::<f64>() + 0.5 * self.l2 * x.dot(x)
+    }
+
+    fn prox(&self, x: &Array1<f64>, t: f64) -> Array1<f64> {
+        soft_threshold(x, t * self.l1) / (1. + t * self.l2)
+    }
+}
+
+/// Indicator of the Euclidean ball of the given radius around the origin.
+#[derive(Debug, Clone, Copy)]
+pub struct L2Ball(pub f64);
+
+impl Prox for L2Ball {
+    fn value(&self, x: &Array1<f64>) -> f64 {
+        if x.dot(x).sqrt() <= self.0 * (1. + f64::EPSILON) {
+            0.
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    fn prox(&self, x: &Array1<f64>, _t: f64) -> Array1<f64> {
+        let norm = x.dot(x).sqrt();
+        if norm <= self.0 {
+            x.clone()
+        } else {
+            x * (self.0 / norm)
+        }
+    }
+}
+
+/// Indicator of the nonnegative orthant.
+#[derive(Debug, Clone, Copy)]
+pub struct Nonnegative;
+
+impl Prox for Nonnegative {
+    fn value(&self, x: &Array1<f64>) -> f64 {
+        if x.iter().all(|&v| v >= 0.) {
+            0.
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    fn prox(&self, x: &Array1<f64>, _t: f64) -> Array1<f64> {
+        x.mapv(|v| v.max(0.))
+    }
+}
+
+/// Indicator of the box.
+impl Prox for Bounds {
+    fn value(&self, x: &Array1<f64>) -> f64 {
+        if self.contains(x) {
+            0.
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    fn prox(&self, x: &Array1<f64>, _t: f64) -> Array1<f64> {
+        self.project(x)
+    }
+}
+
+/// Objective `smooth(x) + nonsmooth(x)` for the proximal gradient methods.
+pub struct Composite<F, G> {
+    pub smooth: F,
+    pub nonsmooth: G,
+}
+
+impl<F, G> Composite<F, G> {
+    pub fn new(smooth: F, nonsmooth: G) -> Self {
+        Self { smooth, nonsmooth }
+    }
+}
+
+/// Criterion for restarting the momentum of FISTA.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Restart {
+    Never,
+    /// Restart when the objective increases.
+    Function,
+    /// Restart when the momentum points against the proximal gradient step.
+    Gradient,
+}
+
+/// Quantities describing the last proximal gradient iteration.
+#[derive(Debug, Clone)]
+pub struct ProximalState {
+    pub cost: f64,
+    /// Step length `1 / L` after the backtracking on the Lipschitz constant `L`.
+    pub step: f64,
+    /// Norm of the gradient mapping `L (x_prev - x)`, which vanishes at a minimizer.
+    pub residual: f64,
+    /// Number of nonzero components of the variable.
+    pub nonzeros: usize,
+    pub restarts: usize,
+}
+
+impl ProximalState {
+    fn new(step: f64) -> Self {
+        Self {
+            cost: f64::NAN,
+            step,
+            residual: f64::NAN,
+            nonzeros: 0,
+            restarts: 0,
+        }
+    }
+}
+
+/// One proximal gradient step from `y` with backtracking on the Lipschitz constant.
+fn prox_step<F, G, const N: usize>(
+    problem: &Composite<F, G>,
+    y: &Array1<f64>,
+    lipschitz: &mut f64,
+) -> Result<Array1<f64>, Error>
+where
+    F: DifferentiableCost,
+    G: Prox,
+{
+    let (fy, g) = gradient::<F, N>(&problem.smooth, y)?;
+    for _ in 0..100 {
+        let t = 1. / *lipschitz;
+        let z = problem.nonsmooth.prox(&(y - &(&g * t)), t);
+        let d = &z - y;
+        let bound = fy + g.dot(&d) + 0.5 * *lipschitz * d.dot(&d);
+        match problem.smooth.cost::<f64>(&z.to_vec()) {
+            Ok(fz) if fz <= bound + 1e-12 * fy.abs() => return Ok(z),
+            Ok(_) | Err(Error::InvalidVariable) => *lipschitz *= 2.,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(Error::Failure(String::from(
+        "no step length satisfies the sufficient decrease condition",
+    )))
+}
+
+fn composite_cost<F, G>(problem: &Composite<F, G>, x: &Array1<f64>) -> Result<f64, Error>
+where
+    F: DifferentiableCost,
+    G: Prox,
+{
+    Ok(problem.smooth.cost::<f64>(&x.to_vec())? + problem.nonsmooth.value(x))
+}
+
+/// Proximal gradient method (ISTA) with backtracking on the step length.
+pub struct Ista<const N: usize> {
+    lipschitz: f64,
+    state: ProximalState,
+}
+
+impl<const N: usize> Default for Ista<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Ista<N> {
+    pub fn new() -> Self {
+        Self {
+            lipschitz: 1.,
+            state: ProximalState::new(1.),
+        }
+    }
+
+    /// Initial estimate of the Lipschitz constant of the smooth gradient; 1 by default.
+    pub fn lipschitz(mut self, l: f64) -> Self {
+        self.lipschitz = l;
+        self.state.step = 1. / l;
+        self
+    }
+}
+
+impl<F, G, const N: usize> Solver<Minimize<Composite<F, G>>> for Ista<N>
+where
+    F: DifferentiableCost,
+    G: Prox,
+{
+    type ReportArg = ProximalState;
+
+    fn next_iter(
+        &mut self,
+        op: &Minimize<Composite<F, G>>,
+        x: &Array1<f64>,
+    ) -> Result<Array1<f64>, Error> {
+        let z = prox_step::<F, G, N>(&op.0, x, &mut self.lipschitz)?;
+        let d = &z - x;
+        self.state.cost = composite_cost(&op.0, &z)?;
+        self.state.step = 1. / self.lipschitz;
+        self.state.residual = self.lipschitz * d.dot(&d).sqrt();
+        self.state.nonzeros = z.iter().filter(|&&v| v != 0.).count();
+        Ok(z)
+    }
+
+    fn init_report<R: Report<Arg = ProximalState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.init(&self.state)
+    }
+
+    fn update_report<R: Report<Arg = ProximalState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.update(&self.state)
+    }
+}
+
+/// Accelerated proximal gradient method (FISTA) with adaptive restart of the momentum.
+pub struct Fista<const N: usize> {
+    lipschitz: f64,
+    restart: Restart,
+    momentum: f64,
+    y: Option<Array1<f64>>,
+    state: ProximalState,
+}
+
+impl<const N: usize> Default for Fista<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Fista<N> {
+    pub fn new() -> Self {
+        Self {
+            lipschitz: 1.,
+            restart: Restart::Gradient,
+            momentum: 1.,
+            y: None,
+            state: ProximalState::new(1.),
+        }
+    }
+
+    /// Initial estimate of the Lipschitz constant of the smooth gradient; 1 by default.
+    pub fn lipschitz(mut self, l: f64) -> Self {
+        self.lipschitz = l;
+        self.state.step = 1. / l;
+        self
+    }
+
+    /// Restart criterion; `Restart::Gradient` by default.
+    pub fn restart(mut self, restart: Restart) -> Self {
+        self.restart = restart;
+        self
+    }
+}
+
+impl<F, G, const N: usize> Solver<Minimize<Composite<F, G>>> for Fista<N>
+where
+    F: DifferentiableCost,
+    G: Prox,
+{
+    type ReportArg = ProximalState;
+
+    fn next_iter(
+        &mut self,
+        op: &Minimize<Composite<F, G>>,
+        x: &Array1<f64>,
+    ) -> Result<Array1<f64>, Error> {
+        let y = self.y.take().unwrap_or_else(|| x.clone());
+        let z = prox_step::<F, G, N>(&op.0, &y, &mut self.lipschitz)?;
+        let cost = composite_cost(&op.0, &z)?;
+
+        let restart = match self.restart {
+            Restart::Never => false,
+            Restart::Function => cost > self.state.cost,
+            Restart::Gradient => (&y - &z).dot(&(&z - x)) > 0.,
+        };
+        let t = if restart {
+            self.state.restarts += 1;
+            1.
+        } else {
+            self.momentum
+        };
+        let t_next = 0.5 * (1. + (1. + 4. * t * t).sqrt());
+        self.y = Some(&z + &((&z - x) * ((t - 1.) / t_next)));
+        self.momentum = t_next;
+
+        let d = &z - &y;
+        self.state.cost = cost;
+        self.state.step = 1. / self.lipschitz;
+        self.state.residual = self.lipschitz * d.dot(&d).sqrt();
+        self.state.nonzeros = z.iter().filter(|&&v| v != 0.).count();
+        Ok(z)
+    }
+
+    fn init_report<R: Report<Arg = ProximalState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.init(&self.state)
+    }
+
+    fn update_report<R: Report<Arg = ProximalState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.update(&self.state)
+    }
+}
+
+#[derive(Serialize)]
+pub struct ProximalReport {
+    pub count: usize,
+    pub cost: f64,
+    pub step: f64,
+    pub residual: f64,
+    pub nonzeros: usize,
+    pub restarts: usize,
+}
+
+impl Report for ProximalReport {
+    type Arg = ProximalState;
+
+    fn init(&mut self, s: &ProximalState) -> Result<(), Error> {
+        *self = Self::default();
+        self.step = s.step;
+        Ok(())
+    }
+
+    fn update(&mut self, s: &ProximalState) -> Result<(), Error> {
+        self.count += 1;
+        self.cost = s.cost;
+        self.step = s.step;
+        self.residual = s.residual;
+        self.nonzeros = s.nonzeros;
+        self.restarts = s.restarts;
+        Ok(())
+    }
+}
+
+impl Default for ProximalReport {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            cost: f64::NAN,
+            step: f64::NAN,
+            residual: f64::NAN,
+            nonzeros: 0,
+            restarts: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::*;
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use num_traits::Float;
+
+    /// `|A x - b|^2 / 2`
+    struct LeastSquares {
+        a: Array2<f64>,
+        b: Array1<f64>,
+    }
+
+    impl LeastSquares {
+        fn new() -> Self {
+            Self {
+                a: array![
+                    [1., 0.2, 0.1, 0.],
+                    [0.3, 1., 0., 0.2],
+                    [0., 0.1, 1., 0.4],
+                    [0.2, 0., 0.3, 1.],
+                    [0.5, 0.5, 0., 0.]
+                ],
+                b: array![1., -0.05, 0.02, 2., 0.4],
+            }
+        }
+
+        fn grad(&self, x: &Array1<f64>) -> Array1<f64> {
+            self.a.t().dot(&(self.a.dot(x) - &self.b))
+        }
+    }
+
+    impl DifferentiableCost for LeastSquares {
+        fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+            let half = T::from(0.5).unwrap();
+            Ok(self
+                .a
+                .outer_iter()
+                .zip(self.b.iter())
+                .fold(T::zero(), |acc, (row, &b)| {
+                    let r = row
+                        .iter()
+                        .zip(x.iter())
+                        .fold(T::from(-b).unwrap(), |acc, (&a, &x)| {
+                            acc + T::from(a).unwrap() * x
+                        });
+                    acc + half * r * r
+                }))
+        }
+    }
+
+    fn solve<S>(solver: S) -> anyhow::Result<(Array1<f64>, usize)>
+    where
+        S: Solver<Minimize<Composite<LeastSquares, L1>>, ReportArg = ProximalState>,
+    {
+        let mut count = 0;
+        let x = Executor::new(
+            solver,
+            Minimize(Composite::new(LeastSquares::new(), L1(0.3))),
+        )
+        .report(ProximalReport::default())
+        .add_monitor(|r: &ProximalReport| {
+            count = r.count;
+            Ok(())
+        })
+        .terminate(when(|r: &ProximalReport| {
+            r.residual < 1e-10 || r.count >= 10000
+        }))
+        .run(Array1::zeros(4))?;
+        Ok((x, count))
+    }
+
+    #[test]
+    fn lasso() -> anyhow::Result<()> {
+        let (ista, ista_count) = solve(Ista::<4>::new())?;
+        let (fista, fista_count) = solve(Fista::<4>::new())?;
+        let (plain, _) = solve(Fista::<4>::new().restart(Restart::Never))?;
+        assert!(fista_count < ista_count);
+        for x in [&ista, &fista, &plain] {
+            // subgradient optimality of the L1 term
+            let g = LeastSquares::new().grad(x);
+            for (xi, gi) in x.iter().zip(g.iter()) {
+                if *xi == 0. {
+                    assert!(gi.abs() <= 0.3 + 1e-8);
+                } else {
+                    assert_abs_diff_eq!(*gi, -0.3 * xi.signum(), epsilon = 1e-8);
+                }
+            }
+        }
+        assert!(fista.iter().any(|&v| v == 0.));
+        Ok(())
+    }
+
+    #[test]
+    fn proximal_operators() {
+        let x = array![1.5, -0.2, 0.5, -3.];
+        assert_eq!(L1(0.5).prox(&x, 1.), array![1., 0., 0., -2.5]);
+        assert_eq!(
+            ElasticNet { l1: 0.5, l2: 1. }.prox(&x, 1.),
+            array![0.5, 0., 0., -1.25]
+        );
+        let p = L2Ball(1.).prox(&x, 1.);
+        assert_abs_diff_eq!(p.dot(&p), 1., epsilon = 1e-15);
+        assert_eq!(L2Ball(1.).value(&p), 0.);
+        assert_eq!(Nonnegative.prox(&x, 1.), array![1.5, 0., 0.5, 0.]);
+        assert_eq!(Nonnegative.value(&x), f64::INFINITY);
+        let bounds = Bounds::new(Array1::from_elem(4, -1.), Array1::from_elem(4, 1.));
+        assert_eq!(bounds.prox(&x, 1.), array![1., -0.2, 0.5, -1.]);
+    }
+}
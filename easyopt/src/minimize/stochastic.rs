@@ -0,0 +1,522 @@
+use super::population::new_rng;
+use super::{check_dimension, Minimize};
+use crate::error::*;
+use crate::traits::*;
+use dual::Variables;
+use ndarray::prelude::*;
+use ndarray::Zip;
+use num_traits::Float;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use serde::Serialize;
+
+/// Objective given as a sum over data points, evaluated on minibatches.
+pub trait MinibatchLoss {
+    /// Number of data points.
+    fn n_samples(&self) -> usize;
+
+    /// Loss of the data points with indices `batch` at `params`.
+    fn loss<T: Float>(&self, params: &[T], batch: &[usize]) -> Result<T, Error>;
+}
+
+/// Loss and gradient on a minibatch with `Dual<f64, N>`.
+pub fn batch_gradient<F, const N: usize>(
+    f: &F,
+    params: &Array1<f64>,
+    batch: &[usize],
+) -> Result<(f64, Array1<f64>), Error>
+where
+    F: MinibatchLoss + ?Sized,
+{
+    check_dimension::<N>(params)?;
+    let vars = Variables::<f64, N>::new().gen_all(&params.to_vec());
+    let y = f.loss(&vars, batch)?;
+    Ok((*y.val(), y.grad().to_owned()))
+}
+
+/// Learning rate as a function of the number of steps and completed epochs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Schedule {
+    Constant(f64),
+    /// `rate * factor^(epoch / epochs)`, decaying once every `epochs` epochs.
+    Step {
+        rate: f64,
+        factor: f64,
+        epochs: usize,
+    },
+    /// `rate * gamma^epoch`.
+    Exponential {
+        rate: f64,
+        gamma: f64,
+    },
+    /// `rate / (1 + decay * step)`.
+    InverseTime {
+        rate: f64,
+        decay: f64,
+    },
+}
+
+impl Schedule {
+    pub fn rate(&self, step: usize, epoch: usize) -> f64 {
+        match *self {
+            Schedule::Constant(rate) => rate,
+            Schedule::Step {
+                rate,
+                factor,
+                epochs,
+            } => rate * factor.powi((epoch / epochs.max(1)) as i32),
+            Schedule::Exponential { rate, gamma } => rate * gamma.powi(epoch as i32),
+            Schedule::InverseTime { rate, decay } => rate / (1. + decay * step as f64),
+        }
+    }
+}
+
+/// Clipping applied to each minibatch gradient before the update.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Clipping {
+    None,
+    /// Clips each component to `[-c, c]`.
+    Value(f64),
+    /// Rescales the gradient so that its Euclidean norm does not exceed `c`.
+    Norm(f64),
+}
+
+impl Clipping {
+    fn apply(&self, g: &mut Array1<f64>) {
+        match *self {
+            Clipping::None => {}
+            Clipping::Value(c) => g.mapv_inplace(|v| v.max(-c).min(c)),
+            Clipping::Norm(c) => {
+                let norm = g.dot(g).sqrt();
+                if norm > c {
+                    *g *= c / norm;
+                }
+            }
+        }
+    }
+}
+
+/// Update rule of `Stochastic`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    Sgd,
+    /// Heavy-ball momentum, or Nesterov's variant.
+    Momentum {
+        beta: f64,
+        nesterov: bool,
+    },
+    Adam {
+        beta1: f64,
+        beta2: f64,
+        eps: f64,
+    },
+    AdaGrad {
+        eps: f64,
+    },
+    RmsProp {
+        rho: f64,
+        eps: f64,
+    },
+}
+
+/// Quantities describing the last minibatch step.
+#[derive(Debug, Clone)]
+pub struct StochasticState {
+    /// Number of completed epochs.
+    pub epoch: usize,
+    /// Number of minibatches processed in the current epoch.
+    pub batch: usize,
+    /// Loss on the last minibatch.
+    pub loss: f64,
+    /// Mean minibatch loss over the last completed epoch.
+    pub epoch_loss: f64,
+    pub grad_norm: f64,
+    pub learning_rate: f64,
+}
+
+/// First-order stochastic optimizer; every iteration of `Executor` processes one minibatch.
+///
+/// The data points are visited in an order shuffled at the start of every epoch, so a seeded
+/// run is reproducible.
+pub struct Stochastic<const N: usize> {
+    method: Method,
+    schedule: Schedule,
+    clipping: Clipping,
+    batch_size: usize,
+    shuffle: bool,
+    rng: StdRng,
+    order: Vec<usize>,
+    position: usize,
+    step: usize,
+    epoch_sum: f64,
+    m: Array1<f64>,
+    v: Array1<f64>,
+    state: StochasticState,
+}
+
+impl<const N: usize> Stochastic<N> {
+    pub fn new(method: Method) -> Self {
+        Self {
+            method,
+            schedule: Schedule::Constant(match method {
+                Method::Adam { .. } | Method::RmsProp { .. } => 1e-3,
+                _ => 1e-2,
+            }),
+            clipping: Clipping::None,
+            batch_size: 32,
+            shuffle: true,
+            rng: new_rng(None),
+            order: Vec::new(),
+            position: 0,
+            step: 0,
+            epoch_sum: 0.,
+            m: Array1::zeros(N),
+            v: Array1::zeros(N),
+            state: StochasticState {
+                epoch: 0,
+                batch: 0,
+                loss: f64::NAN,
+                epoch_loss: f64::NAN,
+                grad_norm: f64::NAN,
+                learning_rate: f64::NAN,
+            },
+        }
+    }
+
+    pub fn sgd() -> Self {
+        Self::new(Method::Sgd)
+    }
+
+    pub fn momentum(beta: f64) -> Self {
+        Self::new(Method::Momentum {
+            beta,
+            nesterov: false,
+        })
+    }
+
+    pub fn nesterov(beta: f64) -> Self {
+        Self::new(Method::Momentum {
+            beta,
+            nesterov: true,
+        })
+    }
+
+    pub fn adam() -> Self {
+        Self::new(Method::Adam {
+            beta1: 0.9,
+            beta2: 0.999,
+            eps: 1e-8,
+        })
+    }
+
+    pub fn adagrad() -> Self {
+        Self::new(Method::AdaGrad { eps: 1e-10 })
+    }
+
+    pub fn rmsprop() -> Self {
+        Self::new(Method::RmsProp {
+            rho: 0.9,
+            eps: 1e-8,
+        })
+    }
+
+    /// Constant learning rate; 1e-3 for Adam and RMSProp and 1e-2 otherwise by default.
+    pub fn learning_rate(self, rate: f64) -> Self {
+        self.schedule(Schedule::Constant(rate))
+    }
+
+    pub fn schedule(mut self, schedule: Schedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    pub fn clipping(mut self, clipping: Clipping) -> Self {
+        self.clipping = clipping;
+        self
+    }
+
+    /// Number of data points per minibatch; 32 by default. The last minibatch of an epoch
+    /// may be smaller.
+    pub fn batch_size(mut self, n: usize) -> Self {
+        self.batch_size = n.max(1);
+        self
+    }
+
+    /// Whether to shuffle the data points every epoch; true by default.
+    pub fn shuffle(mut self, shuffle: bool) -> Self {
+        self.shuffle = shuffle;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = new_rng(Some(seed));
+        self
+    }
+
+    fn next_batch(&mut self, n: usize) -> Result<&[usize], Error> {
+        if n == 0 {
+            return Err(Error::Failure(String::from("no data points")));
+        }
+        if self.order.len() != n {
+            self.order = (0..n).collect();
+            self.position = n;
+        }
+        if self.position >= n {
+            if self.shuffle {
+                self.order.shuffle(&mut self.rng);
+            }
+            self.position = 0;
+        }
+        let start = self.position;
+        self.position = (start + self.batch_size).min(n);
+        Ok(&self.order[start..self.position])
+    }
+
+    /// Parameter update from the gradient `g` with learning rate `lr`.
+    fn update(&mut self, x: &Array1<f64>, g: &Array1<f64>, lr: f64) -> Array1<f64> {
+        match self.method {
+            Method::Sgd => x - &(g * lr),
+            Method::Momentum { beta, nesterov } => {
+                self.m = &self.m * beta + g;
+                if nesterov {
+                    x - &((&self.m * beta + g) * lr)
+                } else {
+                    x - &(&self.m * lr)
+                }
+            }
+            Method::Adam { beta1, beta2, eps } => {
+                let t = self.step as i32;
+                self.m = &self.m * beta1 + &(g * (1. - beta1));
+                self.v = &self.v * beta2 + &(g * g * (1. - beta2));
+                let c1 = 1. - beta1.powi(t);
+                let c2 = 1. - beta2.powi(t);
+                Zip::from(x)
+                    .and(&self.m)
+                    .and(&self.v)
+                    .map_collect(|&x, &m, &v| x - lr * (m / c1) / ((v / c2).sqrt() + eps))
+            }
+            Method::AdaGrad { eps } => {
+                self.v += &(g * g);
+                Zip::from(x)
+                    .and(g)
+                    .and(&self.v)
+                    .map_collect(|&x, &g, &v| x - lr * g / (v.sqrt() + eps))
+            }
+            Method::RmsProp { rho, eps } => {
+                self.v = &self.v * rho + &(g * g * (1. - rho));
+                Zip::from(x)
+                    .and(g)
+                    .and(&self.v)
+                    .map_collect(|&x, &g, &v| x - lr * g / (v.sqrt() + eps))
+            }
+        }
+    }
+}
+
+impl<F, const N: usize> Solver<Minimize<F>> for Stochastic<N>
+where
+    F: MinibatchLoss,
+{
+    type ReportArg = StochasticState;
+
+    fn next_iter(&mut self, op: &Minimize<F>, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+        let n = op.0.n_samples();
+        let batch = self.next_batch(n)?.to_vec();
+        let (loss, mut g) = batch_gradient::<F, N>(&op.0, x, &batch)?;
+        self.clipping.apply(&mut g);
+
+        let lr = self.schedule.rate(self.step, self.state.epoch);
+        self.step += 1;
+        let next = self.update(x, &g, lr);
+
+        self.epoch_sum += loss;
+        self.state.batch += 1;
+        self.state.loss = loss;
+        self.state.grad_norm = g.dot(&g).sqrt();
+        self.state.learning_rate = lr;
+        if self.position >= n {
+            self.state.epoch_loss = self.epoch_sum / self.state.batch as f64;
+            self.state.epoch += 1;
+            self.state.batch = 0;
+            self.epoch_sum = 0.;
+        }
+        Ok(next)
+    }
+
+    fn init_report<R: Report<Arg = StochasticState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.init(&self.state)
+    }
+
+    fn update_report<R: Report<Arg = StochasticState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.update(&self.state)
+    }
+}
+
+#[derive(Serialize)]
+pub struct StochasticReport {
+    pub count: usize,
+    pub epoch: usize,
+    pub batch: usize,
+    pub loss: f64,
+    pub epoch_loss: f64,
+    pub grad_norm: f64,
+    pub learning_rate: f64,
+}
+
+impl Report for StochasticReport {
+    type Arg = StochasticState;
+
+    fn init(&mut self, s: &StochasticState) -> Result<(), Error> {
+        *self = Self::default();
+        self.epoch = s.epoch;
+        self.batch = s.batch;
+        Ok(())
+    }
+
+    fn update(&mut self, s: &StochasticState) -> Result<(), Error> {
+        self.count += 1;
+        self.epoch = s.epoch;
+        self.batch = s.batch;
+        self.loss = s.loss;
+        self.epoch_loss = s.epoch_loss;
+        self.grad_norm = s.grad_norm;
+        self.learning_rate = s.learning_rate;
+        Ok(())
+    }
+}
+
+impl Default for StochasticReport {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            epoch: 0,
+            batch: 0,
+            loss: f64::NAN,
+            epoch_loss: f64::NAN,
+            grad_norm: f64::NAN,
+            learning_rate: f64::NAN,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::*;
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use num_traits::Float;
+
+    /// Mean squared error of the line `a x + b` on points of `y = 2 x + 1`.
+    struct Line {
+        x: Vec<f64>,
+        y: Vec<f64>,
+    }
+
+    impl Line {
+        fn new(n: usize) -> Self {
+            let x: Vec<f64> = (0..n).map(|i| i as f64 / n as f64 * 2. - 1.).collect();
+            let y = x.iter().map(|x| 2. * x + 1.).collect();
+            Self { x, y }
+        }
+    }
+
+    impl MinibatchLoss for Line {
+        fn n_samples(&self) -> usize {
+            self.x.len()
+        }
+
+        fn loss<T: Float>(&self, params: &[T], batch: &[usize]) -> Result<T, Error> {
+            let sum = batch.iter().fold(T::zero(), |acc, &i| {
+                let x = T::from(self.x[i]).unwrap();
+                let y = T::from(self.y[i]).unwrap();
+                acc + (params[0] * x + params[1] - y).powi(2)
+            });
+            Ok(sum * T::from(batch.len()).unwrap().recip())
+        }
+    }
+
+    fn fit(solver: Stochastic<2>, epochs: usize) -> anyhow::Result<Array1<f64>> {
+        Executor::new(solver, Minimize(Line::new(100)))
+            .report(StochasticReport::default())
+            .terminate(when(move |r: &StochasticReport| r.epoch >= epochs))
+            .run(array![0., 0.])
+    }
+
+    #[test]
+    fn linear_regression() -> anyhow::Result<()> {
+        let solvers = vec![
+            Stochastic::sgd().learning_rate(0.1),
+            Stochastic::momentum(0.9).learning_rate(0.02),
+            Stochastic::nesterov(0.9).learning_rate(0.02),
+            Stochastic::adam().learning_rate(0.05),
+            Stochastic::adagrad().learning_rate(0.5),
+            Stochastic::rmsprop()
+                .schedule(Schedule::Exponential {
+                    rate: 0.05,
+                    gamma: 0.97,
+                })
+                .clipping(Clipping::Norm(1.)),
+        ];
+        for solver in solvers {
+            let x = fit(solver.batch_size(10).seed(3), 200)?;
+            assert_abs_diff_eq!(x[0], 2., epsilon = 1e-3);
+            assert_abs_diff_eq!(x[1], 1., epsilon = 1e-3);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn epochs_and_batches() -> anyhow::Result<()> {
+        let mut rows = Vec::new();
+        let x = Executor::new(
+            Stochastic::<2>::sgd().batch_size(32).seed(1),
+            Minimize(Line::new(100)),
+        )
+        .report(StochasticReport::default())
+        .add_monitor(|r: &StochasticReport| {
+            rows.push((r.count, r.epoch, r.batch));
+            Ok(())
+        })
+        .terminate(when(|r: &StochasticReport| r.epoch >= 2))
+        .run(array![0., 0.])?;
+        // four minibatches per epoch, the last one holding 4 points
+        assert_eq!(rows.len(), 9);
+        assert_eq!(rows[4], (4, 1, 0));
+        assert_eq!(rows[6], (6, 1, 2));
+        assert_eq!(rows[8], (8, 2, 0));
+
+        let y = fit(Stochastic::sgd().batch_size(32).seed(1), 2)?;
+        assert_eq!(x, y);
+        Ok(())
+    }
+
+    #[test]
+    fn schedules() {
+        let step = Schedule::Step {
+            rate: 1.,
+            factor: 0.5,
+            epochs: 10,
+        };
+        assert_eq!(step.rate(0, 9), 1.);
+        assert_eq!(step.rate(0, 25), 0.25);
+        let inv = Schedule::InverseTime {
+            rate: 1.,
+            decay: 0.5,
+        };
+        assert_eq!(inv.rate(2, 0), 0.5);
+        let mut g = array![3., -4.];
+        Clipping::Norm(1.).apply(&mut g);
+        assert_abs_diff_eq!(g.dot(&g), 1., epsilon = 1e-15);
+        let mut g = array![3., -4.];
+        Clipping::Value(1.).apply(&mut g);
+        assert_eq!(g, array![1., -1.]);
+    }
+}
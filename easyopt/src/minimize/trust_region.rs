@@ -0,0 +1,450 @@
+use super::{hessian, DifferentiableCost, Minimize};
+use crate::error::*;
+use crate::linalg;
+use crate::traits::*;
+use ndarray::prelude::*;
+use ndarray::Zip;
+use serde::Serialize;
+
+/// Method used to solve the trust-region subproblem
+/// `min g^T p + p^T H p / 2` subject to `|p| <= radius`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Subproblem {
+    /// Dogleg path between the Cauchy point and the Newton step.
+    Dogleg,
+    /// Steihaug-Toint truncated conjugate gradient.
+    Steihaug,
+    /// Nearly exact solution by the More-Sorensen iteration.
+    Exact,
+}
+
+/// Kind of step taken in the last iteration.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum StepType {
+    /// No step has been taken.
+    None,
+    /// Full Newton step inside the region.
+    Newton,
+    /// Steepest-descent step, cut at the boundary if needed.
+    Cauchy,
+    /// Dogleg step on the boundary.
+    Dogleg,
+    /// Unconstrained minimizer of the model inside the region.
+    Interior,
+    /// Constrained minimizer on the boundary.
+    Boundary,
+    /// Direction of negative curvature followed to the boundary.
+    NegativeCurvature,
+    /// Boundary step of the hard case, where the gradient has no component along the
+    /// eigenvector of the smallest eigenvalue.
+    HardCase,
+}
+
+/// Quantities describing the last trust-region iteration.
+#[derive(Debug, Clone)]
+pub struct TrustRegionState {
+    pub cost: f64,
+    pub grad_norm: f64,
+    pub radius: f64,
+    pub step_norm: f64,
+    pub predicted: f64,
+    pub actual: f64,
+    pub rho: f64,
+    pub accepted: bool,
+    pub step: StepType,
+}
+
+/// Quadratic model of the cost around `x`.
+struct Model {
+    x: Array1<f64>,
+    cost: f64,
+    grad: Array1<f64>,
+    hess: Array2<f64>,
+}
+
+/// Trust-region Newton method with the gradient and Hessian of a `DifferentiableCost`
+/// computed by `Dual<Dual<f64, N>, N>`.
+pub struct TrustRegion<const N: usize> {
+    subproblem: Subproblem,
+    max_radius: f64,
+    eta: f64,
+    current: Option<Model>,
+    state: TrustRegionState,
+}
+
+impl<const N: usize> TrustRegion<N> {
+    pub fn new(subproblem: Subproblem) -> Self {
+        Self {
+            subproblem,
+            max_radius: f64::INFINITY,
+            eta: 0.1,
+            current: None,
+            state: TrustRegionState {
+                cost: f64::NAN,
+                grad_norm: f64::NAN,
+                radius: 1.,
+                step_norm: f64::NAN,
+                predicted: f64::NAN,
+                actual: f64::NAN,
+                rho: f64::NAN,
+                accepted: false,
+                step: StepType::None,
+            },
+        }
+    }
+
+    /// Initial trust radius; 1 by default.
+    pub fn radius(mut self, radius: f64) -> Self {
+        self.state.radius = radius;
+        self
+    }
+
+    pub fn max_radius(mut self, radius: f64) -> Self {
+        self.max_radius = radius;
+        self
+    }
+
+    /// Minimum ratio of the actual to the predicted reduction for a step to be accepted.
+    pub fn eta(mut self, eta: f64) -> Self {
+        self.eta = eta;
+        self
+    }
+
+    fn evaluate<F>(&mut self, f: &F, x: &Array1<f64>) -> Result<(), Error>
+    where
+        F: DifferentiableCost,
+    {
+        let (cost, grad, hess) = hessian::<F, N>(f, x)?;
+        self.state.cost = cost;
+        self.state.grad_norm = grad.dot(&grad).sqrt();
+        self.current = Some(Model {
+            x: x.clone(),
+            cost,
+            grad,
+            hess,
+        });
+        Ok(())
+    }
+}
+
+/// Step length `tau >= 0` such that `|z + tau d| = radius`.
+fn to_boundary(z: &Array1<f64>, d: &Array1<f64>, radius: f64) -> f64 {
+    let a = d.dot(d);
+    let b = 2. * z.dot(d);
+    let c = z.dot(z) - radius * radius;
+    (-b + (b * b - 4. * a * c).max(0.).sqrt()) / (2. * a)
+}
+
+fn dogleg(g: &Array1<f64>, h: &Array2<f64>, radius: f64) -> (Array1<f64>, StepType) {
+    let g_norm = g.dot(g).sqrt();
+    let ghg = g.dot(&h.dot(g));
+    let newton = linalg::cholesky(h).map(|l| -linalg::cholesky_solve(&l, g));
+    match newton {
+        Some(pb) if pb.dot(&pb).sqrt() <= radius => (pb, StepType::Newton),
+        Some(pb) if ghg > 0. => {
+            let pu = g * (-g.dot(g) / ghg);
+            if pu.dot(&pu).sqrt() >= radius {
+                (g * (-radius / g_norm), StepType::Cauchy)
+            } else {
+                let d = &pb - &pu;
+                let tau = to_boundary(&pu, &d, radius);
+                (&pu + &(d * tau), StepType::Dogleg)
+            }
+        }
+        _ => {
+            let tau = if ghg <= 0. {
+                1.
+            } else {
+                (g_norm.powi(3) / (radius * ghg)).min(1.)
+            };
+            (g * (-tau * radius / g_norm), StepType::Cauchy)
+        }
+    }
+}
+
+fn steihaug(g: &Array1<f64>, h: &Array2<f64>, radius: f64) -> (Array1<f64>, StepType) {
+    let g_norm = g.dot(g).sqrt();
+    let tol = g_norm.sqrt().min(0.5) * g_norm;
+    let mut z = Array1::<f64>::zeros(g.len());
+    let mut r = g.clone();
+    let mut d = -g;
+    for _ in 0..(2 * g.len()).max(10) {
+        let hd = h.dot(&d);
+        let dhd = d.dot(&hd);
+        if dhd <= 0. {
+            let tau = to_boundary(&z, &d, radius);
+            return (&z + &(d * tau), StepType::NegativeCurvature);
+        }
+        let rr = r.dot(&r);
+        let alpha = rr / dhd;
+        let z_next = &z + &(&d * alpha);
+        if z_next.dot(&z_next).sqrt() >= radius {
+            let tau = to_boundary(&z, &d, radius);
+            return (&z + &(d * tau), StepType::Boundary);
+        }
+        r.scaled_add(alpha, &hd);
+        z = z_next;
+        let rr_next = r.dot(&r);
+        if rr_next.sqrt() < tol {
+            break;
+        }
+        d = &d * (rr_next / rr) - &r;
+    }
+    (z, StepType::Interior)
+}
+
+fn exact(g: &Array1<f64>, h: &Array2<f64>, radius: f64) -> (Array1<f64>, StepType) {
+    let (w, q) = linalg::symmetric_eigen(h);
+    let gt = q.t().dot(g);
+    let g_norm = g.dot(g).sqrt();
+    let lambda1 = w.iter().cloned().fold(f64::INFINITY, f64::min);
+    let step = |lambda: f64| -> Array1<f64> {
+        let coef = Zip::from(&gt).and(&w).map_collect(|&gi, &wi| {
+            if wi + lambda > 0. {
+                -gi / (wi + lambda)
+            } else {
+                0.
+            }
+        });
+        q.dot(&coef)
+    };
+
+    if lambda1 > 0. {
+        let p = step(0.);
+        if p.dot(&p).sqrt() <= radius {
+            return (p, StepType::Interior);
+        }
+    }
+
+    let lower = (-lambda1).max(0.);
+    let scale = w.iter().fold(0f64, |acc, v| acc.max(v.abs())).max(1.);
+    let in_min_space = |i: usize| w[i] - lambda1 <= 1e-10 * scale;
+    let orthogonal = (0..w.len())
+        .filter(|&i| in_min_space(i))
+        .all(|i| gt[i].abs() <= 1e-10 * g_norm.max(f64::MIN_POSITIVE));
+    if orthogonal && lambda1 <= 0. {
+        let p = step(lower);
+        let p_norm = p.dot(&p).sqrt();
+        if p_norm <= radius {
+            let i = (0..w.len()).find(|&i| in_min_space(i)).unwrap();
+            let z = q.column(i).to_owned();
+            let tau = to_boundary(&p, &z, radius);
+            return (p + z * tau, StepType::HardCase);
+        }
+    }
+
+    // Newton iteration on 1/radius - 1/|p(lambda)| = 0
+    let mut lambda = lower + 1e-12 * scale;
+    for _ in 0..100 {
+        let coef = Zip::from(&gt)
+            .and(&w)
+            .map_collect(|&gi, &wi| gi / (wi + lambda));
+        let p_norm = coef.dot(&coef).sqrt();
+        if (p_norm - radius).abs() <= 1e-10 * radius {
+            break;
+        }
+        let q_norm2: f64 = Zip::from(&coef)
+            .and(&w)
+            .fold(0., |acc, &c, &wi| acc + c * c / (wi + lambda));
+        let next = lambda + (p_norm * p_norm / q_norm2) * (p_norm - radius) / radius;
+        lambda = if next > lower {
+            next
+        } else {
+            (lambda + lower) / 2.
+        };
+    }
+    (step(lambda), StepType::Boundary)
+}
+
+impl<F, const N: usize> Solver<Minimize<F>> for TrustRegion<N>
+where
+    F: DifferentiableCost,
+{
+    type ReportArg = TrustRegionState;
+
+    fn next_iter(&mut self, op: &Minimize<F>, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+        if self.current.as_ref().is_none_or(|m| m.x != x) {
+            self.evaluate(&op.0, x)?;
+        }
+        let Model {
+            cost: fx,
+            grad: g,
+            hess: h,
+            ..
+        } = self.current.as_ref().unwrap();
+        let fx = *fx;
+        let radius = self.state.radius;
+        if self.state.grad_norm == 0. {
+            self.state.step = StepType::None;
+            self.state.step_norm = 0.;
+            self.state.accepted = false;
+            return Ok(x.clone());
+        }
+
+        let (p, step) = match self.subproblem {
+            Subproblem::Dogleg => dogleg(g, h, radius),
+            Subproblem::Steihaug => steihaug(g, h, radius),
+            Subproblem::Exact => exact(g, h, radius),
+        };
+        let predicted = -(g.dot(&p) + 0.5 * p.dot(&h.dot(&p)));
+        let trial = x + &p;
+        let actual = match op.0.cost::<f64>(&trial.to_vec()) {
+            Ok(v) => fx - v,
+            Err(Error::InvalidVariable) => f64::NEG_INFINITY,
+            Err(e) => return Err(e),
+        };
+        let rho = actual / predicted;
+        let step_norm = p.dot(&p).sqrt();
+
+        if rho.is_nan() || rho < 0.25 || predicted <= 0. {
+            self.state.radius = 0.25 * step_norm.min(radius);
+        } else if rho > 0.75 && step_norm >= 0.99 * radius {
+            self.state.radius = (2. * radius).min(self.max_radius);
+        }
+        let accepted = predicted > 0. && rho > self.eta;
+        self.state.step = step;
+        self.state.step_norm = step_norm;
+        self.state.predicted = predicted;
+        self.state.actual = actual;
+        self.state.rho = rho;
+        self.state.accepted = accepted;
+        if accepted {
+            self.evaluate(&op.0, &trial)?;
+            Ok(trial)
+        } else {
+            Ok(x.clone())
+        }
+    }
+
+    fn init_report<R: Report<Arg = TrustRegionState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.init(&self.state)
+    }
+
+    fn update_report<R: Report<Arg = TrustRegionState>>(
+        &self,
+        report: &mut R,
+        _x: &Array1<f64>,
+    ) -> Result<(), Error> {
+        report.update(&self.state)
+    }
+}
+
+#[derive(Serialize)]
+pub struct TrustRegionReport {
+    pub count: usize,
+    pub cost: f64,
+    pub grad_norm: f64,
+    pub radius: f64,
+    pub step_norm: f64,
+    pub predicted: f64,
+    pub actual: f64,
+    pub rho: f64,
+    pub accepted: bool,
+    pub step: StepType,
+}
+
+impl Report for TrustRegionReport {
+    type Arg = TrustRegionState;
+
+    fn init(&mut self, s: &TrustRegionState) -> Result<(), Error> {
+        *self = Self::default();
+        self.radius = s.radius;
+        Ok(())
+    }
+
+    fn update(&mut self, s: &TrustRegionState) -> Result<(), Error> {
+        self.count += 1;
+        self.cost = s.cost;
+        self.grad_norm = s.grad_norm;
+        self.radius = s.radius;
+        self.step_norm = s.step_norm;
+        self.predicted = s.predicted;
+        self.actual = s.actual;
+        self.rho = s.rho;
+        self.accepted = s.accepted;
+        self.step = s.step;
+        Ok(())
+    }
+}
+
+impl Default for TrustRegionReport {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            cost: f64::NAN,
+            grad_norm: f64::NAN,
+            radius: f64::NAN,
+            step_norm: f64::NAN,
+            predicted: f64::NAN,
+            actual: f64::NAN,
+            rho: f64::NAN,
+            accepted: false,
+            step: StepType::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::*;
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use num_traits::Float;
+
+    struct Rosenbrock {
+        a: f64,
+        b: f64,
+    }
+
+    impl DifferentiableCost for Rosenbrock {
+        fn cost<T: Float>(&self, x: &[T]) -> Result<T, Error> {
+            let a = T::from(self.a).unwrap();
+            let b = T::from(self.b).unwrap();
+            Ok((a - x[0]).powi(2) + b * (x[1] - x[0] * x[0]).powi(2))
+        }
+    }
+
+    #[test]
+    fn rosenbrock() -> anyhow::Result<()> {
+        for &subproblem in &[Subproblem::Dogleg, Subproblem::Steihaug, Subproblem::Exact] {
+            // the Hessian is indefinite at the second starting point
+            for x0 in &[array![-1.2, 1.], array![0., 1.]] {
+                let op = Minimize(Rosenbrock { a: 1., b: 100. });
+                let x = Executor::new(TrustRegion::<2>::new(subproblem), op)
+                    .report(TrustRegionReport::default())
+                    .terminate(when(|r: &TrustRegionReport| {
+                        r.grad_norm < 1e-10 || r.count >= 200
+                    }))
+                    .run(x0.clone())?;
+                assert_abs_diff_eq!(x[0], 1., epsilon = 1e-8);
+                assert_abs_diff_eq!(x[1], 1., epsilon = 1e-8);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn hard_case() {
+        // g is orthogonal to the eigenvector of the negative eigenvalue
+        let h = array![[-2., 0.], [0., 1.]];
+        let g = array![0., 1.];
+        let (p, step) = exact(&g, &h, 2.);
+        assert_eq!(step, StepType::HardCase);
+        assert_abs_diff_eq!(p.dot(&p).sqrt(), 2., epsilon = 1e-10);
+        assert_abs_diff_eq!(p[1], -1. / 3., epsilon = 1e-10);
+    }
+
+    #[test]
+    fn dimension_mismatch() {
+        let op = Minimize(Rosenbrock { a: 1., b: 100. });
+        let res = Executor::new(TrustRegion::<3>::new(Subproblem::Dogleg), op)
+            .report(TrustRegionReport::default())
+            .terminate(when(|r: &TrustRegionReport| r.count >= 1))
+            .run(array![0., 0.]);
+        assert!(res.is_err());
+    }
+}
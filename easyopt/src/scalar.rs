@@ -0,0 +1,236 @@
+pub use crate::criteria::*;
+pub use crate::error::*;
+pub use crate::executor::*;
+pub use crate::monitor;
+pub use crate::traits::*;
+
+use serde::Serialize;
+
+/// Problem of finding `x` such that `f(x) = 0`.
+///
+/// Bracketing solvers take `f` as `Fn(&f64) -> f64`, while `root::Newton` evaluates it on
+/// `Dual<f64, 1>` to obtain the derivative.
+pub struct Root<F>(pub F);
+
+impl<F> Op for Root<F> {
+    type Variable = f64;
+}
+
+/// Problem of finding a local minimum of `f: Fn(&f64) -> f64`.
+pub struct Minimum<F>(pub F);
+
+impl<F> Op for Minimum<F> {
+    type Variable = f64;
+}
+
+/// Interval `[lower, upper]` known to contain the solution, with the current estimate `x`.
+///
+/// Solvers collapse the interval to `x` once no representable point is left inside it,
+/// so a criterion on `width()` terminates for any non-negative tolerance.
+#[derive(Debug, Clone, Copy)]
+pub struct Bracket {
+    pub x: f64,
+    pub fx: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl Bracket {
+    pub fn new(a: f64, b: f64) -> Self {
+        Self {
+            x: f64::NAN,
+            fx: f64::NAN,
+            lower: a.min(b),
+            upper: a.max(b),
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.upper - self.lower
+    }
+
+    fn set(&mut self, a: f64, b: f64) {
+        self.lower = a.min(b);
+        self.upper = a.max(b);
+    }
+
+    fn collapse(&mut self, x: f64, fx: f64) {
+        self.x = x;
+        self.fx = fx;
+        self.lower = x;
+        self.upper = x;
+    }
+}
+
+#[derive(Serialize)]
+pub struct BracketReport {
+    pub count: usize,
+    pub x: f64,
+    pub fx: f64,
+    pub lower: f64,
+    pub upper: f64,
+    pub width: f64,
+}
+
+impl Report for BracketReport {
+    type Arg = Bracket;
+
+    fn init(&mut self, b: &Bracket) -> Result<(), Error> {
+        self.count = 0;
+        self.x = b.x;
+        self.fx = b.fx;
+        self.lower = b.lower;
+        self.upper = b.upper;
+        self.width = b.width();
+        Ok(())
+    }
+
+    fn update(&mut self, b: &Bracket) -> Result<(), Error> {
+        self.count += 1;
+        self.x = b.x;
+        self.fx = b.fx;
+        self.lower = b.lower;
+        self.upper = b.upper;
+        self.width = b.width();
+        Ok(())
+    }
+}
+
+impl Default for BracketReport {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            x: f64::NAN,
+            fx: f64::NAN,
+            lower: f64::NAN,
+            upper: f64::NAN,
+            width: f64::NAN,
+        }
+    }
+}
+
+/// Returns `true` when no representable number lies strictly between `a` and `b`.
+fn exhausted(a: f64, b: f64) -> bool {
+    let m = a + (b - a) / 2.;
+    m == a || m == b
+}
+
+pub mod minimize;
+pub mod root;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use dual::Dual;
+
+    fn cubic(x: &f64) -> f64 {
+        x * x * x - 2. * x - 5.
+    }
+    const CUBIC_ROOT: f64 = 2.0945514815423265;
+
+    fn solve<S>(solver: S) -> anyhow::Result<(f64, usize)>
+    where
+        S: Solver<Root<fn(&f64) -> f64>, ReportArg = Bracket>,
+    {
+        let mut count = 0;
+        let x = Executor::new(solver, Root(cubic as fn(&f64) -> f64))
+            .report(BracketReport::default())
+            .add_monitor(|r: &BracketReport| {
+                count = r.count;
+                Ok(())
+            })
+            .terminate(when(|r: &BracketReport| r.width <= 0.))
+            .run(2.)?;
+        Ok((x, count))
+    }
+
+    #[test]
+    fn bracketing_roots() -> anyhow::Result<()> {
+        let (x, n) = solve(root::Bisection::new(2., 3.))?;
+        assert_abs_diff_eq!(x, CUBIC_ROOT, epsilon = 1e-15);
+        assert!(n < 70);
+        let (x, n) = solve(root::RegulaFalsi::new(2., 3.))?;
+        assert_abs_diff_eq!(x, CUBIC_ROOT, epsilon = 1e-15);
+        assert!(n < 70);
+        let (x, n) = solve(root::Brent::new(2., 3.))?;
+        assert_abs_diff_eq!(x, CUBIC_ROOT, epsilon = 1e-15);
+        assert!(n < 20);
+        Ok(())
+    }
+
+    #[test]
+    fn bracket_without_sign_change() {
+        assert!(solve(root::Bisection::new(3., 4.)).is_err());
+    }
+
+    #[test]
+    fn root_on_bracket_end() -> anyhow::Result<()> {
+        fn run<S>(solver: S) -> anyhow::Result<f64>
+        where
+            S: Solver<Root<fn(&f64) -> f64>, ReportArg = Bracket>,
+        {
+            Executor::new(solver, Root((|x: &f64| *x) as fn(&f64) -> f64))
+                .report(BracketReport::default())
+                .terminate(when(|r: &BracketReport| r.width <= 0. || r.count > 200))
+                .run(0.5)
+        }
+        assert_eq!(0., run(root::Bisection::new(0., 1.))?);
+        assert_eq!(0., run(root::Bisection::new(-1., 0.))?);
+        assert_eq!(0., run(root::RegulaFalsi::new(0., 1.))?);
+        assert_eq!(0., run(root::Brent::new(1., 0.))?);
+
+        let f = |x: &Dual<f64, 1>| x * x - 1.;
+        let x = Executor::new(root::Newton::new().bracket(1., 3.), Root(f))
+            .report(BracketReport::default())
+            .terminate(when(|r: &BracketReport| r.width <= 0. || r.count > 200))
+            .run(2.)?;
+        assert_eq!(1., x);
+        Ok(())
+    }
+
+    #[test]
+    fn newton() -> anyhow::Result<()> {
+        let f = |x: &Dual<f64, 1>| x * x * x - x * 2. - 5.;
+        let x = Executor::new(root::Newton::new(), Root(f))
+            .report(BracketReport::default())
+            .terminate(when(|r: &BracketReport| r.width < 1e-14 || r.count > 50))
+            .run(2.)?;
+        assert_abs_diff_eq!(x, CUBIC_ROOT, epsilon = 1e-15);
+
+        // x^3 - 2x + 2 sends plain Newton into the 0 <-> 1 cycle
+        let f = |x: &Dual<f64, 1>| x * x * x - x * 2. + 2.;
+        let x = Executor::new(root::Newton::new().bracket(-3., 1.), Root(f))
+            .report(BracketReport::default())
+            .terminate(when(|r: &BracketReport| r.width <= 0. || r.count > 200))
+            .run(0.)?;
+        assert_abs_diff_eq!(x, -1.7692923542386314, epsilon = 1e-14);
+        Ok(())
+    }
+
+    #[test]
+    fn minimization() -> anyhow::Result<()> {
+        let f = |x: &f64| (x - 1.5).powi(2) * (x + 0.5) + 0.1 * x;
+        // f'(x) = 3x^2 - 5x + 0.85
+        let expected = (5. + f64::sqrt(25. - 12. * 0.85)) / 6.;
+
+        let x = Executor::new(minimize::GoldenSection::new(0., 3.), Minimum(f))
+            .report(BracketReport::default())
+            .terminate(when(|r: &BracketReport| r.width <= 0.))
+            .run(1.)?;
+        assert_abs_diff_eq!(x, expected, epsilon = 1e-7);
+
+        let mut count = 0;
+        let x = Executor::new(minimize::Brent::new(0., 3.), Minimum(f))
+            .report(BracketReport::default())
+            .add_monitor(|r: &BracketReport| {
+                count = r.count;
+                Ok(())
+            })
+            .terminate(when(|r: &BracketReport| r.width <= 0.))
+            .run(1.)?;
+        assert_abs_diff_eq!(x, expected, epsilon = 1e-7);
+        assert!(count < 30);
+        Ok(())
+    }
+}
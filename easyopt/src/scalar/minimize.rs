@@ -0,0 +1,246 @@
+use super::{exhausted, Bracket, Minimum};
+use crate::error::*;
+use crate::traits::*;
+
+const INV_PHI: f64 = 0.618_033_988_749_895;
+const CGOLD: f64 = 1. - INV_PHI;
+
+/// Golden-section search on the bracket `[a, b]`.
+pub struct GoldenSection {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    fc: f64,
+    fd: f64,
+    bracket: Bracket,
+}
+
+impl GoldenSection {
+    pub fn new(a: f64, b: f64) -> Self {
+        let (a, b) = (a.min(b), a.max(b));
+        Self {
+            a,
+            b,
+            c: b - INV_PHI * (b - a),
+            d: a + INV_PHI * (b - a),
+            fc: f64::NAN,
+            fd: f64::NAN,
+            bracket: Bracket::new(a, b),
+        }
+    }
+}
+
+impl<F> Solver<Minimum<F>> for GoldenSection
+where
+    F: Fn(&f64) -> f64,
+{
+    type ReportArg = Bracket;
+
+    fn next_iter(&mut self, op: &Minimum<F>, _x: &f64) -> Result<f64, Error> {
+        if self.bracket.width() == 0. {
+            return Ok(self.bracket.x);
+        }
+        if self.fc.is_nan() {
+            self.fc = (op.0)(&self.c);
+            self.fd = (op.0)(&self.d);
+        }
+        if self.fc < self.fd {
+            self.b = self.d;
+            self.d = self.c;
+            self.fd = self.fc;
+            self.c = self.b - INV_PHI * (self.b - self.a);
+            self.fc = (op.0)(&self.c);
+        } else {
+            self.a = self.c;
+            self.c = self.d;
+            self.fc = self.fd;
+            self.d = self.a + INV_PHI * (self.b - self.a);
+            self.fd = (op.0)(&self.d);
+        }
+        let (x, fx) = if self.fc < self.fd {
+            (self.c, self.fc)
+        } else {
+            (self.d, self.fd)
+        };
+        if exhausted(self.a, self.b) || self.c <= self.a || self.d >= self.b || self.c >= self.d {
+            self.bracket.collapse(x, fx);
+        } else {
+            self.bracket.set(self.a, self.b);
+            self.bracket.x = x;
+            self.bracket.fx = fx;
+        }
+        Ok(x)
+    }
+
+    fn init_report<R: Report<Arg = Bracket>>(&self, report: &mut R, _x: &f64) -> Result<(), Error> {
+        report.init(&self.bracket)
+    }
+
+    fn update_report<R: Report<Arg = Bracket>>(
+        &self,
+        report: &mut R,
+        _x: &f64,
+    ) -> Result<(), Error> {
+        report.update(&self.bracket)
+    }
+}
+
+/// Brent's minimization on the bracket `[a, b]`, mixing parabolic interpolation with
+/// golden-section steps.
+///
+/// The initial variable passed to `Executor::run` is used as the first point when it lies
+/// inside the bracket.
+pub struct Brent {
+    a: f64,
+    b: f64,
+    v: f64,
+    w: f64,
+    x: f64,
+    fv: f64,
+    fw: f64,
+    fx: f64,
+    d: f64,
+    e: f64,
+    tol: f64,
+    bracket: Bracket,
+}
+
+impl Brent {
+    pub fn new(a: f64, b: f64) -> Self {
+        let (a, b) = (a.min(b), a.max(b));
+        Self {
+            a,
+            b,
+            v: f64::NAN,
+            w: f64::NAN,
+            x: f64::NAN,
+            fv: f64::NAN,
+            fw: f64::NAN,
+            fx: f64::NAN,
+            d: 0.,
+            e: 0.,
+            tol: f64::EPSILON.sqrt(),
+            bracket: Bracket::new(a, b),
+        }
+    }
+
+    /// Relative tolerance on the minimizer; the square root of the machine epsilon by default.
+    pub fn tolerance(mut self, tol: f64) -> Self {
+        self.tol = tol;
+        self
+    }
+}
+
+impl<F> Solver<Minimum<F>> for Brent
+where
+    F: Fn(&f64) -> f64,
+{
+    type ReportArg = Bracket;
+
+    fn next_iter(&mut self, op: &Minimum<F>, x: &f64) -> Result<f64, Error> {
+        if self.bracket.width() == 0. {
+            return Ok(self.bracket.x);
+        }
+        if self.fx.is_nan() {
+            self.x = if *x > self.a && *x < self.b {
+                *x
+            } else {
+                self.a + CGOLD * (self.b - self.a)
+            };
+            self.fx = (op.0)(&self.x);
+            self.v = self.x;
+            self.w = self.x;
+            self.fv = self.fx;
+            self.fw = self.fx;
+        }
+
+        let xm = 0.5 * (self.a + self.b);
+        let tol1 = self.tol * self.x.abs() + 1e-3 * f64::EPSILON;
+        let tol2 = 2. * tol1;
+        if (self.x - xm).abs() <= tol2 - 0.5 * (self.b - self.a) || exhausted(self.a, self.b) {
+            self.bracket.collapse(self.x, self.fx);
+            return Ok(self.x);
+        }
+
+        let golden = |x: f64, a: f64, b: f64| if x >= xm { a - x } else { b - x };
+        if self.e.abs() > tol1 {
+            let r = (self.x - self.w) * (self.fx - self.fv);
+            let q = (self.x - self.v) * (self.fx - self.fw);
+            let mut p = (self.x - self.v) * q - (self.x - self.w) * r;
+            let mut q = 2. * (q - r);
+            if q > 0. {
+                p = -p;
+            }
+            q = q.abs();
+            let etemp = self.e;
+            self.e = self.d;
+            if p.abs() >= (0.5 * q * etemp).abs()
+                || p <= q * (self.a - self.x)
+                || p >= q * (self.b - self.x)
+            {
+                self.e = golden(self.x, self.a, self.b);
+                self.d = CGOLD * self.e;
+            } else {
+                self.d = p / q;
+                let u = self.x + self.d;
+                if u - self.a < tol2 || self.b - u < tol2 {
+                    self.d = tol1.copysign(xm - self.x);
+                }
+            }
+        } else {
+            self.e = golden(self.x, self.a, self.b);
+            self.d = CGOLD * self.e;
+        }
+        let u = if self.d.abs() >= tol1 {
+            self.x + self.d
+        } else {
+            self.x + tol1.copysign(self.d)
+        };
+        let fu = (op.0)(&u);
+        if fu <= self.fx {
+            if u >= self.x {
+                self.a = self.x;
+            } else {
+                self.b = self.x;
+            }
+            self.v = self.w;
+            self.w = self.x;
+            self.x = u;
+            self.fv = self.fw;
+            self.fw = self.fx;
+            self.fx = fu;
+        } else {
+            if u < self.x {
+                self.a = u;
+            } else {
+                self.b = u;
+            }
+            if fu <= self.fw || self.w == self.x {
+                self.v = self.w;
+                self.w = u;
+                self.fv = self.fw;
+                self.fw = fu;
+            } else if fu <= self.fv || self.v == self.x || self.v == self.w {
+                self.v = u;
+                self.fv = fu;
+            }
+        }
+        self.bracket.set(self.a, self.b);
+        self.bracket.x = self.x;
+        self.bracket.fx = self.fx;
+        Ok(self.x)
+    }
+
+    fn init_report<R: Report<Arg = Bracket>>(&self, report: &mut R, _x: &f64) -> Result<(), Error> {
+        report.init(&self.bracket)
+    }
+
+    fn update_report<R: Report<Arg = Bracket>>(
+        &self,
+        report: &mut R,
+        _x: &f64,
+    ) -> Result<(), Error> {
+        report.update(&self.bracket)
+    }
+}
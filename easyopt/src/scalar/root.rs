@@ -0,0 +1,471 @@
+use super::{exhausted, Bracket, Root};
+use crate::error::*;
+use crate::traits::*;
+use dual::{Dual, Variables};
+
+/// Whether `fa` and `fb` are both positive or both negative. A product would underflow to
+/// zero for tiny values, and `signum` gives `1.0` for a zero.
+fn same_sign(fa: f64, fb: f64) -> bool {
+    (fa > 0. && fb > 0.) || (fa < 0. && fb < 0.)
+}
+
+/// Checks that the function changes sign over the bracket `[a, b]`, and returns the end point
+/// where it vanishes, if any, so that the solver can stop there.
+fn check_bracket(a: f64, fa: f64, b: f64, fb: f64) -> Result<Option<f64>, Error> {
+    if fa == 0. {
+        Ok(Some(a))
+    } else if fb == 0. {
+        Ok(Some(b))
+    } else if same_sign(fa, fb) {
+        Err(Error::Failure(String::from(
+            "the function must have opposite signs at the ends of the bracket",
+        )))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Bisection method on the bracket `[a, b]`.
+pub struct Bisection {
+    a: f64,
+    b: f64,
+    fa: f64,
+    bracket: Bracket,
+}
+
+impl Bisection {
+    pub fn new(a: f64, b: f64) -> Self {
+        Self {
+            a,
+            b,
+            fa: f64::NAN,
+            bracket: Bracket::new(a, b),
+        }
+    }
+}
+
+impl<F> Solver<Root<F>> for Bisection
+where
+    F: Fn(&f64) -> f64,
+{
+    type ReportArg = Bracket;
+
+    fn next_iter(&mut self, op: &Root<F>, _x: &f64) -> Result<f64, Error> {
+        if self.bracket.width() == 0. {
+            return Ok(self.bracket.x);
+        }
+        if self.fa.is_nan() {
+            self.fa = (op.0)(&self.a);
+            let fb = (op.0)(&self.b);
+            if let Some(x) = check_bracket(self.a, self.fa, self.b, fb)? {
+                self.bracket.collapse(x, 0.);
+                return Ok(x);
+            }
+        }
+        let m = self.a + (self.b - self.a) / 2.;
+        if m == self.a || m == self.b {
+            let fm = (op.0)(&m);
+            self.bracket.collapse(m, fm);
+            return Ok(m);
+        }
+        let fm = (op.0)(&m);
+        if fm == 0. {
+            self.bracket.collapse(m, fm);
+        } else {
+            if same_sign(fm, self.fa) {
+                self.a = m;
+                self.fa = fm;
+            } else {
+                self.b = m;
+            }
+            self.bracket.set(self.a, self.b);
+            self.bracket.x = m;
+            self.bracket.fx = fm;
+        }
+        Ok(m)
+    }
+
+    fn init_report<R: Report<Arg = Bracket>>(&self, report: &mut R, _x: &f64) -> Result<(), Error> {
+        report.init(&self.bracket)
+    }
+
+    fn update_report<R: Report<Arg = Bracket>>(
+        &self,
+        report: &mut R,
+        _x: &f64,
+    ) -> Result<(), Error> {
+        report.update(&self.bracket)
+    }
+}
+
+/// Regula falsi with the Illinois modification on the bracket `[a, b]`.
+///
+/// The function value kept at a retained end point is halved, so that both ends of the
+/// bracket keep moving and the method converges superlinearly.
+pub struct RegulaFalsi {
+    a: f64,
+    b: f64,
+    fa: f64,
+    fb: f64,
+    bracket: Bracket,
+}
+
+impl RegulaFalsi {
+    pub fn new(a: f64, b: f64) -> Self {
+        Self {
+            a,
+            b,
+            fa: f64::NAN,
+            fb: f64::NAN,
+            bracket: Bracket::new(a, b),
+        }
+    }
+}
+
+impl<F> Solver<Root<F>> for RegulaFalsi
+where
+    F: Fn(&f64) -> f64,
+{
+    type ReportArg = Bracket;
+
+    fn next_iter(&mut self, op: &Root<F>, _x: &f64) -> Result<f64, Error> {
+        if self.bracket.width() == 0. {
+            return Ok(self.bracket.x);
+        }
+        if self.fa.is_nan() {
+            self.fa = (op.0)(&self.a);
+            self.fb = (op.0)(&self.b);
+            if let Some(x) = check_bracket(self.a, self.fa, self.b, self.fb)? {
+                self.bracket.collapse(x, 0.);
+                return Ok(x);
+            }
+        }
+        if exhausted(self.a, self.b) {
+            let (x, fx) = if self.fa.abs() < self.fb.abs() {
+                (self.a, self.fa)
+            } else {
+                (self.b, self.fb)
+            };
+            self.bracket.collapse(x, fx);
+            return Ok(x);
+        }
+        let mut c = self.b - self.fb * (self.b - self.a) / (self.fb - self.fa);
+        let (lower, upper) = (self.a.min(self.b), self.a.max(self.b));
+        if !(c > lower && c < upper) {
+            c = self.a + (self.b - self.a) / 2.;
+        }
+        let fc = (op.0)(&c);
+        if fc == 0. {
+            self.bracket.collapse(c, fc);
+            return Ok(c);
+        }
+        if same_sign(fc, self.fb) {
+            self.fa /= 2.;
+        } else {
+            self.a = self.b;
+            self.fa = self.fb;
+        }
+        self.b = c;
+        self.fb = fc;
+        self.bracket.set(self.a, self.b);
+        self.bracket.x = c;
+        self.bracket.fx = fc;
+        Ok(c)
+    }
+
+    fn init_report<R: Report<Arg = Bracket>>(&self, report: &mut R, _x: &f64) -> Result<(), Error> {
+        report.init(&self.bracket)
+    }
+
+    fn update_report<R: Report<Arg = Bracket>>(
+        &self,
+        report: &mut R,
+        _x: &f64,
+    ) -> Result<(), Error> {
+        report.update(&self.bracket)
+    }
+}
+
+/// Brent's method (zeroin) on the bracket `[a, b]`.
+///
+/// Combines inverse quadratic interpolation and the secant method with bisection, so the
+/// bracket shrinks at least as fast as bisection in the long run.
+pub struct Brent {
+    a: f64,
+    b: f64,
+    c: f64,
+    fa: f64,
+    fb: f64,
+    fc: f64,
+    d: f64,
+    e: f64,
+    tol: f64,
+    bracket: Bracket,
+}
+
+impl Brent {
+    pub fn new(a: f64, b: f64) -> Self {
+        Self {
+            a,
+            b,
+            c: b,
+            fa: f64::NAN,
+            fb: f64::NAN,
+            fc: f64::NAN,
+            d: b - a,
+            e: b - a,
+            tol: 0.,
+            bracket: Bracket::new(a, b),
+        }
+    }
+
+    /// Absolute tolerance on the root, on top of the relative machine precision.
+    pub fn tolerance(mut self, tol: f64) -> Self {
+        self.tol = tol;
+        self
+    }
+}
+
+impl<F> Solver<Root<F>> for Brent
+where
+    F: Fn(&f64) -> f64,
+{
+    type ReportArg = Bracket;
+
+    fn next_iter(&mut self, op: &Root<F>, _x: &f64) -> Result<f64, Error> {
+        if self.bracket.width() == 0. {
+            return Ok(self.bracket.x);
+        }
+        if self.fa.is_nan() {
+            self.fa = (op.0)(&self.a);
+            self.fb = (op.0)(&self.b);
+            if let Some(x) = check_bracket(self.a, self.fa, self.b, self.fb)? {
+                self.bracket.collapse(x, 0.);
+                return Ok(x);
+            }
+            self.fc = self.fb;
+        }
+        if same_sign(self.fb, self.fc) {
+            self.c = self.a;
+            self.fc = self.fa;
+            self.d = self.b - self.a;
+            self.e = self.d;
+        }
+        if self.fc.abs() < self.fb.abs() {
+            self.a = self.b;
+            self.b = self.c;
+            self.c = self.a;
+            self.fa = self.fb;
+            self.fb = self.fc;
+            self.fc = self.fa;
+        }
+        let tol1 = 2. * f64::EPSILON * self.b.abs() + 0.5 * self.tol + f64::MIN_POSITIVE;
+        let xm = 0.5 * (self.c - self.b);
+        if xm.abs() <= tol1 || self.fb == 0. {
+            self.bracket.collapse(self.b, self.fb);
+            return Ok(self.b);
+        }
+        if self.e.abs() >= tol1 && self.fa.abs() > self.fb.abs() {
+            let s = self.fb / self.fa;
+            let (mut p, mut q) = if self.a == self.c {
+                (2. * xm * s, 1. - s)
+            } else {
+                let q = self.fa / self.fc;
+                let r = self.fb / self.fc;
+                (
+                    s * (2. * xm * q * (q - r) - (self.b - self.a) * (r - 1.)),
+                    (q - 1.) * (r - 1.) * (s - 1.),
+                )
+            };
+            if p > 0. {
+                q = -q;
+            }
+            p = p.abs();
+            let min1 = 3. * xm * q - (tol1 * q).abs();
+            let min2 = (self.e * q).abs();
+            if 2. * p < min1.min(min2) {
+                self.e = self.d;
+                self.d = p / q;
+            } else {
+                self.d = xm;
+                self.e = self.d;
+            }
+        } else {
+            self.d = xm;
+            self.e = self.d;
+        }
+        self.a = self.b;
+        self.fa = self.fb;
+        if self.d.abs() > tol1 {
+            self.b += self.d;
+        } else {
+            self.b += tol1.copysign(xm);
+        }
+        self.fb = (op.0)(&self.b);
+
+        let other = if same_sign(self.fb, self.fc) {
+            self.a
+        } else {
+            self.c
+        };
+        self.bracket.set(self.b, other);
+        self.bracket.x = self.b;
+        self.bracket.fx = self.fb;
+        Ok(self.b)
+    }
+
+    fn init_report<R: Report<Arg = Bracket>>(&self, report: &mut R, _x: &f64) -> Result<(), Error> {
+        report.init(&self.bracket)
+    }
+
+    fn update_report<R: Report<Arg = Bracket>>(
+        &self,
+        report: &mut R,
+        _x: &f64,
+    ) -> Result<(), Error> {
+        report.update(&self.bracket)
+    }
+}
+
+/// Newton's method with the derivative obtained from `Dual<f64, 1>`.
+///
+/// Without a bracket the reported width is the length of the last step. With a bracket the
+/// iteration falls back to bisection whenever the Newton step would leave it.
+pub struct Newton {
+    bounds: Option<(f64, f64, f64)>,
+    last: Option<(f64, f64, f64)>,
+    bracket: Bracket,
+}
+
+impl Default for Newton {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Newton {
+    pub fn new() -> Self {
+        Self {
+            bounds: None,
+            last: None,
+            bracket: Bracket {
+                x: f64::NAN,
+                fx: f64::NAN,
+                lower: f64::NEG_INFINITY,
+                upper: f64::INFINITY,
+            },
+        }
+    }
+
+    /// Safeguards the iteration with the bracket `[a, b]`.
+    pub fn bracket(mut self, a: f64, b: f64) -> Self {
+        self.bounds = Some((a.min(b), a.max(b), f64::NAN));
+        self.bracket = Bracket::new(a, b);
+        self
+    }
+
+    fn eval<F>(&mut self, f: &F, x: f64) -> (f64, f64)
+    where
+        F: Fn(&Dual<f64, 1>) -> Dual<f64, 1>,
+    {
+        match self.last {
+            Some((last, fx, dfx)) if last == x => (fx, dfx),
+            _ => {
+                let var = Variables::<f64, 1>::new().gen(x).unwrap();
+                let y = f(&var);
+                let (fx, dfx) = (*y.val(), y.grad()[0]);
+                self.last = Some((x, fx, dfx));
+                (fx, dfx)
+            }
+        }
+    }
+
+    /// Narrows the safeguarding bracket with a point where `f` has been evaluated.
+    fn narrow(&mut self, x: f64, fx: f64) {
+        if let Some((a, b, fa)) = self.bounds.as_mut() {
+            if x >= *a && x <= *b {
+                if same_sign(fx, *fa) {
+                    *a = x;
+                    *fa = fx;
+                } else {
+                    *b = x;
+                }
+            }
+            self.bracket.set(*a, *b);
+        }
+    }
+}
+
+impl<F> Solver<Root<F>> for Newton
+where
+    F: Fn(&Dual<f64, 1>) -> Dual<f64, 1>,
+{
+    type ReportArg = Bracket;
+
+    fn next_iter(&mut self, op: &Root<F>, x: &f64) -> Result<f64, Error> {
+        if self.bracket.width() == 0. {
+            return Ok(self.bracket.x);
+        }
+        let x = *x;
+        let (fx, dfx) = self.eval(&op.0, x);
+        if fx == 0. {
+            self.bracket.collapse(x, fx);
+            return Ok(x);
+        }
+        let step = -fx / dfx;
+        let next = match self.bounds {
+            None => {
+                if !step.is_finite() {
+                    return Err(Error::Failure(String::from(
+                        "the derivative vanishes at the current point",
+                    )));
+                }
+                self.bracket.set(x, x + step);
+                x + step
+            }
+            Some((a, b, fa)) => {
+                if fa.is_nan() {
+                    let fa = self.eval(&op.0, a).0;
+                    let fb = self.eval(&op.0, b).0;
+                    if let Some(x) = check_bracket(a, fa, b, fb)? {
+                        self.bracket.collapse(x, 0.);
+                        return Ok(x);
+                    }
+                    self.bounds = Some((a, b, fa));
+                }
+                self.narrow(x, fx);
+                let (a, b, _) = self.bounds.unwrap();
+                if exhausted(a, b) {
+                    self.bracket.collapse(x, fx);
+                    return Ok(x);
+                }
+                if x + step > a && x + step < b {
+                    x + step
+                } else {
+                    a + (b - a) / 2.
+                }
+            }
+        };
+        let (fnext, _) = self.eval(&op.0, next);
+        self.narrow(next, fnext);
+        if fnext == 0. {
+            self.bracket.collapse(next, fnext);
+        } else {
+            self.bracket.x = next;
+            self.bracket.fx = fnext;
+        }
+        Ok(next)
+    }
+
+    fn init_report<R: Report<Arg = Bracket>>(&self, report: &mut R, _x: &f64) -> Result<(), Error> {
+        report.init(&self.bracket)
+    }
+
+    fn update_report<R: Report<Arg = Bracket>>(
+        &self,
+        report: &mut R,
+        _x: &f64,
+    ) -> Result<(), Error> {
+        report.update(&self.bracket)
+    }
+}
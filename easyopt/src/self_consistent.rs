@@ -4,6 +4,7 @@ pub use crate::executor::*;
 pub use crate::monitor;
 pub use crate::traits::*;
 
+use ndarray::Array1;
 use num_traits::Zero;
 use serde::Serialize;
 
@@ -46,6 +47,29 @@ where
     }
 }
 
+/// A vector operator generic over the scalar type, so that it can be evaluated on dual numbers.
+pub trait DifferentiableOp {
+    fn apply<T: num_traits::Float>(&self, x: &[T]) -> Result<Vec<T>, Error>;
+}
+
+/// Wraps a `DifferentiableOp` as a `SelfConsistentOp` on `Array1<f64>`.
+pub struct Differentiable<F>(pub F);
+
+impl<F> SelfConsistentOp for Differentiable<F>
+where
+    F: DifferentiableOp,
+{
+    type Variable = Array1<f64>;
+    fn apply(&self, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+        let y = self.0.apply(&x.to_vec())?;
+        if y.len() == x.len() {
+            Ok(Array1::from(y))
+        } else {
+            Err(Error::InvalidVariable)
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct DefaultReport<T>
 where
@@ -98,6 +122,48 @@ where
     }
 }
 
+/// Report for vector-valued variables, measuring the change between iterates by the
+/// infinity norm.
+#[derive(Serialize)]
+pub struct VectorReport {
+    pub count: usize,
+    /// `|x - x_prev| / |x_prev|`, or the absolute change when `x_prev` vanishes.
+    pub error: f64,
+    #[serde(skip)]
+    pub current: Array1<f64>,
+}
+
+impl Report for VectorReport {
+    type Arg = Array1<f64>;
+
+    fn init(&mut self, x: &Array1<f64>) -> Result<(), Error> {
+        self.count = 0;
+        self.current = x.clone();
+        self.error = f64::NAN;
+        Ok(())
+    }
+
+    fn update(&mut self, x: &Array1<f64>) -> Result<(), Error> {
+        let inf_norm = |v: &Array1<f64>| v.iter().fold(0f64, |acc, x| acc.max(x.abs()));
+        let change = inf_norm(&(x - &self.current));
+        let scale = inf_norm(&self.current);
+        self.error = if scale > 0. { change / scale } else { change };
+        self.current = x.clone();
+        self.count += 1;
+        Ok(())
+    }
+}
+
+impl Default for VectorReport {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            error: f64::NAN,
+            current: Array1::zeros(0),
+        }
+    }
+}
+
 pub trait SelfConsistentOpSolver<T>
 where
     T: SelfConsistentOp,
@@ -158,7 +224,9 @@ where
     }
 }
 
+pub mod implicit;
 pub mod solver;
+pub mod sparse;
 
 #[cfg(test)]
 mod test {
@@ -184,7 +252,8 @@ mod test {
             b: 1.,
             c: -2.,
         };
-        let solver = solver::Wegstein::<f64>::new();
+        // F'(sqrt(2)) > 1, so the fixed point needs q beyond the classic [-5, 0]
+        let solver = solver::Wegstein::<f64>::new().q_bounds(-5., 5.);
         let x = Executor::new(solver, op)
             .report(DefaultReport::<TestCase01>::default())
             .add_monitor(monitor::to_file("test.log")?)
@@ -198,7 +267,7 @@ mod test {
     #[test]
     fn case02_wegstein() -> anyhow::Result<()> {
         let op = |x: &f64| -> f64 { x * x + x - 2. };
-        let solver = solver::Wegstein::<f64>::new();
+        let solver = solver::Wegstein::<f64>::new().q_bounds(-5., 5.);
         let x = Executor::new(solver, op)
             .add_monitor(monitor::to_file("case02_wegstein.log")?)
             .terminate(when(|report: &DefaultReport<_>| report.error < 1e-8))
@@ -219,4 +288,29 @@ mod test {
         assert!(relative_eq!(f64::sqrt(2.), x));
         Ok(())
     }
+
+    #[test]
+    fn vector_wegstein() -> anyhow::Result<()> {
+        struct Coupled;
+        impl SelfConsistentOp for Coupled {
+            type Variable = Array1<f64>;
+            fn apply(&self, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+                // the last component is already at its fixed point and never changes
+                Ok(ndarray::array![
+                    0.9 * x[0] + 0.05 * x[1].sin() + 0.1,
+                    0.8 * x[1] + 0.05 * x[0] + 0.2,
+                    1.
+                ])
+            }
+        }
+        for every in 1..=3 {
+            let x = Executor::new(solver::Wegstein::<Array1<f64>>::new().every(every), Coupled)
+                .report(VectorReport::default())
+                .terminate(when(|r: &VectorReport| r.error < 1e-12 || r.count >= 500))
+                .run(ndarray::array![0., 0., 1.])?;
+            let r = Coupled.apply(&x)? - &x;
+            assert!(r.iter().all(|v| v.abs() < 1e-10));
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,143 @@
+use crate::continuation::{directional, ParametricOp};
+use crate::error::*;
+use crate::linalg;
+use dual::{Dual, Variables};
+use ndarray::prelude::*;
+
+/// Sensitivity `dx*/dp` of a solution `x* = F(x*, p)` by the implicit function theorem,
+/// `(I - dF/dx) dx*/dp = dF/dp`.
+///
+/// `x` must already be converged; the Jacobians are evaluated there column by column. Fails
+/// if `I - dF/dx` is singular, as it is at a fold of the solution branch.
+pub fn sensitivity<F>(f: &F, x: &Array1<f64>, p: &Array1<f64>) -> Result<Array2<f64>, Error>
+where
+    F: ParametricOp + ?Sized,
+{
+    let (n, m) = (x.len(), p.len());
+    let p = p.to_vec();
+    let zero_x = Array1::zeros(n);
+    let zero_p = vec![0.; m];
+    let mut a = Array2::eye(n);
+    for j in 0..n {
+        let mut v = zero_x.clone();
+        v[j] = 1.;
+        let column = directional(f, x, &p, &v, &zero_p)?;
+        a.column_mut(j).scaled_add(-1., &column);
+    }
+    let (q, r) = linalg::qr(&a, 1e-12)
+        .ok_or_else(|| Error::Failure("I - dF/dx is singular at the solution".to_string()))?;
+    let mut s = Array2::zeros((n, m));
+    for k in 0..m {
+        let mut w = zero_p.clone();
+        w[k] = 1.;
+        let column = directional(f, x, &p, &zero_x, &w)?;
+        s.column_mut(k)
+            .assign(&linalg::solve_upper(&r, &q.t().dot(&column)));
+    }
+    Ok(s)
+}
+
+/// The solution `x` at parameters given as dual numbers, carrying `dx*/dp` chained with the
+/// derivatives of `p`, so that it can be used in an outer computation on `Dual<f64, N>`.
+pub fn dual_solution<F, const N: usize>(
+    f: &F,
+    x: &Array1<f64>,
+    p: &[Dual<f64, N>],
+) -> Result<Vec<Dual<f64, N>>, Error>
+where
+    F: ParametricOp + ?Sized,
+{
+    let values: Array1<f64> = p.iter().map(|d| *d.val()).collect();
+    let s = sensitivity(f, x, &values)?;
+    let vars = Variables::<f64, N>::new();
+    Ok(x.iter()
+        .zip(s.rows())
+        .map(|(&xi, row)| {
+            let mut d = vars.constant(xi);
+            for (&sik, pk) in row.iter().zip(p.iter()) {
+                d.grad_mut().scaled_add(sik, &pk.grad());
+            }
+            d
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    /// `x = A x + B p`.
+    struct Linear;
+
+    impl ParametricOp for Linear {
+        fn apply<T: num_traits::Float>(&self, x: &[T], p: &[T]) -> Result<Vec<T>, Error> {
+            let c = |v: f64| T::from(v).unwrap();
+            Ok(vec![
+                c(0.5) * x[0] + c(0.2) * x[1] + p[0],
+                c(0.1) * x[0] + c(0.3) * x[1] + c(2.) * p[1] - p[0],
+            ])
+        }
+    }
+
+    /// `x_i = p_0 cos(x_{i+1}) + p_1 x_i / 2` with cyclic indices.
+    struct Cosine;
+
+    impl ParametricOp for Cosine {
+        fn apply<T: num_traits::Float>(&self, x: &[T], p: &[T]) -> Result<Vec<T>, Error> {
+            let n = x.len();
+            let half = T::from(0.5).unwrap();
+            Ok((0..n)
+                .map(|i| p[0] * x[(i + 1) % n].cos() + half * p[1] * x[i])
+                .collect())
+        }
+    }
+
+    fn solve(p: &Array1<f64>) -> Array1<f64> {
+        let mut x = Array1::<f64>::zeros(3);
+        for _ in 0..500 {
+            x = Array1::from(Cosine.apply(&x.to_vec(), &p.to_vec()).unwrap());
+        }
+        x
+    }
+
+    #[test]
+    fn linear() -> anyhow::Result<()> {
+        let s = sensitivity(&Linear, &array![0., 0.], &array![0., 0.])?;
+        // (I - A)^-1 B with det(I - A) = 0.33
+        let expected = array![[0.5, 0.4], [-0.4, 1.0]] / 0.33;
+        for (a, b) in s.iter().zip(expected.iter()) {
+            assert_abs_diff_eq!(*a, *b, epsilon = 1e-12);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn nonlinear_against_finite_differences() -> anyhow::Result<()> {
+        let p = array![0.7, 0.4];
+        let x = solve(&p);
+        let s = sensitivity(&Cosine, &x, &p)?;
+        let h = 1e-6;
+        for k in 0..2 {
+            let mut dp = Array1::zeros(2);
+            dp[k] = h;
+            let fd = (solve(&(&p + &dp)) - solve(&(&p - &dp))) / (2. * h);
+            for i in 0..3 {
+                assert_abs_diff_eq!(s[[i, k]], fd[i], epsilon = 1e-7);
+            }
+        }
+
+        // an outer objective sum x_i^2 differentiated through the solution
+        let duals = Variables::<f64, 2>::new().gen_all(&p.to_vec());
+        let xd = dual_solution(&Cosine, &x, &duals)?;
+        let objective = xd
+            .iter()
+            .fold(Dual::<f64, 2>::default(), |acc, &xi| acc + xi * xi);
+        let grad = 2. * s.t().dot(&x);
+        assert_abs_diff_eq!(*objective.val(), x.dot(&x), epsilon = 1e-12);
+        for k in 0..2 {
+            assert_abs_diff_eq!(objective.grad()[k], grad[k], epsilon = 1e-12);
+        }
+        Ok(())
+    }
+}
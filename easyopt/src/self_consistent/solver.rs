@@ -1,53 +1,109 @@
 use super::{SelfConsistentOp, SelfConsistentOpSolver};
 use crate::error::*;
 use crate::traits::*;
-use num_traits::One;
-use std::marker::PhantomData;
 //use std::ops::{Add, Div, Mul, Sub};
 
+/// Wegstein's method applied to each component of the variable.
+///
+/// Each component is updated as `q x + (1 - q) y` from `y = F(x)`, where `q = s / (s - 1)` and
+/// `s` is the secant slope of that component. `q` is clamped to a configurable interval, and
+/// components that barely moved fall back to direct substitution (`q = 0`) instead of dividing
+/// by a tiny difference.
 pub struct Wegstein<T, K = f64> {
     y_prev: Option<T>,
     x_prev: Option<T>,
-    _scalar_type: PhantomData<K>,
+    q_min: K,
+    q_max: K,
+    tolerance: K,
+    every: usize,
+    count: usize,
 }
 
-impl<T, F> Wegstein<T, F> {
+impl<T, K> Default for Wegstein<T, K>
+where
+    K: num_traits::Float,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, K> Wegstein<T, K>
+where
+    K: num_traits::Float,
+{
     pub fn new() -> Self {
         Self {
             y_prev: None,
             x_prev: None,
-            _scalar_type: PhantomData,
+            q_min: K::from(-5.).unwrap(),
+            q_max: K::zero(),
+            tolerance: K::epsilon().sqrt(),
+            every: 1,
+            count: 0,
         }
     }
+
+    /// Interval to which `q` is clamped; `[-5, 0]` by default.
+    pub fn q_bounds(mut self, q_min: K, q_max: K) -> Self {
+        self.q_min = q_min;
+        self.q_max = q_max;
+        self
+    }
+
+    /// Relative change of a component below which it is not accelerated; the square root of
+    /// the machine epsilon by default.
+    pub fn tolerance(mut self, tol: K) -> Self {
+        self.tolerance = tol;
+        self
+    }
+
+    /// Accelerates only every `k`-th iteration and substitutes directly in between; 1 by
+    /// default.
+    pub fn every(mut self, k: usize) -> Self {
+        self.every = k.max(1);
+        self
+    }
 }
 
-impl<T, U, F> SelfConsistentOpSolver<T> for Wegstein<U, F>
+impl<T, U, K> SelfConsistentOpSolver<T> for Wegstein<U, K>
 where
     T: Op<Variable = U> + SelfConsistentOp<Variable = <T as Op>::Variable>,
-    F: One + BinaryOperand<U, U> + for<'a> BinaryOperand<&'a U, U>,
-    for<'a> U: Clone + BinaryOperand<&'a U, U>,
-    for<'a, 'b> &'a U: BinaryOperand<&'b U, U>,
+    U: Clone + Elementwise<K>,
+    K: num_traits::Float,
 {
     fn next_iter(&mut self, op: &T, x: &<T as Op>::Variable) -> Result<<T as Op>::Variable, Error> {
-        let y = op.apply(&x)?;
-        if let Some(y_prev) = self.y_prev.as_ref() {
-            let x_prev = self.x_prev.as_ref().unwrap();
-            let s = (&y - &y_prev) / &(x - &x_prev);
-            let t = F::one() / (F::one() - &s);
-            let next = (&t * &y) + &((F::one() - &t) * &x);
-            self.x_prev.replace(x.clone());
-            self.y_prev.replace(y);
-            Ok(next)
-        } else {
-            self.x_prev.replace(x.clone());
-            self.y_prev.replace(y.clone());
-            Ok(y)
-        }
+        let y = op.apply(x)?;
+        self.count += 1;
+        let next = match (self.x_prev.as_ref(), self.y_prev.as_ref()) {
+            (Some(x_prev), Some(y_prev)) if self.count.is_multiple_of(self.every) => {
+                let (q_min, q_max, tol) = (self.q_min, self.q_max, self.tolerance);
+                x.zip_map4(&y, x_prev, y_prev, |x, y, x_prev, y_prev| {
+                    let dx = x - x_prev;
+                    if dx.abs() <= tol * x.abs().max(K::one()) {
+                        return y;
+                    }
+                    let s = (y - y_prev) / dx;
+                    let q = (s / (s - K::one())).max(q_min).min(q_max);
+                    q * x + (K::one() - q) * y
+                })
+            }
+            _ => y.clone(),
+        };
+        self.x_prev.replace(x.clone());
+        self.y_prev.replace(y);
+        Ok(next)
     }
 }
 
 pub struct Steffensen;
 
+impl Default for Steffensen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Steffensen {
     pub fn new() -> Self {
         Self {}
@@ -62,10 +118,16 @@ where
         BinaryOperand<&'b <T as Op>::Variable, <T as Op>::Variable>,
 {
     fn next_iter(&mut self, op: &T, x: &<T as Op>::Variable) -> Result<<T as Op>::Variable, Error> {
-        let y = op.apply(&x)?;
+        let y = op.apply(x)?;
         let z = op.apply(&y)? - &y;
-        let y = y - &x;
+        let y = y - x;
         let x = x - &(&y * &y / &(z - &y));
         Ok(x)
     }
 }
+
+pub mod extrapolation;
+pub use extrapolation::*;
+
+pub mod newton_krylov;
+pub use newton_krylov::*;
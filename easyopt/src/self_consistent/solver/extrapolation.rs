@@ -0,0 +1,299 @@
+use crate::error::*;
+use crate::linalg;
+use crate::self_consistent::{SelfConsistentOp, SelfConsistentOpSolver};
+use ndarray::prelude::*;
+
+/// `x_0 = x` followed by `count` fixed-point iterates.
+fn iterates<T>(op: &T, x: &Array1<f64>, count: usize) -> Result<Vec<Array1<f64>>, Error>
+where
+    T: SelfConsistentOp<Variable = Array1<f64>>,
+{
+    let mut xs = Vec::with_capacity(count + 1);
+    xs.push(x.clone());
+    for _ in 0..count {
+        let next = op.apply(xs.last().unwrap())?;
+        xs.push(next);
+    }
+    Ok(xs)
+}
+
+fn differences(xs: &[Array1<f64>]) -> Array2<f64> {
+    let n = xs[0].len();
+    let mut u = Array2::zeros((n, xs.len() - 1));
+    for (j, w) in xs.windows(2).enumerate() {
+        u.column_mut(j).assign(&(&w[1] - &w[0]));
+    }
+    u
+}
+
+/// `sum_j gamma_j x_j`, or `None` if it is not finite.
+fn combine(xs: &[Array1<f64>], gamma: &Array1<f64>) -> Option<Array1<f64>> {
+    let mut s = Array1::zeros(xs[0].len());
+    for (x, &g) in xs.iter().zip(gamma.iter()) {
+        s.scaled_add(g, x);
+    }
+    if s.iter().all(|v| v.is_finite()) {
+        Some(s)
+    } else {
+        None
+    }
+}
+
+/// Minimal polynomial extrapolation.
+///
+/// Each iteration applies the operator `order + 1` times and returns the extrapolated limit of
+/// the iterates. When the differences of the iterates are numerically dependent, as they are
+/// once the sequence has converged, the order is lowered; if no order works the last iterate is
+/// returned.
+pub struct Mpe {
+    order: usize,
+    rcond: f64,
+}
+
+impl Mpe {
+    pub fn new(order: usize) -> Self {
+        Self {
+            order: order.max(1),
+            rcond: 1e-12,
+        }
+    }
+
+    /// Relative threshold below which differences are treated as dependent; 1e-12 by default.
+    pub fn rcond(mut self, rcond: f64) -> Self {
+        self.rcond = rcond;
+        self
+    }
+
+    fn extrapolate(&self, xs: &[Array1<f64>]) -> Option<Array1<f64>> {
+        let k = xs.len() - 2;
+        let u = differences(xs);
+        let (q, r) = linalg::qr(&u.slice(s![.., ..k]).to_owned(), self.rcond)?;
+        let rhs = -u.column(k).to_owned();
+        let c = linalg::solve_upper(&r, &q.t().dot(&rhs));
+        let sum = c.sum() + 1.;
+        let size = c.iter().map(|v| v.abs()).sum::<f64>() + 1.;
+        if sum.abs() <= f64::EPSILON.sqrt() * size {
+            return None;
+        }
+        let mut gamma = Array1::ones(k + 1);
+        gamma.slice_mut(s![..k]).assign(&c);
+        combine(xs, &(gamma / sum))
+    }
+}
+
+impl<T> SelfConsistentOpSolver<T> for Mpe
+where
+    T: SelfConsistentOp<Variable = Array1<f64>>,
+{
+    fn next_iter(&mut self, op: &T, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+        let xs = iterates(op, x, self.order + 1)?;
+        for k in (1..=self.order).rev() {
+            if let Some(s) = self.extrapolate(&xs[..k + 2]) {
+                return Ok(s);
+            }
+        }
+        Ok(xs.last().unwrap().clone())
+    }
+}
+
+/// Reduced rank extrapolation.
+///
+/// Each iteration applies the operator `order + 1` times and returns the affine combination of
+/// the iterates whose combined difference has the least norm. Dependent differences lower the
+/// order as in `Mpe`.
+pub struct Rre {
+    order: usize,
+    rcond: f64,
+}
+
+impl Rre {
+    pub fn new(order: usize) -> Self {
+        Self {
+            order: order.max(1),
+            rcond: 1e-12,
+        }
+    }
+
+    /// Relative threshold below which differences are treated as dependent; 1e-12 by default.
+    pub fn rcond(mut self, rcond: f64) -> Self {
+        self.rcond = rcond;
+        self
+    }
+
+    fn extrapolate(&self, xs: &[Array1<f64>]) -> Option<Array1<f64>> {
+        // s = x_0 + U xi with xi minimizing |u_0 + W xi|, W holding the second differences
+        let u = differences(xs);
+        let w = &u.slice(s![.., 1..]) - &u.slice(s![.., ..-1]);
+        let (q, r) = linalg::qr(&w, self.rcond)?;
+        let xi = -linalg::solve_upper(&r, &q.t().dot(&u.column(0)));
+        let s = &xs[0] + &u.slice(s![.., ..xi.len()]).dot(&xi);
+        if s.iter().all(|v| v.is_finite()) {
+            Some(s)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> SelfConsistentOpSolver<T> for Rre
+where
+    T: SelfConsistentOp<Variable = Array1<f64>>,
+{
+    fn next_iter(&mut self, op: &T, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+        let xs = iterates(op, x, self.order + 1)?;
+        for k in (1..=self.order).rev() {
+            if let Some(s) = self.extrapolate(&xs[..k + 2]) {
+                return Ok(s);
+            }
+        }
+        Ok(xs.last().unwrap().clone())
+    }
+}
+
+/// Wynn's vector epsilon algorithm with the Samelson inverse `v / |v|^2`.
+///
+/// Each iteration applies the operator `2 order` times and returns `epsilon_{2 order}`. When a
+/// difference in the table vanishes the last complete even column is used instead.
+pub struct VectorEpsilon {
+    order: usize,
+    tolerance: f64,
+}
+
+impl VectorEpsilon {
+    pub fn new(order: usize) -> Self {
+        Self {
+            order: order.max(1),
+            tolerance: 1e-12,
+        }
+    }
+
+    /// Relative size below which a difference in the table counts as vanishing; 1e-12 by
+    /// default.
+    pub fn tolerance(mut self, tol: f64) -> Self {
+        self.tolerance = tol;
+        self
+    }
+}
+
+impl<T> SelfConsistentOpSolver<T> for VectorEpsilon
+where
+    T: SelfConsistentOp<Variable = Array1<f64>>,
+{
+    fn next_iter(&mut self, op: &T, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+        let xs = iterates(op, x, 2 * self.order)?;
+        let scale = xs.iter().map(|x| x.dot(x)).fold(0f64, f64::max);
+        let mut best = xs.last().unwrap().clone();
+        let mut prev: Vec<Array1<f64>> = vec![Array1::zeros(x.len()); xs.len() + 1];
+        let mut cur = xs;
+        for col in 1..=2 * self.order {
+            let mut next = Vec::with_capacity(cur.len() - 1);
+            for j in 0..cur.len() - 1 {
+                let d = &cur[j + 1] - &cur[j];
+                let d2 = d.dot(&d);
+                let threshold = if col == 1 { scale } else { 1. };
+                if d2 <= self.tolerance * self.tolerance * threshold {
+                    return Ok(best);
+                }
+                next.push(&prev[j + 1] + &(d / d2));
+            }
+            prev = cur;
+            cur = next;
+            if col % 2 == 0 {
+                match cur.last() {
+                    Some(s) if s.iter().all(|v| v.is_finite()) => best = s.clone(),
+                    _ => return Ok(best),
+                }
+            }
+        }
+        Ok(best)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::self_consistent::*;
+    use approx::assert_abs_diff_eq;
+    use ndarray::prelude::*;
+
+    /// `x = A x + b` with a contraction `A`.
+    struct Linear;
+
+    impl SelfConsistentOp for Linear {
+        type Variable = Array1<f64>;
+        fn apply(&self, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+            let a = array![[0.5, 0.2, 0.], [0.1, 0.6, 0.2], [0., 0.3, 0.8]];
+            Ok(a.dot(x) + array![1., 2., 3.])
+        }
+    }
+
+    /// `x_i = 0.9 cos(x_{i+1}) + 0.05 x_i` with cyclic indices.
+    struct Cosine;
+
+    impl SelfConsistentOp for Cosine {
+        type Variable = Array1<f64>;
+        fn apply(&self, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+            let n = x.len();
+            Ok(Array1::from_shape_fn(n, |i| {
+                0.9 * x[(i + 1) % n].cos() + 0.05 * x[i]
+            }))
+        }
+    }
+
+    fn residual<T: SelfConsistentOp<Variable = Array1<f64>>>(op: &T, x: &Array1<f64>) -> f64 {
+        let r = op.apply(x).unwrap() - x;
+        r.iter().fold(0f64, |acc, v| acc.max(v.abs()))
+    }
+
+    #[test]
+    fn linear_in_one_cycle() -> anyhow::Result<()> {
+        // the minimal polynomial of A has degree 3
+        let x0 = Array1::zeros(3);
+        let x = Executor::new(solver::Mpe::new(3), Linear)
+            .report(VectorReport::default())
+            .terminate(when(|r: &VectorReport| r.count >= 1))
+            .run(x0.clone())?;
+        assert!(residual(&Linear, &x) < 1e-9);
+        let x = Executor::new(solver::Rre::new(3), Linear)
+            .report(VectorReport::default())
+            .terminate(when(|r: &VectorReport| r.count >= 1))
+            .run(x0.clone())?;
+        assert!(residual(&Linear, &x) < 1e-9);
+        let x = Executor::new(solver::VectorEpsilon::new(3), Linear)
+            .report(VectorReport::default())
+            .terminate(when(|r: &VectorReport| r.count >= 1))
+            .run(x0)?;
+        assert!(residual(&Linear, &x) < 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn nonlinear_and_converged() -> anyhow::Result<()> {
+        let x0 = Array1::zeros(5);
+        let mpe = Executor::new(solver::Mpe::new(4), Cosine)
+            .report(VectorReport::default())
+            .terminate(when(|r: &VectorReport| r.error < 1e-13 || r.count >= 50))
+            .run(x0.clone())?;
+        let rre = Executor::new(solver::Rre::new(4), Cosine)
+            .report(VectorReport::default())
+            .terminate(when(|r: &VectorReport| r.error < 1e-13 || r.count >= 50))
+            .run(x0.clone())?;
+        let eps = Executor::new(solver::VectorEpsilon::new(2), Cosine)
+            .report(VectorReport::default())
+            .terminate(when(|r: &VectorReport| r.error < 1e-13 || r.count >= 50))
+            .run(x0)?;
+        for x in [&mpe, &rre, &eps] {
+            assert!(residual(&Cosine, x) < 1e-12);
+        }
+
+        // at the fixed point every difference vanishes; the solvers must not produce NaN
+        let a = SelfConsistentOpSolver::next_iter(&mut solver::Mpe::new(3), &Cosine, &mpe)?;
+        let b = SelfConsistentOpSolver::next_iter(&mut solver::Rre::new(3), &Cosine, &mpe)?;
+        let c =
+            SelfConsistentOpSolver::next_iter(&mut solver::VectorEpsilon::new(2), &Cosine, &mpe)?;
+        for v in [a, b, c] {
+            assert!(v.iter().all(|v| v.is_finite()));
+            assert_abs_diff_eq!(v[0], mpe[0], epsilon = 1e-12);
+        }
+        Ok(())
+    }
+}
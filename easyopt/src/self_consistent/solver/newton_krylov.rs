@@ -0,0 +1,443 @@
+use crate::error::*;
+use crate::linalg;
+use crate::self_consistent::{
+    Differentiable, DifferentiableOp, SelfConsistentOp, SelfConsistentOpSolver,
+};
+use dual::Variables;
+use ndarray::prelude::*;
+
+fn norm(v: &Array1<f64>) -> f64 {
+    v.dot(v).sqrt()
+}
+
+/// Product of the Jacobian of an operator with a direction.
+pub trait JacobianVector<T>
+where
+    T: SelfConsistentOp<Variable = Array1<f64>>,
+{
+    /// `J v`, where `J` is the Jacobian of `op` at `x` and `fx = op.apply(x)`.
+    fn jvp(
+        &self,
+        op: &T,
+        x: &Array1<f64>,
+        fx: &Array1<f64>,
+        v: &Array1<f64>,
+    ) -> Result<Array1<f64>, Error>;
+
+    /// Forgets anything kept from earlier products. `Jfnk` calls it whenever it does not
+    /// continue from its own last step, as at the start of a run.
+    fn reset(&self) {}
+}
+
+/// Forward differences `(F(x + h v) - F(x)) / h`, usable with any operator.
+pub struct FiniteDifference {
+    step: f64,
+}
+
+impl Default for FiniteDifference {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FiniteDifference {
+    pub fn new() -> Self {
+        Self {
+            step: f64::EPSILON.sqrt(),
+        }
+    }
+
+    /// Relative step; `h = step max(1, |x|) / |v|`. sqrt(eps) by default.
+    pub fn step(mut self, step: f64) -> Self {
+        self.step = step;
+        self
+    }
+}
+
+impl<T> JacobianVector<T> for FiniteDifference
+where
+    T: SelfConsistentOp<Variable = Array1<f64>>,
+{
+    fn jvp(
+        &self,
+        op: &T,
+        x: &Array1<f64>,
+        fx: &Array1<f64>,
+        v: &Array1<f64>,
+    ) -> Result<Array1<f64>, Error> {
+        let v_norm = norm(v);
+        if v_norm == 0. {
+            return Ok(Array1::zeros(x.len()));
+        }
+        let h = self.step * norm(x).max(1.) / v_norm;
+        let shifted = op.apply(&(x + &(h * v)))?;
+        Ok((shifted - fx) / h)
+    }
+}
+
+/// Exact products from a single evaluation on `Dual<f64, 1>` seeded with the direction.
+pub struct ForwardMode;
+
+impl<F> JacobianVector<Differentiable<F>> for ForwardMode
+where
+    F: DifferentiableOp,
+{
+    fn jvp(
+        &self,
+        op: &Differentiable<F>,
+        x: &Array1<f64>,
+        _fx: &Array1<f64>,
+        v: &Array1<f64>,
+    ) -> Result<Array1<f64>, Error> {
+        let vars = Variables::<f64, 1>::new();
+        let seeded: Vec<_> = x
+            .iter()
+            .zip(v.iter())
+            .map(|(&xi, &vi)| {
+                let mut d = vars.constant(xi);
+                d.grad_mut()[0] = vi;
+                d
+            })
+            .collect();
+        let y = op.0.apply(&seeded)?;
+        if y.len() != x.len() {
+            return Err(Error::InvalidVariable);
+        }
+        Ok(y.iter().map(|d| d.grad()[0]).collect())
+    }
+}
+
+/// Tolerance of the inner linear solve relative to the current residual.
+#[derive(Debug, Clone, Copy)]
+pub enum Forcing {
+    Constant(f64),
+    /// Eisenstat–Walker choice 2, `gamma (|r_k| / |r_{k-1}|)^alpha`, safeguarded against
+    /// dropping too fast and capped at `max`, which is also used at the first iteration.
+    EisenstatWalker {
+        gamma: f64,
+        alpha: f64,
+        max: f64,
+    },
+}
+
+impl Default for Forcing {
+    fn default() -> Self {
+        Forcing::EisenstatWalker {
+            gamma: 0.9,
+            alpha: 2.,
+            max: 0.9,
+        }
+    }
+}
+
+type Preconditioner = Box<dyn Fn(&Array1<f64>) -> Array1<f64>>;
+
+/// Jacobian-free Newton–Krylov method for the residual `r(x) = x - F(x)`.
+///
+/// The Newton step solves `(I - J) p = -r` with restarted GMRES, where the products with the
+/// Jacobian `J` of `F` come from `D`. An optional right preconditioner should approximate the
+/// action of `(I - J)^-1`. The step is backtracked until the residual norm decreases
+/// sufficiently.
+pub struct Jfnk<D> {
+    jvp: D,
+    preconditioner: Option<Preconditioner>,
+    forcing: Forcing,
+    krylov: usize,
+    max_restarts: usize,
+    max_backtracks: usize,
+    prev: Option<(f64, f64)>,
+    cache: Option<(Array1<f64>, Array1<f64>)>,
+}
+
+impl<D> Jfnk<D> {
+    pub fn new(jvp: D) -> Self {
+        Self {
+            jvp,
+            preconditioner: None,
+            forcing: Forcing::default(),
+            krylov: 30,
+            max_restarts: 10,
+            max_backtracks: 20,
+            prev: None,
+            cache: None,
+        }
+    }
+
+    pub fn preconditioner<P>(mut self, p: P) -> Self
+    where
+        P: 'static + Fn(&Array1<f64>) -> Array1<f64>,
+    {
+        self.preconditioner = Some(Box::new(p));
+        self
+    }
+
+    pub fn forcing(mut self, forcing: Forcing) -> Self {
+        self.forcing = forcing;
+        self
+    }
+
+    /// Dimension of the Krylov subspace before GMRES restarts; 30 by default.
+    pub fn krylov(mut self, m: usize) -> Self {
+        self.krylov = m.max(1);
+        self
+    }
+
+    /// 10 by default.
+    pub fn max_restarts(mut self, n: usize) -> Self {
+        self.max_restarts = n;
+        self
+    }
+
+    /// Number of step halvings before the shortest step is taken anyway; 20 by default.
+    pub fn max_backtracks(mut self, n: usize) -> Self {
+        self.max_backtracks = n;
+        self
+    }
+
+    fn eta(&self, r_norm: f64) -> f64 {
+        match self.forcing {
+            Forcing::Constant(eta) => eta,
+            Forcing::EisenstatWalker { gamma, alpha, max } => match self.prev {
+                None => max,
+                Some((prev_norm, prev_eta)) => {
+                    let mut eta = gamma * (r_norm / prev_norm).powf(alpha);
+                    let floor = gamma * prev_eta.powf(alpha);
+                    if floor > 0.1 {
+                        eta = eta.max(floor);
+                    }
+                    eta.min(max)
+                }
+            },
+        }
+    }
+
+    fn precondition(&self, v: &Array1<f64>) -> Array1<f64> {
+        match &self.preconditioner {
+            Some(p) => p(v),
+            None => v.clone(),
+        }
+    }
+
+    /// Right-preconditioned restarted GMRES for `A p = b` starting from `p = 0`.
+    fn gmres<A>(&self, mut a: A, b: &Array1<f64>, tol: f64) -> Result<Array1<f64>, Error>
+    where
+        A: FnMut(&Array1<f64>) -> Result<Array1<f64>, Error>,
+    {
+        let m = self.krylov;
+        let mut p = Array1::zeros(b.len());
+        for restart in 0..=self.max_restarts {
+            let r0 = if restart == 0 { b.clone() } else { b - &a(&p)? };
+            let beta = norm(&r0);
+            if beta <= tol {
+                break;
+            }
+            let mut v = vec![r0 / beta];
+            let mut z = Vec::with_capacity(m);
+            let mut h = Array2::<f64>::zeros((m + 1, m));
+            let mut g = Array1::<f64>::zeros(m + 1);
+            let mut rotations = Vec::<(f64, f64)>::with_capacity(m);
+            g[0] = beta;
+            let mut k = 0;
+            for j in 0..m {
+                z.push(self.precondition(&v[j]));
+                let mut w = a(&z[j])?;
+                for (i, vi) in v.iter().enumerate() {
+                    h[[i, j]] = w.dot(vi);
+                    w.scaled_add(-h[[i, j]], vi);
+                }
+                let w_norm = norm(&w);
+                h[[j + 1, j]] = w_norm;
+                for (i, &(c, s)) in rotations.iter().enumerate() {
+                    let (hi, hk) = (h[[i, j]], h[[i + 1, j]]);
+                    h[[i, j]] = c * hi + s * hk;
+                    h[[i + 1, j]] = -s * hi + c * hk;
+                }
+                let d = h[[j, j]].hypot(h[[j + 1, j]]);
+                let (c, s) = if d > 0. {
+                    (h[[j, j]] / d, h[[j + 1, j]] / d)
+                } else {
+                    (1., 0.)
+                };
+                rotations.push((c, s));
+                h[[j, j]] = d;
+                h[[j + 1, j]] = 0.;
+                g[j + 1] = -s * g[j];
+                g[j] *= c;
+                k = j + 1;
+                if g[j + 1].abs() <= tol || w_norm <= f64::EPSILON * beta || d == 0. {
+                    break;
+                }
+                v.push(w / w_norm);
+            }
+            while k > 0 && h[[k - 1, k - 1]] == 0. {
+                k -= 1;
+            }
+            let y = linalg::solve_upper(
+                &h.slice(s![..k, ..k]).to_owned(),
+                &g.slice(s![..k]).to_owned(),
+            );
+            for (zi, &yi) in z.iter().zip(y.iter()) {
+                p.scaled_add(yi, zi);
+            }
+            if g[k].abs() <= tol {
+                break;
+            }
+        }
+        Ok(p)
+    }
+}
+
+impl<T, D> SelfConsistentOpSolver<T> for Jfnk<D>
+where
+    T: SelfConsistentOp<Variable = Array1<f64>>,
+    D: JacobianVector<T>,
+{
+    fn next_iter(&mut self, op: &T, x: &Array1<f64>) -> Result<Array1<f64>, Error> {
+        let fx = match self.cache.take() {
+            Some((cx, cf)) if cx == *x => cf,
+            _ => {
+                self.jvp.reset();
+                op.apply(x)?
+            }
+        };
+        let r = x - &fx;
+        let r_norm = norm(&r);
+        if !r_norm.is_finite() {
+            return Err(Error::Failure("residual is not finite".to_string()));
+        }
+        if r_norm == 0. {
+            self.cache = Some((x.clone(), fx));
+            return Ok(x.clone());
+        }
+        let eta = self.eta(r_norm);
+        let jvp = &self.jvp;
+        let p = self.gmres(|v| Ok(v - &jvp.jvp(op, x, &fx, v)?), &(-&r), eta * r_norm)?;
+
+        let mut lambda = 1.;
+        let mut trial = x + &p;
+        let mut f_trial = op.apply(&trial)?;
+        for _ in 0..self.max_backtracks {
+            let trial_norm = norm(&(&trial - &f_trial));
+            if trial_norm <= (1. - 1e-4 * lambda) * r_norm {
+                break;
+            }
+            lambda *= 0.5;
+            trial = x + &(lambda * &p);
+            f_trial = op.apply(&trial)?;
+        }
+        self.prev = Some((r_norm, eta));
+        self.cache = Some((trial.clone(), f_trial));
+        Ok(trial)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::self_consistent::*;
+
+    /// Discretized Bratu problem `u'' + lambda exp(u) = 0` on (0, 1) with zero boundaries,
+    /// written as the Jacobi-like fixed point `u_i = (u_{i-1} + u_{i+1} + h^2 lambda exp(u_i)) / 2`.
+    #[derive(Clone, Copy)]
+    struct Bratu {
+        n: usize,
+        lambda: f64,
+    }
+
+    impl DifferentiableOp for Bratu {
+        fn apply<T: num_traits::Float>(&self, u: &[T]) -> Result<Vec<T>, Error> {
+            let h = 1. / (self.n + 1) as f64;
+            let c = T::from(h * h * self.lambda).unwrap();
+            let half = T::from(0.5).unwrap();
+            Ok((0..self.n)
+                .map(|i| {
+                    let left = if i == 0 { T::zero() } else { u[i - 1] };
+                    let right = if i + 1 == self.n { T::zero() } else { u[i + 1] };
+                    (left + right + c * u[i].exp()) * half
+                })
+                .collect())
+        }
+    }
+
+    fn residual<T: SelfConsistentOp<Variable = Array1<f64>>>(op: &T, x: &Array1<f64>) -> f64 {
+        let r = op.apply(x).unwrap() - x;
+        r.iter().fold(0f64, |acc, v| acc.max(v.abs()))
+    }
+
+    /// Applies `(I - T / 2)^-1` with the tridiagonal `T` of the neighbours (Thomas algorithm).
+    fn laplacian_inverse(b: &Array1<f64>) -> Array1<f64> {
+        let n = b.len();
+        let mut c = vec![0.; n];
+        let mut d = b.to_vec();
+        let mut diag = 1.;
+        for i in 0..n {
+            if i > 0 {
+                diag = 1. + 0.5 * c[i - 1];
+                d[i] += 0.5 * d[i - 1];
+            }
+            c[i] = -0.5 / diag;
+            d[i] /= diag;
+        }
+        for i in (0..n - 1).rev() {
+            d[i] -= c[i] * d[i + 1];
+        }
+        Array1::from(d)
+    }
+
+    #[test]
+    fn bratu() -> anyhow::Result<()> {
+        let bratu = Bratu { n: 50, lambda: 1. };
+        let op = Differentiable(bratu);
+        let x0 = Array1::zeros(50);
+        let mut results = Vec::new();
+        for (solver, max_krylov) in [
+            (solver::Jfnk::new(solver::FiniteDifference::new()), 200),
+            (
+                solver::Jfnk::new(solver::FiniteDifference::new())
+                    .preconditioner(laplacian_inverse),
+                3,
+            ),
+        ] {
+            let x = Executor::new(solver.krylov(max_krylov), Differentiable(bratu))
+                .report(VectorReport::default())
+                .terminate(when(|r: &VectorReport| r.error < 1e-12 || r.count >= 20))
+                .run(x0.clone())?;
+            assert!(residual(&op, &x) < 1e-10);
+            results.push(x);
+        }
+        let x = Executor::new(
+            solver::Jfnk::new(solver::ForwardMode),
+            Differentiable(bratu),
+        )
+        .report(VectorReport::default())
+        .terminate(when(|r: &VectorReport| r.error < 1e-12 || r.count >= 20))
+        .run(x0)?;
+        assert!(residual(&op, &x) < 1e-10);
+        for y in &results {
+            assert!((y - &x).iter().all(|d| d.abs() < 1e-9));
+        }
+        // the maximum of the lower branch is about 0.140
+        assert!((x[24] - 0.140).abs() < 2e-3);
+        Ok(())
+    }
+
+    #[test]
+    fn constant_forcing_converges_fast() -> anyhow::Result<()> {
+        let bratu = Bratu { n: 20, lambda: 2. };
+        let op = Differentiable(bratu);
+        let mut count = 0;
+        let x = Executor::new(
+            solver::Jfnk::new(solver::ForwardMode).forcing(solver::Forcing::Constant(1e-12)),
+            Differentiable(bratu),
+        )
+        .report(VectorReport::default())
+        .add_monitor(|r: &VectorReport| {
+            count = r.count;
+            Ok(())
+        })
+        .terminate(when(|r: &VectorReport| r.error < 1e-14 || r.count >= 20))
+        .run(Array1::zeros(20))?;
+        assert!(residual(&op, &x) < 1e-12);
+        assert!(count <= 8);
+        Ok(())
+    }
+}
@@ -0,0 +1,381 @@
+use crate::error::*;
+use crate::self_consistent::solver::JacobianVector;
+use crate::self_consistent::{Differentiable, DifferentiableOp};
+use dual::tracer::{self, Tracer};
+use dual::Variables;
+use ndarray::prelude::*;
+use std::cell::RefCell;
+
+/// Sparse matrix in compressed sparse row format. The column indices of each row are sorted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsrMatrix {
+    pub nrows: usize,
+    pub ncols: usize,
+    /// Row `i` is stored in `indptr[i]..indptr[i + 1]`.
+    pub indptr: Vec<usize>,
+    pub indices: Vec<usize>,
+    pub values: Vec<f64>,
+}
+
+/// Sparse matrix as coordinate triplets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CooMatrix {
+    pub nrows: usize,
+    pub ncols: usize,
+    pub rows: Vec<usize>,
+    pub cols: Vec<usize>,
+    pub values: Vec<f64>,
+}
+
+impl CsrMatrix {
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Column indices and values of row `i`.
+    pub fn row(&self, i: usize) -> (&[usize], &[f64]) {
+        let range = self.indptr[i]..self.indptr[i + 1];
+        (&self.indices[range.clone()], &self.values[range])
+    }
+
+    pub fn dot(&self, v: &Array1<f64>) -> Array1<f64> {
+        (0..self.nrows)
+            .map(|i| {
+                let (cols, values) = self.row(i);
+                cols.iter().zip(values.iter()).map(|(&j, a)| a * v[j]).sum()
+            })
+            .collect()
+    }
+
+    pub fn to_dense(&self) -> Array2<f64> {
+        let mut a = Array2::zeros((self.nrows, self.ncols));
+        for i in 0..self.nrows {
+            let (cols, values) = self.row(i);
+            for (&j, &v) in cols.iter().zip(values.iter()) {
+                a[[i, j]] = v;
+            }
+        }
+        a
+    }
+
+    pub fn to_coo(&self) -> CooMatrix {
+        CooMatrix {
+            nrows: self.nrows,
+            ncols: self.ncols,
+            rows: (0..self.nrows)
+                .flat_map(|i| std::iter::repeat_n(i, self.indptr[i + 1] - self.indptr[i]))
+                .collect(),
+            cols: self.indices.clone(),
+            values: self.values.clone(),
+        }
+    }
+}
+
+/// Nonzero structure of a Jacobian in compressed sparse row format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparsityPattern {
+    pub nrows: usize,
+    pub ncols: usize,
+    pub indptr: Vec<usize>,
+    pub indices: Vec<usize>,
+}
+
+/// Assignment of the columns of a Jacobian to groups that can be evaluated together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coloring {
+    /// Color of each column.
+    pub colors: Vec<usize>,
+    pub count: usize,
+}
+
+impl SparsityPattern {
+    /// Pattern of the Jacobian of `f` at `x`, traced through a single evaluation on
+    /// `dual::tracer::Tracer`.
+    ///
+    /// The pattern is valid wherever `f` takes the same branches as at `x`.
+    pub fn detect<F>(f: &F, x: &Array1<f64>) -> Result<Self, Error>
+    where
+        F: DifferentiableOp + ?Sized,
+    {
+        let rows = tracer::trace(&x.to_vec(), |vars| -> Result<_, Error> {
+            Ok(f.apply(vars)?
+                .iter()
+                .map(Tracer::dependencies)
+                .collect::<Vec<_>>())
+        })?;
+        let mut indptr = Vec::with_capacity(rows.len() + 1);
+        indptr.push(0);
+        let mut indices = Vec::new();
+        for row in rows.iter() {
+            indices.extend_from_slice(row);
+            indptr.push(indices.len());
+        }
+        Ok(Self {
+            nrows: rows.len(),
+            ncols: x.len(),
+            indptr,
+            indices,
+        })
+    }
+
+    pub fn nnz(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Greedy coloring of the columns such that no two columns with a nonzero in the same
+    /// row share a color, visiting the columns with the most nonzeros first.
+    ///
+    /// The columns of one color can be seeded together in a single forward pass, since each
+    /// row then picks up the derivative with respect to at most one of them.
+    pub fn color_columns(&self) -> Coloring {
+        // rows of each column
+        let mut column_rows = vec![Vec::new(); self.ncols];
+        for i in 0..self.nrows {
+            for &j in &self.indices[self.indptr[i]..self.indptr[i + 1]] {
+                column_rows[j].push(i);
+            }
+        }
+        let mut order: Vec<usize> = (0..self.ncols).collect();
+        order.sort_by_key(|&j| std::cmp::Reverse(column_rows[j].len()));
+
+        const NONE: usize = usize::MAX;
+        let mut colors = vec![NONE; self.ncols];
+        // forbidden[c] == j marks color c as taken by a neighbour of column j
+        let mut forbidden = Vec::new();
+        let mut count = 0;
+        for &j in order.iter() {
+            for &i in column_rows[j].iter() {
+                for &k in &self.indices[self.indptr[i]..self.indptr[i + 1]] {
+                    if colors[k] != NONE {
+                        forbidden[colors[k]] = j;
+                    }
+                }
+            }
+            let color = (0..count).find(|&c| forbidden[c] != j).unwrap_or(count);
+            if color == count {
+                count += 1;
+                forbidden.push(NONE);
+            }
+            colors[j] = color;
+        }
+        Coloring { colors, count }
+    }
+}
+
+/// Jacobian of a `DifferentiableOp` with known sparsity, evaluated with one forward pass on
+/// `Dual<f64, 1>` per color of its columns.
+///
+/// As a `JacobianVector` for `Jfnk`, the Jacobian is evaluated once per point and kept for the
+/// following products at the same point, so that each GMRES iteration only costs a sparse
+/// product. The kept Jacobian is keyed on the point alone and dropped by `reset`, which `Jfnk`
+/// calls at the start of every run; callers of `jvp` that switch to another operator, or
+/// change its parameters, must call it themselves.
+pub struct SparseJacobian {
+    pattern: SparsityPattern,
+    coloring: Coloring,
+    cache: RefCell<Option<(Array1<f64>, CsrMatrix)>>,
+}
+
+impl SparseJacobian {
+    pub fn new(pattern: SparsityPattern) -> Self {
+        let coloring = pattern.color_columns();
+        Self {
+            pattern,
+            coloring,
+            cache: RefCell::new(None),
+        }
+    }
+
+    /// Detects the pattern of the Jacobian of `f` at `x`, see `SparsityPattern::detect`.
+    pub fn detect<F>(f: &F, x: &Array1<f64>) -> Result<Self, Error>
+    where
+        F: DifferentiableOp + ?Sized,
+    {
+        Ok(Self::new(SparsityPattern::detect(f, x)?))
+    }
+
+    pub fn pattern(&self) -> &SparsityPattern {
+        &self.pattern
+    }
+
+    pub fn coloring(&self) -> &Coloring {
+        &self.coloring
+    }
+
+    /// Drops the Jacobian kept by `jvp`.
+    pub fn reset(&self) {
+        self.cache.borrow_mut().take();
+    }
+
+    /// The Jacobian of `f` at `x`.
+    pub fn evaluate<F>(&self, f: &F, x: &Array1<f64>) -> Result<CsrMatrix, Error>
+    where
+        F: DifferentiableOp + ?Sized,
+    {
+        let pattern = &self.pattern;
+        if x.len() != pattern.ncols {
+            return Err(Error::InvalidVariable);
+        }
+        let colors = &self.coloring.colors;
+        let vars = Variables::<f64, 1>::new();
+        let mut values = vec![0.; pattern.nnz()];
+        for color in 0..self.coloring.count {
+            let seeded: Vec<_> = x
+                .iter()
+                .zip(colors.iter())
+                .map(|(&xi, &c)| {
+                    let mut d = vars.constant(xi);
+                    if c == color {
+                        d.grad_mut()[0] = 1.;
+                    }
+                    d
+                })
+                .collect();
+            let y = f.apply(&seeded)?;
+            if y.len() != pattern.nrows {
+                return Err(Error::InvalidVariable);
+            }
+            for (i, yi) in y.iter().enumerate() {
+                for k in pattern.indptr[i]..pattern.indptr[i + 1] {
+                    if colors[pattern.indices[k]] == color {
+                        values[k] = yi.grad()[0];
+                    }
+                }
+            }
+        }
+        Ok(CsrMatrix {
+            nrows: pattern.nrows,
+            ncols: pattern.ncols,
+            indptr: pattern.indptr.clone(),
+            indices: pattern.indices.clone(),
+            values,
+        })
+    }
+}
+
+impl<F> JacobianVector<Differentiable<F>> for SparseJacobian
+where
+    F: DifferentiableOp,
+{
+    fn jvp(
+        &self,
+        op: &Differentiable<F>,
+        x: &Array1<f64>,
+        _fx: &Array1<f64>,
+        v: &Array1<f64>,
+    ) -> Result<Array1<f64>, Error> {
+        let mut cache = self.cache.borrow_mut();
+        match cache.as_ref() {
+            Some((cx, _)) if cx == x => {}
+            _ => *cache = Some((x.clone(), self.evaluate(&op.0, x)?)),
+        }
+        Ok(cache.as_ref().unwrap().1.dot(v))
+    }
+
+    fn reset(&self) {
+        SparseJacobian::reset(self);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::self_consistent::*;
+
+    /// Bratu problem on an `m` x `m` grid with the five-point stencil, as the fixed point
+    /// `u_ij = (sum of the neighbours + h^2 lambda exp(u_ij)) / 4`.
+    #[derive(Clone, Copy)]
+    struct Bratu2d {
+        m: usize,
+        lambda: f64,
+    }
+
+    impl DifferentiableOp for Bratu2d {
+        fn apply<T: num_traits::Float>(&self, u: &[T]) -> Result<Vec<T>, Error> {
+            let m = self.m;
+            let h = 1. / (m + 1) as f64;
+            let c = T::from(h * h * self.lambda).unwrap();
+            let quarter = T::from(0.25).unwrap();
+            let at = |i: usize, j: usize| u[i * m + j];
+            Ok((0..m * m)
+                .map(|k| {
+                    let (i, j) = (k / m, k % m);
+                    let mut sum = c * u[k].exp();
+                    if i > 0 {
+                        sum = sum + at(i - 1, j);
+                    }
+                    if i + 1 < m {
+                        sum = sum + at(i + 1, j);
+                    }
+                    if j > 0 {
+                        sum = sum + at(i, j - 1);
+                    }
+                    if j + 1 < m {
+                        sum = sum + at(i, j + 1);
+                    }
+                    sum * quarter
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn five_point_stencil() -> anyhow::Result<()> {
+        let (m, n) = (8, 64);
+        let problem = Bratu2d { m, lambda: 5. };
+        let x = Array1::from_shape_fn(n, |k| 0.1 + 0.01 * k as f64);
+        let jacobian = SparseJacobian::detect(&problem, &x)?;
+
+        let pattern = jacobian.pattern();
+        assert_eq!(5 * n - 4 * m, pattern.nnz());
+        let coloring = jacobian.coloring();
+        assert!(coloring.count >= 5 && coloring.count <= 8);
+        for i in 0..n {
+            let row = &pattern.indices[pattern.indptr[i]..pattern.indptr[i + 1]];
+            for (a, &j) in row.iter().enumerate() {
+                for &k in &row[a + 1..] {
+                    assert_ne!(coloring.colors[j], coloring.colors[k]);
+                }
+            }
+        }
+
+        // against dense columns from single-direction passes
+        let j = jacobian.evaluate(&problem, &x)?;
+        let dense = j.to_dense();
+        for col in 0..n {
+            let mut v = Array1::zeros(n);
+            v[col] = 1.;
+            let column = solver::ForwardMode.jvp(&Differentiable(problem), &x, &x, &v)?;
+            assert_eq!(column, dense.column(col));
+        }
+        let coo = j.to_coo();
+        assert_eq!(j.nnz(), coo.values.len());
+        for ((&r, &c), &v) in coo.rows.iter().zip(coo.cols.iter()).zip(coo.values.iter()) {
+            assert_eq!(dense[[r, c]], v);
+        }
+
+        let x = Executor::new(solver::Jfnk::new(jacobian), Differentiable(problem))
+            .report(VectorReport::default())
+            .terminate(when(|r: &VectorReport| r.error < 1e-12 || r.count >= 20))
+            .run(Array1::zeros(n))?;
+        let r = Differentiable(problem).apply(&x)? - &x;
+        assert!(r.iter().all(|v| v.abs() < 1e-12));
+        Ok(())
+    }
+
+    #[test]
+    fn reset_between_operators() -> anyhow::Result<()> {
+        let (m, n) = (4, 16);
+        let (a, b) = (Bratu2d { m, lambda: 1. }, Bratu2d { m, lambda: 6. });
+        let x = Array1::from_shape_fn(n, |k| 0.1 * k as f64);
+        let v = Array1::from_shape_fn(n, |k| 1. - 0.05 * k as f64);
+        let jacobian = SparseJacobian::detect(&a, &x)?;
+        let ja = jacobian.jvp(&Differentiable(a), &x, &x, &v)?;
+        assert_eq!(ja, jacobian.evaluate(&a, &x)?.dot(&v));
+        jacobian.reset();
+        let jb = jacobian.jvp(&Differentiable(b), &x, &x, &v)?;
+        assert_eq!(jb, jacobian.evaluate(&b, &x)?.dot(&v));
+        assert_ne!(ja, jb);
+        Ok(())
+    }
+}
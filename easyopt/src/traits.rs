@@ -48,7 +48,7 @@ impl Float for f64 {
 
     #[inline]
     fn nan() -> Self {
-        std::f64::NAN
+        f64::NAN
     }
 
     #[inline]
@@ -57,6 +57,51 @@ impl Float for f64 {
     }
 }
 
+/// Variables made of scalar components of type `K`, which solvers can treat one by one.
+pub trait Elementwise<K>: Sized {
+    /// Applies `f` to the corresponding components of `self`, `a`, `b` and `c`.
+    fn zip_map4<F>(&self, a: &Self, b: &Self, c: &Self, f: F) -> Self
+    where
+        F: FnMut(K, K, K, K) -> K;
+}
+
+impl Elementwise<f32> for f32 {
+    #[inline]
+    fn zip_map4<F>(&self, a: &Self, b: &Self, c: &Self, mut f: F) -> Self
+    where
+        F: FnMut(f32, f32, f32, f32) -> f32,
+    {
+        f(*self, *a, *b, *c)
+    }
+}
+
+impl Elementwise<f64> for f64 {
+    #[inline]
+    fn zip_map4<F>(&self, a: &Self, b: &Self, c: &Self, mut f: F) -> Self
+    where
+        F: FnMut(f64, f64, f64, f64) -> f64,
+    {
+        f(*self, *a, *b, *c)
+    }
+}
+
+impl<K, D> Elementwise<K> for ndarray::Array<K, D>
+where
+    K: Copy,
+    D: ndarray::Dimension,
+{
+    fn zip_map4<F>(&self, a: &Self, b: &Self, c: &Self, mut f: F) -> Self
+    where
+        F: FnMut(K, K, K, K) -> K,
+    {
+        ndarray::Zip::from(self)
+            .and(a)
+            .and(b)
+            .and(c)
+            .map_collect(|&x, &a, &b, &c| f(x, a, b, c))
+    }
+}
+
 /*
 pub trait ScalarOperand<T>: BinaryOperand<T, T> + for<'a> BinaryOperand<&'a T, T>
 {
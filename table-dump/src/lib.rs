@@ -66,7 +66,7 @@ impl<W: io::Write> Table<W> {
                         write!(self.writer, "\t{}", val)?;
                     }
                 } else {
-                    return Err(Error::Failure(String::from("Data structure was changed")).into())
+                    return Err(Error::Failure(String::from("Data structure was changed")).into());
                 }
             }
         }
@@ -7,12 +7,12 @@ enum Key {
     Index(usize),
 }
 
-impl std::string::ToString for Key {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         use Key::*;
         match self {
-            Label(name) => name.clone(),
-            Index(i) => format!("[{}]", i),
+            Label(name) => write!(f, "{}", name),
+            Index(i) => write!(f, "[{}]", i),
         }
     }
 }
@@ -75,7 +75,7 @@ fn concat_name(keys: &[Key]) -> String {
     }
 }
 
-impl<'a> ser::Serializer for &'a mut Serializer {
+impl ser::Serializer for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -263,7 +263,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeSeq for &'a mut Serializer {
+impl ser::SerializeSeq for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -286,7 +286,7 @@ impl<'a> ser::SerializeSeq for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
+impl ser::SerializeTuple for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -309,7 +309,7 @@ impl<'a> ser::SerializeTuple for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
+impl ser::SerializeTupleStruct for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -332,7 +332,7 @@ impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+impl ser::SerializeTupleVariant for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -348,7 +348,7 @@ impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeMap for &'a mut Serializer {
+impl ser::SerializeMap for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -371,7 +371,7 @@ impl<'a> ser::SerializeMap for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeStruct for &'a mut Serializer {
+impl ser::SerializeStruct for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -390,7 +390,7 @@ impl<'a> ser::SerializeStruct for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
+impl ser::SerializeStructVariant for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -442,7 +442,7 @@ mod tests {
 
     #[test]
     fn test_f64_1() -> Result<()> {
-        let v: f64 = std::f64::NEG_INFINITY;
+        let v: f64 = f64::NEG_INFINITY;
         let mut ser = Serializer::new();
         v.serialize(&mut ser)?;
         assert_eq!(vec![""], ser.columns);
@@ -452,7 +452,7 @@ mod tests {
 
     #[test]
     fn test_f64_2() -> Result<()> {
-        let v: f64 = std::f64::INFINITY;
+        let v: f64 = f64::INFINITY;
         let mut ser = Serializer::new();
         v.serialize(&mut ser)?;
         assert_eq!(vec![""], ser.columns);
@@ -462,7 +462,7 @@ mod tests {
 
     #[test]
     fn test_f64_3() -> Result<()> {
-        let v: f64 = std::f64::NAN;
+        let v: f64 = f64::NAN;
         let mut ser = Serializer::new();
         v.serialize(&mut ser)?;
         assert_eq!(vec![""], ser.columns);